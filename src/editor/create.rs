@@ -1,27 +1,113 @@
 use bevy::prelude::*;
 use bevy_panorbit_camera::PanOrbitCamera;
+use dxf::entities::Entity as DxfEntity;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::editor::scene::SceneFile;
 use crate::editor::*;
+use crate::in_project::{
+    DxfDrawData, DxfLoadTask, DxfSource, EditHistory, LayerTable, LineWeightDisplay, SourceDrawing,
+    spawn_cad_node,
+};
+
+/// 打开场景消息
+#[derive(Message, Debug)]
+pub struct OpenSceneMessage {
+    pub path: PathBuf,
+}
+
 /// 新建空编辑器
 pub fn create_blank_editor(
-    commands: Commands,
-    meshes: ResMut<Assets<Mesh>>,
-    materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    create(commands, meshes, materials, None);
+    create(&mut commands, &mut meshes, &mut materials, None);
 }
 
-pub fn create(
+/// 打开场景系统 - 用 .ron 文件中的场景替换当前编辑器
+#[allow(clippy::too_many_arguments)]
+pub fn open_scene_system(
+    mut messages: MessageReader<OpenSceneMessage>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut draw_data: ResMut<DxfDrawData>,
+    mut load_task: ResMut<DxfLoadTask>,
+    mut history: ResMut<EditHistory>,
+    editor_query: Query<Entity, With<Editor>>,
+) {
+    // 只处理最后一条，连续打开多个文件时以最后一个为准
+    let Some(message) = messages.read().last() else {
+        return;
+    };
+    // 正在加载的 DXF 不再应用到新场景
+    load_task.cancel();
+    draw_data.clear();
+    history.clear();
+    for editor in &editor_query {
+        commands.entity(editor).despawn();
+    }
+    create(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        Some(message.path.clone()),
+    );
+}
+
+pub fn create(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
     ron_file: Option<PathBuf>,
 ) {
+    // 先读取场景文件，失败时退回到空编辑器
+    let scene = ron_file
+        .as_ref()
+        .and_then(|path| match SceneFile::load(path) {
+            Ok(scene) => Some(scene),
+            Err(e) => {
+                eprintln!("打开场景失败 {:?}: {}", path, e);
+                None
+            }
+        });
+    let camera = scene
+        .as_ref()
+        .map(|scene| scene.camera.clone())
+        .unwrap_or_default();
+    // 源 DXF 在导出时按需重新读取
+    let mut source = SourceDrawing {
+        path: scene.as_ref().and_then(|scene| scene.source_dxf.clone()),
+        bytes: None,
+        imported: scene
+            .as_ref()
+            .map(|scene| scene.source_imported.iter().copied().collect())
+            .unwrap_or_default(),
+    };
+    commands.insert_resource(LayerTable::new(
+        scene
+            .as_ref()
+            .map(|scene| scene.layers.clone())
+            .unwrap_or_default(),
+    ));
+    commands.insert_resource(
+        scene
+            .as_ref()
+            .map(|scene| scene.line_types.clone())
+            .unwrap_or_default(),
+    );
+    commands.insert_resource(LineWeightDisplay {
+        enabled: scene
+            .as_ref()
+            .is_some_and(|scene| scene.line_weight_display),
+    });
+
     let editor_scene = commands
         .spawn((
             Editor {
-                path: ron_file.clone(),
+                path: scene.as_ref().and(ron_file),
                 is_dirty: false,
             },
             Transform::default(),
@@ -35,6 +121,10 @@ pub fn create(
                 EditorPart, // 标记为编辑器的零件
                 Transform::from_translation(Vec3::new(0.0, 1.5, 5.0)),
                 PanOrbitCamera {
+                    focus: Vec3::from_array(camera.focus),
+                    yaw: Some(camera.yaw),
+                    pitch: Some(camera.pitch),
+                    radius: Some(camera.radius),
                     button_orbit: MouseButton::Middle,
                     button_pan: MouseButton::Middle,
                     modifier_orbit: Some(KeyCode::ShiftLeft),
@@ -53,7 +143,33 @@ pub fn create(
         })
         .id();
 
-    if ron_file.is_none() {
+    if let Some(scene) = scene {
+        // 有源实体的场景实体按句柄重新关联源 DXF 中的实体（没有句柄的源实体无法关联）
+        let drawing = scene
+            .entities
+            .iter()
+            .any(|scene_entity| scene_entity.source_handle.is_some())
+            .then(|| source.drawing())
+            .flatten();
+        let source_entities: HashMap<u64, &DxfEntity> = drawing
+            .iter()
+            .flat_map(|drawing| drawing.entities())
+            .filter(|ent| !ent.common.handle.is_empty())
+            .map(|ent| (ent.common.handle.0, ent))
+            .collect();
+
+        // 从 ron 文件加载场景
+        for scene_entity in &scene.entities {
+            let entity = spawn_cad_node(commands, editor_scene, scene_entity.to_node());
+            if let Some(ent) = scene_entity
+                .source_handle
+                .and_then(|handle| source_entities.get(&handle))
+            {
+                commands.entity(entity).insert(DxfSource((*ent).clone()));
+            }
+        }
+        println!("场景加载完成, 实体: {}", scene.entities.len());
+    } else {
         // 立方体
         let cube = commands
             .spawn((
@@ -94,8 +210,5 @@ pub fn create(
             .entity(editor_scene)
             .add_children(&[cube, sphere, ground]);
     }
-    else{
-        // 后续从 ron 文件加载场景
-        todo!();
-    }
+    commands.insert_resource(source);
 }
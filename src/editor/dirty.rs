@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::editor::Editor;
+use crate::in_project::{
+    ArcEntity, CadEntity, CircleEntity, DimensionEntity, EllipseEntity, HatchEntity, InsertEntity,
+    LineEntity, PolylineEntity, SplineEntity, TextEntity,
+};
+
+/// 脏标记系统 - CAD 实体被修改时标记编辑器有未保存的更改
+///
+/// 新生成的实体不算修改：打开场景不应变脏，导入或新建实体时由调用方自行标记。
+/// 检查的组件和捕捉缓存系统相同，任何一种几何数据的修改都会标记。
+#[allow(clippy::type_complexity)]
+pub fn mark_dirty_system(
+    mut editor_query: Query<&mut Editor>,
+    changed_query: Query<
+        Ref<CadEntity>,
+        Or<(
+            Changed<CadEntity>,
+            Changed<LineEntity>,
+            Changed<CircleEntity>,
+            Changed<ArcEntity>,
+            Changed<PolylineEntity>,
+            Changed<TextEntity>,
+            Changed<InsertEntity>,
+            Changed<DimensionEntity>,
+            Changed<SplineEntity>,
+            Changed<EllipseEntity>,
+            Changed<HatchEntity>,
+        )>,
+    >,
+) {
+    // 过滤器已经只留下有修改的实体，只需排除新生成的
+    let modified = changed_query.iter().any(|cad| !cad.is_added());
+    if !modified {
+        return;
+    }
+    for mut editor in &mut editor_query {
+        if !editor.is_dirty {
+            editor.is_dirty = true;
+        }
+    }
+}
@@ -1,13 +1,41 @@
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
-use crate::{editor::Editor, in_project::DxfDrawData};
+use crate::{
+    editor::Editor,
+    in_project::{
+        DrawState, DxfDrawData, DxfLoadTask, EditHistory, LayerTable, SnapSettings, SourceDrawing,
+        ViewMode,
+    },
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn dispose_system(
     mut commands: Commands,
     editor_query: Query<Entity, With<Editor>>,
     mut draw_data: ResMut<DxfDrawData>,
+    mut source: ResMut<SourceDrawing>,
+    mut load_task: ResMut<DxfLoadTask>,
+    mut history: ResMut<EditHistory>,
+    mut draw: ResMut<DrawState>,
+    mut view_mode: ResMut<ViewMode>,
+    mut snap: ResMut<SnapSettings>,
+    mut layers: ResMut<LayerTable>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
 ) {
     draw_data.clear();
+    source.clear();
+    load_task.cancel();
+    history.clear();
+    draw.cancel();
+    // 视图模式、捕捉设置和图层状态不带到下一个项目
+    *view_mode = ViewMode::default();
+    *snap = SnapSettings::default();
+    *layers = LayerTable::default();
+    // 恢复窗口标题
+    for mut window in &mut window_query {
+        window.title = "开源Cad".into();
+    }
     for editor in &editor_query {
         commands.entity(editor).despawn();
     }
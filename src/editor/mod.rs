@@ -1,7 +1,15 @@
 pub mod create;
 use bevy::ecs::component::Component;
 use std::path::PathBuf;
-pub use create::create_blank_editor;
+pub use create::{OpenSceneMessage, create_blank_editor, open_scene_system};
+
+pub mod scene;
+
+pub mod save;
+pub use save::{SaveDialog, SaveSceneMessage, save_dialog_system, save_scene_system};
+
+pub mod dirty;
+pub use dirty::mark_dirty_system;
 
 pub mod dispose;
 pub use dispose::dispose_system;
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::editor::Editor;
+use crate::editor::scene::{SCENE_VERSION, SceneCamera, SceneEntity, SceneFile};
+use crate::in_project::{
+    CadEntity, CadGeometryQuery, DxfSource, LayerTable, LineTypeTable, LineWeightDisplay,
+    SourceDrawing,
+};
+
+static SAVE_DIALOG_RESULT: Mutex<Option<Option<PathBuf>>> = Mutex::new(None);
+
+/// 保存场景消息
+#[derive(Message, Debug)]
+pub struct SaveSceneMessage {
+    pub path: PathBuf,
+}
+
+/// 另存为对话框状态
+#[derive(Resource, Default)]
+pub struct SaveDialog {
+    is_open: bool,
+    /// 上一次对话框被取消
+    cancelled: bool,
+}
+
+impl SaveDialog {
+    /// 打开另存为对话框（已打开时忽略）
+    pub fn open(&mut self, directory: PathBuf) {
+        if self.is_open {
+            return;
+        }
+        self.is_open = true;
+        self.cancelled = false;
+        open_save_dialog_in_thread(directory);
+    }
+
+    /// 取出并清除“已取消”标记
+    pub fn take_cancelled(&mut self) -> bool {
+        std::mem::take(&mut self.cancelled)
+    }
+}
+
+/// 另存为对话框系统 - 检查对话框结果并发出保存消息
+pub fn save_dialog_system(
+    mut dialog: ResMut<SaveDialog>,
+    mut save_messages: MessageWriter<SaveSceneMessage>,
+) {
+    if !dialog.is_open {
+        return;
+    }
+    let Ok(mut guard) = SAVE_DIALOG_RESULT.lock() else {
+        return;
+    };
+    let Some(result) = guard.take() else {
+        return;
+    };
+    dialog.is_open = false;
+    match result {
+        Some(mut path) => {
+            if path.extension().is_none() {
+                path.set_extension("ron");
+            }
+            save_messages.write(SaveSceneMessage { path });
+        }
+        None => dialog.cancelled = true,
+    }
+}
+
+/// 保存场景系统 - 把编辑器的子实体序列化为 .ron
+#[allow(clippy::too_many_arguments)]
+pub fn save_scene_system(
+    mut messages: MessageReader<SaveSceneMessage>,
+    mut editor_query: Query<(&mut Editor, &Children)>,
+    cad_query: Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+    source_query: Query<&DxfSource>,
+    camera_query: Query<&PanOrbitCamera>,
+    source: Res<SourceDrawing>,
+    layers: Res<LayerTable>,
+    line_types: Res<LineTypeTable>,
+    line_weight_display: Res<LineWeightDisplay>,
+) {
+    for message in messages.read() {
+        let Some((mut editor, children)) = editor_query.iter_mut().next() else {
+            eprintln!("未找到 Editor 实体");
+            continue;
+        };
+
+        let mut entities = Vec::new();
+        let mut camera = SceneCamera::default();
+        for &child in children {
+            if cad_query.contains(child) {
+                if let Some(mut scene) = scene_entity(child, &cad_query) {
+                    // 只有顶层实体有源实体，块参照的部件由块定义生成
+                    scene.source_handle = source_query.get(child).ok().map(|s| s.0.common.handle.0);
+                    entities.push(scene);
+                }
+            } else if let Ok(orbit) = camera_query.get(child) {
+                camera = SceneCamera {
+                    focus: orbit.focus.to_array(),
+                    yaw: orbit.yaw.unwrap_or(orbit.target_yaw),
+                    pitch: orbit.pitch.unwrap_or(orbit.target_pitch),
+                    radius: orbit.radius.unwrap_or(orbit.target_radius),
+                };
+            }
+        }
+
+        let scene = SceneFile {
+            version: SCENE_VERSION,
+            camera,
+            source_dxf: source.path.clone(),
+            source_imported: source.imported.iter().copied().collect(),
+            layers: layers.layers.clone(),
+            line_types: line_types.clone(),
+            line_weight_display: line_weight_display.enabled,
+            entities,
+        };
+        match scene.save(&message.path) {
+            Ok(()) => {
+                println!(
+                    "场景已保存: {:?}, 实体: {}",
+                    message.path,
+                    scene.entities.len()
+                );
+                editor.path = Some(message.path.clone());
+                editor.is_dirty = false;
+            }
+            Err(e) => eprintln!("保存场景失败 {:?}: {}", message.path, e),
+        }
+    }
+}
+
+/// CAD 实体及其子实体（块参照的部件）转换为场景实体
+fn scene_entity(
+    entity: Entity,
+    cad_query: &Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+) -> Option<SceneEntity> {
+    let (cad, components, children) = cad_query.get(entity).ok()?;
+    let mut scene = SceneEntity::from_cad(cad, &components.geometry()?);
+    if let Some(children) = children {
+        scene.children = children
+            .iter()
+            .filter_map(|child| scene_entity(child, cad_query))
+            .collect();
+    }
+    Some(scene)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_save_dialog_in_thread(directory: PathBuf) {
+    thread::spawn(move || {
+        let result = rfd::FileDialog::new()
+            .set_title("保存场景")
+            .set_directory(&directory)
+            .add_filter("场景文件", &["ron"])
+            .set_file_name("未命名.ron")
+            .save_file();
+
+        if let Ok(mut dialog_result) = SAVE_DIALOG_RESULT.lock() {
+            *dialog_result = Some(result);
+        }
+    });
+}
+
+/// 浏览器中没有文件对话框，按取消处理，避免另存为和退出前保存时崩溃
+#[cfg(target_arch = "wasm32")]
+fn open_save_dialog_in_thread(_directory: PathBuf) {
+    eprintln!("保存场景失败: 浏览器中暂不支持保存场景");
+    if let Ok(mut dialog_result) = SAVE_DIALOG_RESULT.lock() {
+        *dialog_result = Some(None);
+    }
+}
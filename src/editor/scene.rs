@@ -0,0 +1,486 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::in_project::{
+    ArcEntity, CadColor, CadEntity, CadGeometry, CadNode, CircleEntity, DimensionEntity,
+    DimensionKind, EllipseEntity, HatchEntity, HatchLine, InsertEntity, LayerState, LineEntity,
+    LineTypeTable, PolylineEntity, SplineEntity, TextEntity, TextHAlign, TextVAlign,
+};
+
+/// 场景文件格式版本
+pub const SCENE_VERSION: u32 = 5;
+
+/// 编辑器场景文件（.ron）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneFile {
+    pub version: u32,
+    pub camera: SceneCamera,
+    /// 导入来源的 DXF 文件，导出 DXF 时用于保留表头、图层等
+    #[serde(default)]
+    pub source_dxf: Option<PathBuf>,
+    /// 导入为编辑器实体的源实体句柄（包括之后删除的），导出时这些源实体不再原样写出
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_imported: Vec<u64>,
+    /// 图层的显示状态（开关、冻结、锁定、颜色）
+    #[serde(default)]
+    pub layers: Vec<LayerState>,
+    /// 线型表和线型比例
+    #[serde(default)]
+    pub line_types: LineTypeTable,
+    /// 是否显示线宽（LWT）
+    #[serde(default)]
+    pub line_weight_display: bool,
+    pub entities: Vec<SceneEntity>,
+}
+
+/// 相机位姿
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneCamera {
+    pub focus: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub radius: f32,
+}
+
+impl Default for SceneCamera {
+    fn default() -> Self {
+        // 与新建编辑器时相机位于 (0, 1.5, 5) 看向原点一致
+        Self {
+            focus: [0.0; 3],
+            yaw: 0.0,
+            pitch: (1.5f32 / 5.0).atan(),
+            radius: Vec3::new(0.0, 1.5, 5.0).length(),
+        }
+    }
+}
+
+/// 场景中的一个 CAD 实体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneEntity {
+    pub layer: String,
+    /// sRGBA
+    pub color: [f32; 4],
+    /// 颜色设置，color 是按它解析后的颜色
+    #[serde(default)]
+    pub color_spec: CadColor,
+    /// 线型名，为空表示实线
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub line_type: String,
+    #[serde(default = "default_line_type_scale")]
+    pub line_type_scale: f32,
+    /// 线宽（毫米）
+    #[serde(default)]
+    pub line_weight: f32,
+    pub geometry: SceneGeometry,
+    /// 源 DXF 中对应实体的句柄，打开场景时据此重新关联源实体，导出时在其基础上修改
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_handle: Option<u64>,
+    /// 子实体（块参照中的几何数据）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SceneEntity>,
+}
+
+/// CAD 实体的几何数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SceneGeometry {
+    Line {
+        start: [f32; 3],
+        end: [f32; 3],
+    },
+    Circle {
+        center: [f32; 3],
+        radius: f32,
+    },
+    Arc {
+        center: [f32; 3],
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+    },
+    Polyline {
+        vertices: Vec<[f32; 3]>,
+        closed: bool,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        bulges: Vec<f32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        widths: Vec<[f32; 2]>,
+    },
+    Text {
+        position: [f32; 3],
+        height: f32,
+        rotation: f32,
+        h_align: TextHAlign,
+        v_align: TextVAlign,
+        content: String,
+        is_mtext: bool,
+        wrap_width: Option<f32>,
+        fit_width: Option<f32>,
+    },
+    Insert {
+        block: String,
+        position: [f32; 3],
+        scale: [f32; 3],
+        rotation: f32,
+        columns: u16,
+        rows: u16,
+        column_spacing: f32,
+        row_spacing: f32,
+    },
+    Dimension {
+        kind: DimensionKind,
+        dimension_point: [f32; 3],
+        points: Vec<[f32; 3]>,
+        text_position: [f32; 3],
+        rotation: f32,
+        measurement: f32,
+        text: String,
+        style: String,
+        block: String,
+    },
+    Spline {
+        degree: usize,
+        control_points: Vec<[f32; 3]>,
+        knots: Vec<f32>,
+        weights: Vec<f32>,
+        fit_points: Vec<[f32; 3]>,
+        closed: bool,
+    },
+    Ellipse {
+        center: [f32; 3],
+        major_axis: [f32; 3],
+        minor_axis: [f32; 3],
+        start_param: f32,
+        end_param: f32,
+    },
+    Hatch {
+        pattern: String,
+        solid: bool,
+        loops: Vec<Vec<[f32; 3]>>,
+        lines: Vec<SceneHatchLine>,
+    },
+}
+
+/// 填充图案中的一族平行线
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneHatchLine {
+    pub base: [f32; 3],
+    pub direction: [f32; 3],
+    pub offset: [f32; 3],
+    pub dashes: Vec<f32>,
+}
+
+impl SceneFile {
+    /// 从 .ron 文件读取场景
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("读取场景文件失败: {}", e))?;
+        let scene: SceneFile =
+            ron::from_str(&content).map_err(|e| format!("解析场景文件失败: {}", e))?;
+        if scene.version > SCENE_VERSION {
+            return Err(format!(
+                "场景文件版本过高: {}（当前支持 {}）",
+                scene.version, SCENE_VERSION
+            ));
+        }
+        Ok(scene)
+    }
+
+    /// 写入 .ron 文件
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| format!("序列化场景失败: {}", e))?;
+        std::fs::write(path, content).map_err(|e| format!("写入场景文件失败: {}", e))
+    }
+}
+
+fn default_line_type_scale() -> f32 {
+    1.0
+}
+
+impl SceneEntity {
+    /// 从 CAD 实体生成
+    pub fn from_cad(cad: &CadEntity, geometry: &CadGeometry) -> Self {
+        let geometry = match geometry {
+            CadGeometry::Line(line) => SceneGeometry::Line {
+                start: line.start.to_array(),
+                end: line.end.to_array(),
+            },
+            CadGeometry::Circle(circle) => SceneGeometry::Circle {
+                center: circle.center.to_array(),
+                radius: circle.radius,
+            },
+            CadGeometry::Arc(arc) => SceneGeometry::Arc {
+                center: arc.center.to_array(),
+                radius: arc.radius,
+                start_angle: arc.start_angle,
+                end_angle: arc.end_angle,
+            },
+            CadGeometry::Polyline(pl) => SceneGeometry::Polyline {
+                vertices: pl.vertices.iter().map(|v| v.to_array()).collect(),
+                closed: pl.closed,
+                bulges: pl.bulges.clone(),
+                widths: pl.widths.clone(),
+            },
+            CadGeometry::Text(text) => SceneGeometry::Text {
+                position: text.position.to_array(),
+                height: text.height,
+                rotation: text.rotation,
+                h_align: text.h_align,
+                v_align: text.v_align,
+                content: text.content.clone(),
+                is_mtext: text.is_mtext,
+                wrap_width: text.wrap_width,
+                fit_width: text.fit_width,
+            },
+            CadGeometry::Insert(insert) => SceneGeometry::Insert {
+                block: insert.block.clone(),
+                position: insert.position.to_array(),
+                scale: insert.scale.to_array(),
+                rotation: insert.rotation,
+                columns: insert.columns,
+                rows: insert.rows,
+                column_spacing: insert.column_spacing,
+                row_spacing: insert.row_spacing,
+            },
+            CadGeometry::Dimension(dimension) => SceneGeometry::Dimension {
+                kind: dimension.kind,
+                dimension_point: dimension.dimension_point.to_array(),
+                points: dimension.points.iter().map(|v| v.to_array()).collect(),
+                text_position: dimension.text_position.to_array(),
+                rotation: dimension.rotation,
+                measurement: dimension.measurement,
+                text: dimension.text.clone(),
+                style: dimension.style.clone(),
+                block: dimension.block.clone(),
+            },
+            CadGeometry::Spline(spline) => SceneGeometry::Spline {
+                degree: spline.degree,
+                control_points: spline.control_points.iter().map(|v| v.to_array()).collect(),
+                knots: spline.knots.clone(),
+                weights: spline.weights.clone(),
+                fit_points: spline.fit_points.iter().map(|v| v.to_array()).collect(),
+                closed: spline.closed,
+            },
+            CadGeometry::Ellipse(ellipse) => SceneGeometry::Ellipse {
+                center: ellipse.center.to_array(),
+                major_axis: ellipse.major_axis.to_array(),
+                minor_axis: ellipse.minor_axis.to_array(),
+                start_param: ellipse.start_param,
+                end_param: ellipse.end_param,
+            },
+            CadGeometry::Hatch(hatch) => SceneGeometry::Hatch {
+                pattern: hatch.pattern.clone(),
+                solid: hatch.solid,
+                loops: hatch
+                    .loops
+                    .iter()
+                    .map(|points| points.iter().map(|v| v.to_array()).collect())
+                    .collect(),
+                lines: hatch
+                    .lines
+                    .iter()
+                    .map(|line| SceneHatchLine {
+                        base: line.base.to_array(),
+                        direction: line.direction.to_array(),
+                        offset: line.offset.to_array(),
+                        dashes: line.dashes.clone(),
+                    })
+                    .collect(),
+            },
+        };
+        Self {
+            layer: cad.layer.clone(),
+            color: cad.color.to_srgba().to_f32_array(),
+            color_spec: cad.color_spec,
+            line_type: cad.line_type.clone(),
+            line_type_scale: cad.line_type_scale,
+            line_weight: cad.line_weight,
+            geometry,
+            source_handle: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// 转换为可生成的 CAD 实体
+    pub fn to_cad(&self) -> (CadEntity, CadGeometry) {
+        let geometry = match &self.geometry {
+            SceneGeometry::Line { start, end } => CadGeometry::Line(LineEntity {
+                start: Vec3::from_array(*start),
+                end: Vec3::from_array(*end),
+            }),
+            SceneGeometry::Circle { center, radius } => CadGeometry::Circle(CircleEntity {
+                center: Vec3::from_array(*center),
+                radius: *radius,
+            }),
+            SceneGeometry::Arc {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            } => CadGeometry::Arc(ArcEntity {
+                center: Vec3::from_array(*center),
+                radius: *radius,
+                start_angle: *start_angle,
+                end_angle: *end_angle,
+            }),
+            SceneGeometry::Polyline {
+                vertices,
+                closed,
+                bulges,
+                widths,
+            } => CadGeometry::Polyline(PolylineEntity {
+                vertices: vertices.iter().map(|v| Vec3::from_array(*v)).collect(),
+                closed: *closed,
+                bulges: bulges.clone(),
+                widths: widths.clone(),
+            }),
+            SceneGeometry::Text {
+                position,
+                height,
+                rotation,
+                h_align,
+                v_align,
+                content,
+                is_mtext,
+                wrap_width,
+                fit_width,
+            } => CadGeometry::Text(TextEntity {
+                position: Vec3::from_array(*position),
+                height: *height,
+                rotation: *rotation,
+                h_align: *h_align,
+                v_align: *v_align,
+                content: content.clone(),
+                is_mtext: *is_mtext,
+                wrap_width: *wrap_width,
+                fit_width: *fit_width,
+            }),
+            SceneGeometry::Insert {
+                block,
+                position,
+                scale,
+                rotation,
+                columns,
+                rows,
+                column_spacing,
+                row_spacing,
+            } => CadGeometry::Insert(InsertEntity {
+                block: block.clone(),
+                position: Vec3::from_array(*position),
+                scale: Vec3::from_array(*scale),
+                rotation: *rotation,
+                columns: *columns,
+                rows: *rows,
+                column_spacing: *column_spacing,
+                row_spacing: *row_spacing,
+            }),
+            SceneGeometry::Dimension {
+                kind,
+                dimension_point,
+                points,
+                text_position,
+                rotation,
+                measurement,
+                text,
+                style,
+                block,
+            } => CadGeometry::Dimension(DimensionEntity {
+                kind: *kind,
+                dimension_point: Vec3::from_array(*dimension_point),
+                points: points.iter().map(|v| Vec3::from_array(*v)).collect(),
+                text_position: Vec3::from_array(*text_position),
+                rotation: *rotation,
+                measurement: *measurement,
+                text: text.clone(),
+                style: style.clone(),
+                block: block.clone(),
+            }),
+            SceneGeometry::Spline {
+                degree,
+                control_points,
+                knots,
+                weights,
+                fit_points,
+                closed,
+            } => CadGeometry::Spline(SplineEntity {
+                degree: *degree,
+                control_points: control_points
+                    .iter()
+                    .map(|v| Vec3::from_array(*v))
+                    .collect(),
+                knots: knots.clone(),
+                weights: weights.clone(),
+                fit_points: fit_points.iter().map(|v| Vec3::from_array(*v)).collect(),
+                closed: *closed,
+            }),
+            SceneGeometry::Ellipse {
+                center,
+                major_axis,
+                minor_axis,
+                start_param,
+                end_param,
+            } => CadGeometry::Ellipse(EllipseEntity {
+                center: Vec3::from_array(*center),
+                major_axis: Vec3::from_array(*major_axis),
+                minor_axis: Vec3::from_array(*minor_axis),
+                start_param: *start_param,
+                end_param: *end_param,
+            }),
+            SceneGeometry::Hatch {
+                pattern,
+                solid,
+                loops,
+                lines,
+            } => CadGeometry::Hatch(HatchEntity {
+                pattern: pattern.clone(),
+                solid: *solid,
+                loops: loops
+                    .iter()
+                    .map(|points| points.iter().map(|v| Vec3::from_array(*v)).collect())
+                    .collect(),
+                lines: lines
+                    .iter()
+                    .map(|line| HatchLine {
+                        base: Vec3::from_array(line.base),
+                        direction: Vec3::from_array(line.direction),
+                        offset: Vec3::from_array(line.offset),
+                        dashes: line.dashes.clone(),
+                    })
+                    .collect(),
+                // 场景文件不保存源实体，和其他实体一样导出为新实体
+                handle: None,
+            }),
+        };
+        let [r, g, b, a] = self.color;
+        let cad = CadEntity {
+            entity_type: geometry.entity_type(),
+            layer: self.layer.clone(),
+            color: Color::srgba(r, g, b, a),
+            color_spec: self.color_spec,
+            line_type: self.line_type.clone(),
+            line_type_scale: self.line_type_scale,
+            line_weight: self.line_weight,
+            selectable: true,
+        };
+        (cad, geometry)
+    }
+
+    /// 转换为可生成的 CAD 实体树，子实体不可单独选择
+    pub fn to_node(&self) -> CadNode {
+        let (cad, geometry) = self.to_cad();
+        CadNode {
+            cad,
+            geometry,
+            children: self
+                .children
+                .iter()
+                .map(|child| {
+                    let mut node = child.to_node();
+                    node.cad.selectable = false;
+                    node
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,695 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui::{Color32, ComboBox, Key, TextEdit, TopBottomPanel};
+use std::f32::consts::TAU;
+
+use super::dxf_block::{CadNode, spawn_cad_node};
+use super::dxf_linetype::LineTypeTable;
+use super::dxf_renderer::{
+    ArcEntity, CadEntity, CadGeometry, CircleEntity, LineEntity, PolylineEntity, arc_points,
+    dxf_to_world,
+};
+use super::dxf_tessellation::CurveTolerance;
+use super::history::{Change, EditHistory};
+use super::layer_panel::LayerTable;
+use super::zoom::ZoomWindow;
+use crate::editor::Editor;
+
+/// 预览图形的颜色
+const PREVIEW_COLOR: Color = Color::srgb(0.4, 0.8, 1.0);
+/// 连接已输入的点和光标的辅助线的颜色
+const GUIDE_COLOR: Color = Color::srgba(0.6, 0.6, 0.6, 0.6);
+
+/// 绘图工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawTool {
+    /// 连续的直线，每两点生成一条线段
+    Line,
+    Polyline,
+    /// 两个对角点，生成闭合多段线
+    Rectangle,
+    /// 圆心、圆上一点（或半径）
+    CircleCenterRadius,
+    /// 直径的两个端点
+    Circle2Points,
+    /// 圆上三点
+    Circle3Points,
+    /// 起点、圆弧上一点、终点
+    Arc3Points,
+    /// 起点、圆心、终点，从起点逆时针画到终点方向
+    ArcStartCenterEnd,
+}
+
+impl DrawTool {
+    /// 工具的名称，也用作编辑历史中的名称
+    fn name(self) -> &'static str {
+        match self {
+            DrawTool::Line => "直线",
+            DrawTool::Polyline => "多段线",
+            DrawTool::Rectangle => "矩形",
+            DrawTool::CircleCenterRadius | DrawTool::Circle2Points | DrawTool::Circle3Points => {
+                "圆"
+            }
+            DrawTool::Arc3Points | DrawTool::ArcStartCenterEnd => "圆弧",
+        }
+    }
+
+    /// 圆和圆弧的画法
+    fn method(self) -> &'static str {
+        match self {
+            DrawTool::CircleCenterRadius => "圆心、半径",
+            DrawTool::Circle2Points => "两点",
+            DrawTool::Circle3Points | DrawTool::Arc3Points => "三点",
+            DrawTool::ArcStartCenterEnd => "起点、圆心、终点",
+            _ => "",
+        }
+    }
+
+    /// 生成一个实体需要的点数，多段线不限
+    fn point_count(self) -> Option<usize> {
+        match self {
+            DrawTool::Polyline => None,
+            DrawTool::Line
+            | DrawTool::Rectangle
+            | DrawTool::CircleCenterRadius
+            | DrawTool::Circle2Points => Some(2),
+            DrawTool::Circle3Points | DrawTool::Arc3Points | DrawTool::ArcStartCenterEnd => Some(3),
+        }
+    }
+
+    /// 已输入 count 个点时的提示
+    fn prompt(self, count: usize) -> &'static str {
+        match (self, count) {
+            (DrawTool::Line, 0) => "指定第一个点",
+            (DrawTool::Line, _) => "指定下一点，回车结束",
+            (DrawTool::Polyline, 0) => "指定起点",
+            (DrawTool::Polyline, 1) => "指定下一点，U 放弃",
+            (DrawTool::Polyline, _) => "指定下一点，C 闭合，U 放弃，回车结束",
+            (DrawTool::Rectangle, 0) => "指定第一个角点",
+            (DrawTool::Rectangle, _) => "指定另一个角点",
+            (DrawTool::CircleCenterRadius, 0) => "指定圆心",
+            (DrawTool::CircleCenterRadius, _) => "指定圆上一点或输入半径",
+            (DrawTool::Circle2Points, 0) => "指定直径的第一个端点",
+            (DrawTool::Circle2Points, _) => "指定直径的第二个端点",
+            (DrawTool::Circle3Points, 0) => "指定圆上的第一个点",
+            (DrawTool::Circle3Points, 1) => "指定圆上的第二个点",
+            (DrawTool::Circle3Points, _) => "指定圆上的第三个点",
+            (DrawTool::Arc3Points, 0) => "指定圆弧的起点",
+            (DrawTool::Arc3Points, 1) => "指定圆弧上的第二个点",
+            (DrawTool::Arc3Points, _) => "指定圆弧的终点",
+            (DrawTool::ArcStartCenterEnd, 0) => "指定圆弧的起点",
+            (DrawTool::ArcStartCenterEnd, 1) => "指定圆弧的圆心",
+            (DrawTool::ArcStartCenterEnd, _) => "指定圆弧的终点（逆时针）",
+        }
+    }
+}
+
+/// 命令行和鼠标的输入，由绘图系统处理
+#[derive(Debug, Clone, Copy)]
+enum DrawInput {
+    Point(Vec3),
+    /// 闭合多段线
+    Close,
+    /// 放弃上一个点
+    Undo,
+    /// 结束当前图形，没有输入点时退出工具
+    Finish,
+}
+
+/// 绘图状态
+///
+/// 点都在世界坐标中。光标投到过第一个点（没有点时为 DXF 的 Z=0 平面）的水平面上。
+#[derive(Resource, Debug)]
+pub struct DrawState {
+    /// 当前工具，None 表示没有在绘图
+    pub tool: Option<DrawTool>,
+    /// 已输入的点
+    pub points: Vec<Vec3>,
+    /// 光标所在的点，对象捕捉时为捕捉到的点
+    pub cursor: Option<Vec3>,
+    /// 上一个输入的点，相对坐标以它为基点
+    pub last_point: Vec3,
+    /// 新实体所在的图层
+    pub layer: String,
+    /// 命令行中的文字
+    pub input: String,
+    /// 上一次输入的错误，显示在命令行的提示旁，下一次输入时清除
+    error: Option<String>,
+    pending: Vec<DrawInput>,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        Self {
+            tool: None,
+            points: Vec::new(),
+            cursor: None,
+            last_point: Vec3::ZERO,
+            layer: "0".to_string(),
+            input: String::new(),
+            error: None,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl DrawState {
+    pub fn is_active(&self) -> bool {
+        self.tool.is_some()
+    }
+
+    pub fn start(&mut self, tool: DrawTool) {
+        self.tool = Some(tool);
+        self.points.clear();
+        self.input.clear();
+        self.error = None;
+        self.pending.clear();
+    }
+
+    pub fn cancel(&mut self) {
+        self.tool = None;
+        self.points.clear();
+        self.input.clear();
+        self.error = None;
+        self.pending.clear();
+    }
+
+    /// 相对坐标和直接输入距离的基点
+    fn base(&self) -> Vec3 {
+        self.points.last().copied().unwrap_or(self.last_point)
+    }
+
+    /// 处理命令行输入：坐标、C 闭合、U 放弃，空行结束
+    fn submit(&mut self, text: &str) {
+        let text = text.trim();
+        self.error = None;
+        let input = match text.to_uppercase().as_str() {
+            "" => DrawInput::Finish,
+            "C" => DrawInput::Close,
+            "U" => DrawInput::Undo,
+            _ => match parse_point(text, self.base(), self.cursor) {
+                Some(point) => DrawInput::Point(point),
+                None => {
+                    self.error = Some(format!("无法识别的输入: {}", text));
+                    return;
+                }
+            },
+        };
+        self.pending.push(input);
+    }
+}
+
+/// 解析命令行输入的点，坐标按 DXF 坐标系输入
+///
+/// - `x,y` 或 `x,y,z`：绝对坐标
+/// - `@dx,dy`：相对基点的坐标
+/// - `d<a` / `@d<a`：极坐标，角度为度，从 X 轴逆时针量取
+/// - 单个数字：从基点朝光标方向的距离
+fn parse_point(text: &str, base: Vec3, cursor: Option<Vec3>) -> Option<Vec3> {
+    let (relative, text) = match text.strip_prefix('@') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let origin = if relative { base } else { Vec3::ZERO };
+    if let Some((distance, angle)) = text.split_once('<') {
+        let distance: f64 = distance.trim().parse().ok()?;
+        let angle = angle.trim().parse::<f64>().ok()?.to_radians();
+        return Some(origin + dxf_to_world(distance * angle.cos(), distance * angle.sin(), 0.0));
+    }
+    let values: Vec<f64> = text
+        .split(',')
+        .map(|value| value.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match values[..] {
+        [distance] if !relative => {
+            let direction = cursor
+                .map(|cursor| cursor - base)
+                .and_then(|d| Vec3::new(d.x, 0.0, d.z).try_normalize())
+                .unwrap_or(Vec3::X);
+            Some(base + direction * distance as f32)
+        }
+        [x, y] => Some(origin + dxf_to_world(x, y, 0.0)),
+        [x, y, z] => Some(origin + dxf_to_world(x, y, z)),
+        _ => None,
+    }
+}
+
+/// 绘图光标系统 - 把鼠标位置投到绘图平面上
+pub fn draw_cursor_system(
+    mut draw: ResMut<DrawState>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+) {
+    if !draw.is_active() {
+        if draw.cursor.is_some() {
+            draw.cursor = None;
+        }
+        return;
+    }
+    let height = draw.points.first().map_or(0.0, |point| point.y);
+    let cursor = window.and_then(|window| window.cursor_position());
+    draw.cursor =
+        cursor
+            .zip(camera_query.iter().next())
+            .and_then(|(cursor, (camera, global_transform))| {
+                let ray = camera.viewport_to_world(global_transform, cursor).ok()?;
+                let plane = InfinitePlane3d::new(Vec3::Y);
+                let distance = ray.intersect_plane(Vec3::Y * height, plane)?;
+                Some(ray.get_point(distance))
+            });
+}
+
+/// 绘图系统 - 左键或命令行输入点，右键或回车结束，Esc 退出工具
+///
+/// 输入的点够生成一个实体时立即生成，记录到编辑历史，然后继续画下一个。
+#[allow(clippy::too_many_arguments)]
+pub fn draw_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut draw: ResMut<DrawState>,
+    mut history: ResMut<EditHistory>,
+    zoom_window: Res<ZoomWindow>,
+    layers: Res<LayerTable>,
+    line_types: Res<LineTypeTable>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor_query: Query<(Entity, &mut Editor)>,
+) -> Result {
+    let Some(tool) = draw.tool else {
+        return Ok(());
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        draw.cancel();
+        return Ok(());
+    }
+    let mut inputs = std::mem::take(&mut draw.pending);
+    // 窗口缩放时左键用于拖出缩放窗口
+    if !zoom_window.active && !contexts.ctx_mut()?.is_pointer_over_area() {
+        if buttons.just_pressed(MouseButton::Left) {
+            inputs.extend(draw.cursor.map(DrawInput::Point));
+        }
+        if buttons.just_pressed(MouseButton::Right) {
+            inputs.push(DrawInput::Finish);
+        }
+    }
+    let Some((editor_entity, mut editor)) = editor_query.iter_mut().next() else {
+        return Ok(());
+    };
+
+    for input in inputs {
+        draw.error = None;
+        let mut closed = false;
+        match input {
+            DrawInput::Point(point) => {
+                draw.points.push(point);
+                draw.last_point = point;
+            }
+            DrawInput::Undo => {
+                draw.points.pop();
+                continue;
+            }
+            DrawInput::Close if tool == DrawTool::Polyline && draw.points.len() >= 3 => {
+                closed = true;
+            }
+            DrawInput::Close => continue,
+            DrawInput::Finish => {
+                if draw.points.is_empty() {
+                    draw.cancel();
+                    return Ok(());
+                }
+                if tool != DrawTool::Polyline {
+                    draw.points.clear();
+                    continue;
+                }
+            }
+        }
+
+        // 多段线在结束或闭合时生成，其他工具在点数够时生成
+        let complete = match tool.point_count() {
+            Some(count) => draw.points.len() == count,
+            None => !matches!(input, DrawInput::Point(_)),
+        };
+        if !complete {
+            continue;
+        }
+        let geometry = if closed {
+            Some(polyline(draw.points.clone(), true))
+        } else {
+            build(tool, &draw.points)
+        };
+        let Some(geometry) = geometry else {
+            if tool == DrawTool::Polyline {
+                draw.points.clear();
+            } else {
+                draw.error = Some(format!("无法生成{}：输入的点重合或共线", tool.name()));
+                draw.points.pop();
+            }
+            continue;
+        };
+        let node = CadNode {
+            cad: CadEntity::new(geometry.entity_type(), &draw.layer, &layers, &line_types),
+            geometry,
+            children: Vec::new(),
+        };
+        let entity = spawn_cad_node(&mut commands, editor_entity, node.clone());
+        history.push(
+            tool.name(),
+            vec![Change::Create {
+                entity,
+                node: Box::new(node),
+                source: None,
+            }],
+        );
+        editor.is_dirty = true;
+        // 直线从上一条线的终点继续
+        let last = draw.points.last().copied();
+        draw.points.clear();
+        if tool == DrawTool::Line {
+            draw.points.extend(last);
+        }
+    }
+    Ok(())
+}
+
+/// 绘图预览系统 - 把光标当作下一个点，画出将要生成的图形
+pub fn draw_preview_system(
+    mut gizmos: Gizmos,
+    draw: Res<DrawState>,
+    tolerance: Res<CurveTolerance>,
+) {
+    let (Some(tool), Some(cursor)) = (draw.tool, draw.cursor) else {
+        return;
+    };
+    let points: Vec<Vec3> = draw.points.iter().copied().chain([cursor]).collect();
+    // 圆和圆弧的构造点之间画辅助线
+    if !matches!(tool, DrawTool::Line | DrawTool::Polyline) && points.len() >= 2 {
+        gizmos.linestrip(points.iter().copied(), GUIDE_COLOR);
+    }
+    if let Some(geometry) = build(tool, &points) {
+        gizmos.linestrip(preview_points(&geometry, &tolerance), PREVIEW_COLOR);
+    }
+}
+
+/// 绘图界面系统 - 工具栏和命令行
+///
+/// 绘图时命令行在没有其他输入框获得焦点时自动获得焦点，可以直接键入坐标。
+pub fn draw_ui_system(
+    mut contexts: EguiContexts,
+    mut draw: ResMut<DrawState>,
+    layers: Res<LayerTable>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    TopBottomPanel::top("draw_toolbar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            for tool in [DrawTool::Line, DrawTool::Polyline, DrawTool::Rectangle] {
+                if ui
+                    .selectable_label(draw.tool == Some(tool), tool.name())
+                    .clicked()
+                {
+                    draw.start(tool);
+                }
+            }
+            for (name, tools) in [
+                (
+                    "圆",
+                    &[
+                        DrawTool::CircleCenterRadius,
+                        DrawTool::Circle2Points,
+                        DrawTool::Circle3Points,
+                    ][..],
+                ),
+                (
+                    "圆弧",
+                    &[DrawTool::Arc3Points, DrawTool::ArcStartCenterEnd][..],
+                ),
+            ] {
+                ui.menu_button(name, |ui| {
+                    for &tool in tools {
+                        if ui
+                            .selectable_label(draw.tool == Some(tool), tool.method())
+                            .clicked()
+                        {
+                            draw.start(tool);
+                            ui.close();
+                        }
+                    }
+                });
+            }
+            ui.separator();
+            ui.label("图层");
+            ComboBox::from_id_salt("draw_layer")
+                .selected_text(draw.layer.clone())
+                .show_ui(ui, |ui| {
+                    for layer in &layers.layers {
+                        ui.selectable_value(&mut draw.layer, layer.name.clone(), &layer.name);
+                    }
+                });
+        });
+    });
+
+    let Some(tool) = draw.tool else {
+        return Ok(());
+    };
+    TopBottomPanel::bottom("command_line").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let method = match tool.method() {
+                "" => String::new(),
+                method => format!("（{}）", method),
+            };
+            ui.label(format!(
+                "{}{}：{}",
+                tool.name(),
+                method,
+                tool.prompt(draw.points.len())
+            ));
+            if let Some(error) = &draw.error {
+                ui.colored_label(Color32::RED, error);
+            }
+            let response = ui.add(
+                TextEdit::singleline(&mut draw.input)
+                    .hint_text("x,y  @dx,dy  @距离<角度")
+                    .desired_width(240.0),
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+                let text = std::mem::take(&mut draw.input);
+                draw.submit(&text);
+            }
+            if !response.has_focus() && ui.memory(|memory| memory.focused().is_none()) {
+                response.request_focus();
+            }
+            if ui.button("取消").clicked() {
+                draw.cancel();
+            }
+        });
+    });
+    Ok(())
+}
+
+/// 由输入的点生成几何数据，点数不对或点重合、共线时返回 None
+fn build(tool: DrawTool, points: &[Vec3]) -> Option<CadGeometry> {
+    match (tool, points) {
+        (DrawTool::Line, &[start, end]) => {
+            (start != end).then_some(CadGeometry::Line(LineEntity { start, end }))
+        }
+        (DrawTool::Polyline, vertices) if vertices.len() >= 2 => {
+            Some(polyline(vertices.to_vec(), false))
+        }
+        (DrawTool::Rectangle, &[a, b]) => {
+            // 矩形在第一个角点的水平面上
+            let b = Vec3::new(b.x, a.y, b.z);
+            (a.x != b.x && a.z != b.z).then(|| {
+                let vertices = vec![a, Vec3::new(b.x, a.y, a.z), b, Vec3::new(a.x, a.y, b.z)];
+                polyline(vertices, true)
+            })
+        }
+        (DrawTool::CircleCenterRadius, &[center, point]) => {
+            circle(center, horizontal_distance(center, point))
+        }
+        (DrawTool::Circle2Points, &[a, b]) => {
+            circle((a + b) * 0.5, horizontal_distance(a, b) * 0.5)
+        }
+        (DrawTool::Circle3Points, &[a, b, c]) => {
+            let center = circumcenter(a, b, c)?;
+            circle(center, horizontal_distance(center, a))
+        }
+        (DrawTool::Arc3Points, &[start, middle, end]) => {
+            let center = circumcenter(start, middle, end)?;
+            // 圆弧逆时针，三点顺时针排列时起点和终点互换
+            let (start, end) = if turn(start, middle, end) > 0.0 {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            arc(center, start, end)
+        }
+        (DrawTool::ArcStartCenterEnd, &[start, center, end]) => {
+            let center = Vec3::new(center.x, start.y, center.z);
+            (horizontal_distance(center, end) > 0.0)
+                .then(|| arc(center, start, end))
+                .flatten()
+        }
+        _ => None,
+    }
+}
+
+fn polyline(vertices: Vec<Vec3>, closed: bool) -> CadGeometry {
+    CadGeometry::Polyline(PolylineEntity::new(vertices, closed))
+}
+
+fn circle(center: Vec3, radius: f32) -> Option<CadGeometry> {
+    (radius > 0.0).then_some(CadGeometry::Circle(CircleEntity { center, radius }))
+}
+
+/// 以 center 为圆心、从 start 逆时针到 end 方向的圆弧，半径为圆心到起点的距离
+fn arc(center: Vec3, start: Vec3, end: Vec3) -> Option<CadGeometry> {
+    let radius = horizontal_distance(center, start);
+    (radius > 0.0).then(|| {
+        CadGeometry::Arc(ArcEntity {
+            center,
+            radius,
+            start_angle: angle(center, start),
+            end_angle: angle(center, end),
+        })
+    })
+}
+
+/// 水平面内的距离
+fn horizontal_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec2::new(b.x - a.x, b.z - a.z).length()
+}
+
+/// DXF XY 平面内从 center 指向 point 的角度（弧度，0 到 2π）
+fn angle(center: Vec3, point: Vec3) -> f32 {
+    (point.z - center.z)
+        .atan2(point.x - center.x)
+        .rem_euclid(TAU)
+}
+
+/// 三点在 DXF XY 平面内的转向，逆时针为正
+fn turn(a: Vec3, b: Vec3, c: Vec3) -> f32 {
+    Vec2::new(b.x - a.x, b.z - a.z).perp_dot(Vec2::new(c.x - b.x, c.z - b.z))
+}
+
+/// 三点的外接圆圆心，在第一个点的水平面上；三点共线时返回 None
+fn circumcenter(a: Vec3, b: Vec3, c: Vec3) -> Option<Vec3> {
+    let ab = Vec2::new(b.x - a.x, b.z - a.z);
+    let ac = Vec2::new(c.x - a.x, c.z - a.z);
+    let d = 2.0 * ab.perp_dot(ac);
+    if d.abs() <= f32::EPSILON * (ab.length_squared() + ac.length_squared()) {
+        return None;
+    }
+    let x = (ac.y * ab.length_squared() - ab.y * ac.length_squared()) / d;
+    let z = (ab.x * ac.length_squared() - ac.x * ab.length_squared()) / d;
+    Some(Vec3::new(a.x + x, a.y, a.z + z))
+}
+
+/// 预览图形离散后的折线
+fn preview_points(geometry: &CadGeometry, tolerance: &CurveTolerance) -> Vec<Vec3> {
+    match geometry {
+        CadGeometry::Line(line) => vec![line.start, line.end],
+        CadGeometry::Circle(c) => arc_points(c.center, c.radius, 0.0, TAU, tolerance),
+        CadGeometry::Arc(a) => {
+            arc_points(a.center, a.radius, a.start_angle, a.end_angle, tolerance)
+        }
+        CadGeometry::Polyline(pl) => pl.tessellate(tolerance),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    #[test]
+    fn parse_absolute_point() {
+        // DXF 的 (x, y, z) 对应世界的 (x, z, y)
+        let base = Vec3::new(10.0, 0.0, 10.0);
+        assert_eq!(
+            parse_point("3,4", base, None),
+            Some(Vec3::new(3.0, 0.0, 4.0))
+        );
+        assert_eq!(
+            parse_point(" 3 , 4 , 5 ", base, None),
+            Some(Vec3::new(3.0, 5.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn parse_relative_point() {
+        let base = Vec3::new(10.0, 0.0, 10.0);
+        assert_eq!(
+            parse_point("@1,2", base, None),
+            Some(Vec3::new(11.0, 0.0, 12.0))
+        );
+    }
+
+    #[test]
+    fn parse_polar_point() {
+        let base = Vec3::new(1.0, 0.0, 1.0);
+        let point = parse_point("@2<90", base, None).unwrap();
+        assert!(close(point, Vec3::new(1.0, 0.0, 3.0)));
+        let point = parse_point("2<180", base, None).unwrap();
+        assert!(close(point, Vec3::new(-2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn parse_distance_along_cursor() {
+        let base = Vec3::new(1.0, 0.0, 1.0);
+        let cursor = Some(Vec3::new(1.0, 0.0, 11.0));
+        let point = parse_point("5", base, cursor).unwrap();
+        assert!(close(point, Vec3::new(1.0, 0.0, 6.0)));
+        // 没有光标时沿 X 轴
+        let point = parse_point("5", base, None).unwrap();
+        assert!(close(point, Vec3::new(6.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn parse_invalid_point() {
+        assert_eq!(parse_point("", Vec3::ZERO, None), None);
+        assert_eq!(parse_point("a,b", Vec3::ZERO, None), None);
+        assert_eq!(parse_point("@5", Vec3::ZERO, None), None);
+        assert_eq!(parse_point("1,2,3,4", Vec3::ZERO, None), None);
+    }
+
+    #[test]
+    fn circumcenter_of_three_points() {
+        let center = circumcenter(
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+        )
+        .unwrap();
+        assert!(close(center, Vec3::ZERO));
+        assert_eq!(
+            circumcenter(Vec3::ZERO, Vec3::X, Vec3::new(2.0, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn turn_direction() {
+        let (a, b) = (Vec3::ZERO, Vec3::X);
+        assert!(turn(a, b, Vec3::new(1.0, 0.0, 1.0)) > 0.0);
+        assert!(turn(a, b, Vec3::new(1.0, 0.0, -1.0)) < 0.0);
+        assert_eq!(turn(a, b, Vec3::new(2.0, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn invalid_input_sets_error_until_next_input() {
+        let mut draw = DrawState::default();
+        draw.start(DrawTool::Line);
+        draw.submit("1,a");
+        assert!(draw.pending.is_empty());
+        assert_eq!(draw.error.as_deref(), Some("无法识别的输入: 1,a"));
+
+        draw.submit("1,2");
+        assert_eq!(draw.pending.len(), 1);
+        assert!(draw.error.is_none());
+    }
+}
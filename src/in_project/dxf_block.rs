@@ -0,0 +1,409 @@
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use dxf::entities::{Attribute, EntityType, Insert, Text};
+use dxf::{Block, Drawing, Handle};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use super::dxf_codes::DxfCodes;
+use super::dxf_curve::EllipseEntity;
+use super::dxf_ocs::{SWAP_YZ, ocs_matrix, ocs_to_world};
+use super::dxf_renderer::{
+    CadEntity, CadEntityType, CadGeometry, CadGeometryQuery, PolylineEntity, dxf_to_world,
+    geometry_from_dxf, spawn_cad_entity, text_from_dxf,
+};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+
+/// 块嵌套的最大深度，防止循环引用
+const MAX_BLOCK_DEPTH: usize = 16;
+
+/// 块参照实体数据（INSERT）
+///
+/// 块中的几何数据在子实体中，子实体带有 BlockPart 标记。
+#[derive(Component, Debug, Clone)]
+pub struct InsertEntity {
+    /// 块名
+    pub block: String,
+    /// 插入点（世界坐标）
+    pub position: Vec3,
+    /// X/Y/Z 缩放比例（DXF 坐标系）
+    pub scale: Vec3,
+    /// 在块参照 OCS 的 XY 平面内的旋转角（弧度）
+    pub rotation: f32,
+    /// 阵列列数和行数
+    pub columns: u16,
+    pub rows: u16,
+    pub column_spacing: f32,
+    pub row_spacing: f32,
+}
+
+/// 块参照的部件 - 属于某个块参照的子实体，不单独选择和导出
+#[derive(Component, Debug, Clone, Copy)]
+pub struct BlockPart;
+
+/// 待生成的 CAD 实体及其子实体（块参照的部件）
+#[derive(Debug, Clone)]
+pub struct CadNode {
+    pub cad: CadEntity,
+    pub geometry: CadGeometry,
+    pub children: Vec<CadNode>,
+}
+
+/// 按名称查找块定义（块名不区分大小写）
+pub struct BlockTable<'a> {
+    blocks: HashMap<String, &'a Block>,
+    /// 块定义中的填充和属性的通用组码（dxf 库不读取，另外解析）
+    codes: &'a DxfCodes,
+}
+
+impl<'a> BlockTable<'a> {
+    pub fn new(drawing: &'a Drawing, codes: &'a DxfCodes) -> Self {
+        Self {
+            blocks: drawing
+                .blocks()
+                .map(|block| (block.name.to_uppercase(), block))
+                .collect(),
+            codes,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a Block> {
+        self.blocks.get(&name.to_uppercase()).copied()
+    }
+}
+
+/// 在 parent 下生成 CAD 实体及其子实体
+///
+/// 子实体的几何数据仍是世界坐标，Transform 相对于父实体的参考位置。
+pub fn spawn_cad_node(commands: &mut Commands, parent: Entity, node: CadNode) -> Entity {
+    let origin = node.geometry.anchor();
+    let id = spawn_cad_entity(commands, parent, node.cad, node.geometry);
+    for child in node.children {
+        let anchor = child.geometry.anchor();
+        let child_id = spawn_cad_node(commands, id, child);
+        commands
+            .entity(child_id)
+            .insert((BlockPart, Transform::from_translation(anchor - origin)));
+    }
+    id
+}
+
+/// 从实体及其子实体的组件还原 CadNode
+pub fn cad_node(
+    entity: Entity,
+    cad_query: &Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+) -> Option<CadNode> {
+    let (cad, geometry, children) = cad_query.get(entity).ok()?;
+    Some(CadNode {
+        cad: cad.clone(),
+        geometry: geometry.geometry()?,
+        children: children
+            .into_iter()
+            .flatten()
+            .filter_map(|&child| cad_node(child, cad_query))
+            .collect(),
+    })
+}
+
+/// 展开块参照：块定义中的实体经过插入变换后作为子实体
+///
+/// cad 是块参照自身的实体信息，handle 是块参照的句柄（用于查找属性的通用组码），
+/// parent 是外层块参照的变换（世界坐标），顶层块参照传入单位变换。
+pub fn insert_node(
+    blocks: &BlockTable,
+    layers: &LayerTable,
+    cad: CadEntity,
+    handle: Handle,
+    insert: &Insert,
+    parent: Affine3A,
+    depth: usize,
+) -> Option<CadNode> {
+    if depth >= MAX_BLOCK_DEPTH {
+        eprintln!("块嵌套过深，可能存在循环引用: {}", insert.name);
+        return None;
+    }
+    let Some(block) = blocks.get(&insert.name) else {
+        eprintln!("未找到块定义: {}", insert.name);
+        return None;
+    };
+
+    let mut children = Vec::new();
+    for row in 0..insert.row_count.max(1) as u16 {
+        for column in 0..insert.column_count.max(1) as u16 {
+            let transform = parent * insert_transform(insert, block, column, row);
+            children.extend(block_children(
+                blocks, layers, block, &cad, transform, depth,
+            ));
+        }
+    }
+
+    // 属性（ATTRIB）的位置已经是块参照所在坐标系中的位置（属性自己的 OCS）
+    // 属性的图层和颜色按块中实体的规则解析，随块时随块参照
+    for (index, attribute) in insert.attributes().enumerate() {
+        if attribute.is_invisible() {
+            continue;
+        }
+        let mut geometry = CadGeometry::Text(text_from_dxf(&attribute_text(attribute)));
+        if let Some(ocs) = ocs_to_world(&attribute.normal) {
+            geometry = transform_geometry(geometry, &ocs);
+        }
+        let attribute_cad = match blocks.codes.attribute(handle, index) {
+            Some(common) => CadEntity::from_dxf(common, CadEntityType::Text, layers, Some(&cad)),
+            // 没有句柄的文件找不到属性的通用组码，随块参照
+            None => CadEntity {
+                entity_type: CadEntityType::Text,
+                selectable: false,
+                ..cad.clone()
+            },
+        };
+        children.push(CadNode {
+            cad: attribute_cad,
+            geometry: transform_geometry(geometry, &parent),
+            children: Vec::new(),
+        });
+    }
+
+    let location = &insert.location;
+    let ocs = SWAP_YZ * ocs_matrix(&insert.extrusion_direction) * SWAP_YZ;
+    Some(CadNode {
+        cad,
+        geometry: CadGeometry::Insert(InsertEntity {
+            block: insert.name.clone(),
+            position: (parent * ocs)
+                .transform_point3(dxf_to_world(location.x, location.y, location.z)),
+            scale: Vec3::new(
+                insert.x_scale_factor as f32,
+                insert.y_scale_factor as f32,
+                insert.z_scale_factor as f32,
+            ),
+            rotation: insert.rotation.to_radians() as f32,
+            columns: insert.column_count.max(1) as u16,
+            rows: insert.row_count.max(1) as u16,
+            column_spacing: insert.column_spacing as f32,
+            row_spacing: insert.row_spacing as f32,
+        }),
+        children,
+    })
+}
+
+/// 块定义中的实体经过变换后生成子实体
+///
+/// parent 是引用块的实体，块中实体随它的图层和颜色；depth 是引用块的实体的嵌套深度。
+pub fn block_children(
+    blocks: &BlockTable,
+    layers: &LayerTable,
+    block: &Block,
+    parent: &CadEntity,
+    transform: Affine3A,
+    depth: usize,
+) -> Vec<CadNode> {
+    let mut children = Vec::new();
+    for part in &block.entities {
+        let node = match &part.specific {
+            EntityType::Insert(nested) => {
+                let cad =
+                    CadEntity::from_dxf(&part.common, CadEntityType::Insert, layers, Some(parent));
+                let handle = part.common.handle;
+                insert_node(blocks, layers, cad, handle, nested, transform, depth + 1)
+            }
+            specific => {
+                geometry_from_dxf(specific, part.common.elevation).map(|geometry| CadNode {
+                    cad: CadEntity::from_dxf(
+                        &part.common,
+                        geometry.entity_type(),
+                        layers,
+                        Some(parent),
+                    ),
+                    geometry: transform_geometry(geometry, &transform),
+                    children: Vec::new(),
+                })
+            }
+        };
+        children.extend(node);
+    }
+    for (common, hatch) in blocks.codes.hatches.block(&block.name) {
+        children.push(CadNode {
+            cad: CadEntity::from_dxf(common, CadEntityType::Hatch, layers, Some(parent)),
+            geometry: transform_geometry(CadGeometry::Hatch(hatch.clone()), &transform),
+            children: Vec::new(),
+        });
+    }
+    children
+}
+
+/// 块参照中第 (column, row) 个阵列单元的变换（世界坐标）
+fn insert_transform(insert: &Insert, block: &Block, column: u16, row: u16) -> Affine3A {
+    let location = &insert.location;
+    let base = &block.base_point;
+    // DXF 坐标系中：OCS × 平移到插入点 × 旋转 × 阵列偏移 × 缩放 × 移到块基点
+    let dxf = ocs_matrix(&insert.extrusion_direction)
+        * Affine3A::from_translation(Vec3::new(
+            location.x as f32,
+            location.y as f32,
+            location.z as f32,
+        ))
+        * Affine3A::from_rotation_z(insert.rotation.to_radians() as f32)
+        * Affine3A::from_translation(Vec3::new(
+            column as f32 * insert.column_spacing as f32,
+            row as f32 * insert.row_spacing as f32,
+            0.0,
+        ))
+        * Affine3A::from_scale(Vec3::new(
+            insert.x_scale_factor as f32,
+            insert.y_scale_factor as f32,
+            insert.z_scale_factor as f32,
+        ))
+        * Affine3A::from_translation(-Vec3::new(base.x as f32, base.y as f32, base.z as f32));
+    SWAP_YZ * dxf * SWAP_YZ
+}
+
+/// 对几何数据应用变换
+///
+/// 非等比缩放的圆和弧转换为多段线；变换后不再平行于 XZ 平面的圆和弧转换为椭圆。
+pub(super) fn transform_geometry(geometry: CadGeometry, m: &Affine3A) -> CadGeometry {
+    // 变换后 DXF XY 平面内的两个坐标轴
+    let axis_x = m.transform_vector3(Vec3::X);
+    let axis_y = m.transform_vector3(Vec3::Z);
+    let uniform = (axis_x.length() - axis_y.length()).abs() <= 1e-4 * axis_x.length()
+        && axis_x.dot(axis_y).abs() <= 1e-4 * axis_x.length_squared();
+    // 变换后的平面是否仍平行于 XZ 平面（倾斜的 OCS 不平行）
+    let planar = axis_x.cross(axis_y).normalize_or_zero().y.abs() >= 1.0 - 1e-4;
+    // 镜像时弧的方向反转
+    let mirrored = axis_x.x * axis_y.z - axis_x.z * axis_y.x < 0.0;
+    let angle_of = |angle: f32| {
+        let d = m.transform_vector3(Vec3::new(angle.cos(), 0.0, angle.sin()));
+        d.z.atan2(d.x)
+    };
+
+    match geometry {
+        CadGeometry::Line(mut line) => {
+            line.start = m.transform_point3(line.start);
+            line.end = m.transform_point3(line.end);
+            CadGeometry::Line(line)
+        }
+        CadGeometry::Circle(mut circle) if uniform && planar => {
+            circle.center = m.transform_point3(circle.center);
+            circle.radius *= axis_x.length();
+            CadGeometry::Circle(circle)
+        }
+        // 圆的仿射变换是椭圆，原来的两条半径是椭圆的一对共轭半径，显示时按缩放离散
+        CadGeometry::Circle(circle) => CadGeometry::Ellipse(EllipseEntity {
+            center: m.transform_point3(circle.center),
+            major_axis: m.transform_vector3(Vec3::X * circle.radius),
+            minor_axis: m.transform_vector3(Vec3::Z * circle.radius),
+            start_param: 0.0,
+            end_param: TAU,
+        }),
+        CadGeometry::Arc(mut arc) if uniform && planar => {
+            let (start, end) = (angle_of(arc.start_angle), angle_of(arc.end_angle));
+            (arc.start_angle, arc.end_angle) = if mirrored { (end, start) } else { (start, end) };
+            arc.center = m.transform_point3(arc.center);
+            arc.radius *= axis_x.length();
+            CadGeometry::Arc(arc)
+        }
+        CadGeometry::Arc(arc) => CadGeometry::Ellipse(EllipseEntity {
+            center: m.transform_point3(arc.center),
+            major_axis: m.transform_vector3(Vec3::X * arc.radius),
+            minor_axis: m.transform_vector3(Vec3::Z * arc.radius),
+            start_param: arc.start_angle,
+            end_param: arc.end_angle,
+        }),
+        CadGeometry::Polyline(mut pl)
+            if (uniform && planar) || pl.bulges.iter().all(|b| *b == 0.0) =>
+        {
+            for v in &mut pl.vertices {
+                *v = m.transform_point3(*v);
+            }
+            // 镜像后圆弧段方向反转
+            if mirrored {
+                for b in &mut pl.bulges {
+                    *b = -*b;
+                }
+            }
+            let scale = axis_x.length();
+            for w in &mut pl.widths {
+                *w = w.map(|w| w * scale);
+            }
+            CadGeometry::Polyline(pl)
+        }
+        // 非等比缩放或倾斜时圆弧段不再是 XZ 平面内的圆弧，离散后变换
+        CadGeometry::Polyline(pl) => {
+            let closed = pl.closed;
+            let mut vertices: Vec<Vec3> = pl
+                .tessellate(&CurveTolerance::relative(&pl.vertices))
+                .into_iter()
+                .map(|v| m.transform_point3(v))
+                .collect();
+            if closed {
+                vertices.pop();
+            }
+            CadGeometry::Polyline(PolylineEntity::new(vertices, closed))
+        }
+        CadGeometry::Text(mut text) => {
+            let x_dir =
+                m.transform_vector3(Vec3::new(text.rotation.cos(), 0.0, text.rotation.sin()));
+            let up_dir =
+                m.transform_vector3(Vec3::new(-text.rotation.sin(), 0.0, text.rotation.cos()));
+            text.position = m.transform_point3(text.position);
+            text.rotation = x_dir.z.atan2(x_dir.x);
+            text.height *= up_dir.length();
+            text.wrap_width = text.wrap_width.map(|w| w * x_dir.length());
+            text.fit_width = text.fit_width.map(|w| w * x_dir.length());
+            CadGeometry::Text(text)
+        }
+        CadGeometry::Insert(mut insert) => {
+            insert.position = m.transform_point3(insert.position);
+            CadGeometry::Insert(insert)
+        }
+        CadGeometry::Dimension(mut dimension) => {
+            dimension.dimension_point = m.transform_point3(dimension.dimension_point);
+            for v in &mut dimension.points {
+                *v = m.transform_point3(*v);
+            }
+            dimension.text_position = m.transform_point3(dimension.text_position);
+            CadGeometry::Dimension(dimension)
+        }
+        CadGeometry::Spline(mut spline) => {
+            for v in spline
+                .control_points
+                .iter_mut()
+                .chain(spline.fit_points.iter_mut())
+            {
+                *v = m.transform_point3(*v);
+            }
+            CadGeometry::Spline(spline)
+        }
+        CadGeometry::Ellipse(mut ellipse) => {
+            // 仿射变换后原来的长短轴是新椭圆的一对共轭半径，参数方程不变
+            ellipse.center = m.transform_point3(ellipse.center);
+            ellipse.major_axis = m.transform_vector3(ellipse.major_axis);
+            ellipse.minor_axis = m.transform_vector3(ellipse.minor_axis);
+            if mirrored {
+                // 镜像后参数方向反转
+                ellipse.minor_axis = -ellipse.minor_axis;
+                (ellipse.start_param, ellipse.end_param) =
+                    (-ellipse.end_param, -ellipse.start_param);
+            }
+            CadGeometry::Ellipse(ellipse)
+        }
+        CadGeometry::Hatch(mut hatch) => {
+            hatch.transform(m);
+            CadGeometry::Hatch(hatch)
+        }
+    }
+}
+
+/// 属性按单行文字显示
+fn attribute_text(attribute: &Attribute) -> Text {
+    Text {
+        location: attribute.location.clone(),
+        text_height: attribute.text_height,
+        value: attribute.value.clone(),
+        rotation: attribute.rotation,
+        horizontal_text_justification: attribute.horizontal_text_justification,
+        second_alignment_point: attribute.second_alignment_point.clone(),
+        vertical_text_justification: attribute.vertical_text_justification,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,350 @@
+use dxf::Handle;
+use dxf::entities::EntityCommon;
+use std::collections::HashMap;
+
+use super::dxf_hatch::HatchTable;
+
+/// DXF 文件中 dxf 库不读取或读取时丢弃的数据，按组码直接解析
+#[derive(Default)]
+pub struct DxfCodes {
+    /// 填充（dxf 库不读取 HATCH）
+    pub hatches: HatchTable,
+    /// 块参照所带属性（ATTRIB）的通用组码，键为块参照的句柄
+    ///
+    /// dxf 库把属性并入块参照时丢弃了属性自己的图层和颜色。
+    attributes: HashMap<u64, Vec<EntityCommon>>,
+    /// 图层标志（70 组码：冻结、锁定），键为大写的图层名
+    layer_flags: HashMap<String, i32>,
+}
+
+impl DxfCodes {
+    /// 解析 DXF 文件内容；二进制 DXF 时为空表
+    pub fn parse(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"AutoCAD Binary DXF") {
+            return Self::default();
+        }
+        let text = String::from_utf8_lossy(bytes);
+        let pairs = group_codes(&text);
+
+        let mut codes = Self::default();
+        let mut section = "";
+        let mut block = String::new();
+        // 正在读取其属性的块参照
+        let mut insert = None;
+        let mut index = 0;
+        while index < pairs.len() {
+            let (code, value) = pairs[index];
+            index += 1;
+            if code != 0 {
+                continue;
+            }
+            // 一个对象的组码到下一个 0 组码为止
+            let end = pairs[index..]
+                .iter()
+                .position(|(code, _)| *code == 0)
+                .map_or(pairs.len(), |n| index + n);
+            let body = &pairs[index..end];
+            let name = || find(body, 2).unwrap_or("");
+            match value {
+                "SECTION" => section = name(),
+                "BLOCK" => block = name().to_uppercase(),
+                "HATCH" => codes.hatches.add(section, &block, body),
+                "LAYER" if section == "TABLES" => {
+                    let flags = find(body, 70).and_then(|v| v.parse().ok());
+                    codes
+                        .layer_flags
+                        .insert(name().to_uppercase(), flags.unwrap_or(0));
+                }
+                "ATTRIB" => {
+                    if let Some(handle) = insert {
+                        codes
+                            .attributes
+                            .entry(handle)
+                            .or_default()
+                            .push(parse_common(body));
+                    }
+                }
+                _ => {}
+            }
+            insert = match value {
+                "INSERT" => handle(body),
+                "ATTRIB" => insert,
+                _ => None,
+            };
+            index = end;
+        }
+        codes
+    }
+
+    /// 图层标志，图层不在 LAYER 表中时为 0
+    pub fn layer_flags(&self, name: &str) -> i32 {
+        self.layer_flags
+            .get(&name.to_uppercase())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// 块参照的第 index 个属性的通用组码；文件中没有句柄时为 None
+    pub fn attribute(&self, insert: Handle, index: usize) -> Option<&EntityCommon> {
+        self.attributes.get(&insert.0)?.get(index)
+    }
+}
+
+/// 把 DXF 文本拆分为 (组码, 值)，遇到无法解析的组码时停止
+pub(super) fn group_codes(text: &str) -> Vec<(i32, &str)> {
+    let mut lines = text.lines();
+    let mut pairs = Vec::new();
+    while let (Some(code), Some(value)) = (lines.next(), lines.next()) {
+        let Ok(code) = code.trim().parse::<i32>() else {
+            break;
+        };
+        pairs.push((code, value.trim()));
+    }
+    pairs
+}
+
+/// 第一个指定组码的值
+fn find<'a>(pairs: &[(i32, &'a str)], code: i32) -> Option<&'a str> {
+    pairs.iter().find(|(c, _)| *c == code).map(|(_, v)| *v)
+}
+
+/// 对象的句柄（5 组码）
+fn handle(pairs: &[(i32, &str)]) -> Option<u64> {
+    u64::from_str_radix(find(pairs, 5)?, 16).ok()
+}
+
+/// 通用组码：句柄、图层、颜色、线型和线宽
+///
+/// 只读取第一个子类标记（AcDbEntity 之后的那个）之前的组码。
+pub(super) fn parse_common(pairs: &[(i32, &str)]) -> EntityCommon {
+    let end = pairs
+        .iter()
+        .position(|(code, value)| *code == 100 && *value != "AcDbEntity")
+        .unwrap_or(pairs.len());
+    let mut common = EntityCommon {
+        layer: "0".to_string(),
+        line_type_name: "BYLAYER".to_string(),
+        color: dxf::Color::by_layer(),
+        line_type_scale: 1.0,
+        lineweight_enum_value: -1,
+        ..Default::default()
+    };
+    for (code, value) in &pairs[..end] {
+        match code {
+            5 => common.handle = Handle(u64::from_str_radix(value, 16).unwrap_or(0)),
+            8 => common.layer = value.to_string(),
+            6 => common.line_type_name = value.to_string(),
+            62 => {
+                if let Ok(raw) = value.parse::<i16>() {
+                    common.color = match raw {
+                        0 => dxf::Color::by_block(),
+                        1..=255 => dxf::Color::from_index(raw as u8),
+                        // 负数表示所在的图层关闭
+                        i16::MIN..=-1 => {
+                            let mut color = dxf::Color::by_layer();
+                            color.turn_off();
+                            color
+                        }
+                        _ => dxf::Color::by_layer(),
+                    };
+                }
+            }
+            420 => common.color_24_bit = value.parse().unwrap_or(0),
+            48 => common.line_type_scale = value.parse().unwrap_or(1.0),
+            370 => common.lineweight_enum_value = value.parse().unwrap_or(-1),
+            _ => {}
+        }
+    }
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::Vec3;
+
+    /// 按 DXF 文本格式写出组码
+    fn dxf(pairs: &[(i32, &str)]) -> String {
+        pairs
+            .iter()
+            .map(|(code, value)| format!("{:>3}\r\n{}\r\n", code, value))
+            .collect()
+    }
+
+    /// 正方形的多段线边界
+    fn square(
+        flags: &'static str,
+        min: &'static str,
+        max: &'static str,
+    ) -> Vec<(i32, &'static str)> {
+        vec![
+            (92, flags),
+            (72, "0"),
+            (73, "1"),
+            (93, "4"),
+            (10, min),
+            (20, min),
+            (10, max),
+            (20, min),
+            (10, max),
+            (20, max),
+            (10, min),
+            (20, max),
+            (97, "0"),
+        ]
+    }
+
+    fn fixture() -> String {
+        let mut pairs = vec![
+            (0, "SECTION"),
+            (2, "TABLES"),
+            (0, "TABLE"),
+            (2, "LAYER"),
+            (0, "LAYER"),
+            (5, "10"),
+            (100, "AcDbSymbolTableRecord"),
+            (2, "Frozen"),
+            (70, "5"),
+            (62, "1"),
+            (0, "ENDTAB"),
+            (0, "ENDSEC"),
+            (0, "SECTION"),
+            (2, "ENTITIES"),
+            (0, "HATCH"),
+            (5, "2A"),
+            (330, "1F"),
+            (100, "AcDbEntity"),
+            (8, "Walls"),
+            (62, "1"),
+            (100, "AcDbHatch"),
+            (10, "0"),
+            (20, "0"),
+            (30, "0"),
+            (210, "0"),
+            (220, "0"),
+            (230, "1"),
+            (2, "SOLID"),
+            (70, "1"),
+            (71, "0"),
+            (91, "2"),
+        ];
+        // 外部边界和其中的孤岛
+        pairs.extend(square("3", "0", "10"));
+        pairs.extend(square("2", "4", "6"));
+        pairs.extend([
+            (75, "0"),
+            (76, "1"),
+            (98, "0"),
+            (0, "LWPOLYLINE"),
+            (5, "2B"),
+            (100, "AcDbEntity"),
+            (8, "0"),
+            (100, "AcDbPolyline"),
+            (90, "2"),
+            (70, "0"),
+            (10, "0"),
+            (20, "0"),
+            (10, "1"),
+            (20, "1"),
+            (0, "INSERT"),
+            (5, "2C"),
+            (100, "AcDbEntity"),
+            (8, "0"),
+            (100, "AcDbBlockReference"),
+            (66, "1"),
+            (2, "TAG"),
+            (10, "0"),
+            (20, "0"),
+            (0, "ATTRIB"),
+            (5, "2D"),
+            (100, "AcDbEntity"),
+            (8, "Text"),
+            (62, "3"),
+            (100, "AcDbText"),
+            (10, "0"),
+            (20, "0"),
+            (40, "1"),
+            (1, "A"),
+            (0, "SEQEND"),
+            (5, "2E"),
+            (100, "AcDbEntity"),
+            (8, "0"),
+            (0, "ENDSEC"),
+            (0, "EOF"),
+        ]);
+        dxf(&pairs)
+    }
+
+    #[test]
+    fn parse_hatch_with_island() {
+        let codes = DxfCodes::parse(fixture().as_bytes());
+        let [(common, hatch)] = codes.hatches.entities.as_slice() else {
+            panic!("expected one hatch");
+        };
+        assert_eq!(common.layer, "Walls");
+        assert_eq!(common.color.index(), Some(1));
+        assert!(hatch.solid);
+        assert_eq!(hatch.handle, Some(Handle(0x2A)));
+        assert_eq!(hatch.loops.len(), 2);
+        assert_eq!(hatch.loops[0].len(), 4);
+        assert_eq!(hatch.loops[1][0], Vec3::new(4.0, 0.0, 4.0));
+    }
+
+    #[test]
+    fn hatch_source_codes_only_while_unchanged() {
+        let codes = DxfCodes::parse(fixture().as_bytes());
+        let mut hatch = codes.hatches.entities[0].1.clone();
+        let source = codes.hatches.source(&hatch).unwrap();
+        assert_eq!(source.first(), Some(&(5, "2A".to_string())));
+        assert_eq!(source.last(), Some(&(98, "0".to_string())));
+
+        hatch.loops.pop();
+        assert!(codes.hatches.source(&hatch).is_none());
+    }
+
+    #[test]
+    fn parse_attribute_codes() {
+        let codes = DxfCodes::parse(fixture().as_bytes());
+        let attribute = codes.attribute(Handle(0x2C), 0).unwrap();
+        assert_eq!(attribute.layer, "Text");
+        assert_eq!(attribute.color.index(), Some(3));
+        assert_eq!(attribute.handle, Handle(0x2D));
+        assert!(codes.attribute(Handle(0x2C), 1).is_none());
+        assert!(codes.attribute(Handle(0x2B), 0).is_none());
+    }
+
+    #[test]
+    fn parse_layer_flags() {
+        let codes = DxfCodes::parse(fixture().as_bytes());
+        assert_eq!(codes.layer_flags("FROZEN"), 5);
+        assert_eq!(codes.layer_flags("missing"), 0);
+    }
+
+    #[test]
+    fn parse_common_colors() {
+        let color = |value| parse_common(&[(62, value)]).color;
+        assert!(color("0").is_by_block());
+        assert!(color("256").is_by_layer());
+        assert_eq!(color("12").index(), Some(12));
+        assert!(color("-5").is_turned_off());
+        // 子类标记之后的组码不属于通用组码
+        let common = parse_common(&[(8, "A"), (100, "AcDbText"), (8, "B")]);
+        assert_eq!(common.layer, "A");
+    }
+
+    #[test]
+    fn handles_match_the_dxf_library() {
+        let text = fixture();
+        let drawing = dxf::Drawing::load(&mut text.as_bytes()).unwrap();
+        let handles: Vec<u64> = drawing.entities().map(|e| e.common.handle.0).collect();
+        assert!(handles.contains(&0x2B));
+        assert!(handles.contains(&0x2C));
+    }
+
+    #[test]
+    fn binary_dxf_is_empty() {
+        let codes = DxfCodes::parse(b"AutoCAD Binary DXF\r\n\x1a\0");
+        assert!(codes.hatches.entities.is_empty());
+        assert!(codes.attributes.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use dxf::entities::EntityCommon;
+use serde::{Deserialize, Serialize};
+
+/// AutoCAD 颜色索引（ACI）对应的颜色（sRGB）
+///
+/// 0（随块）按白色显示；10-249 按色相每 10 个一组，250-255 是灰度。
+#[rustfmt::skip]
+const ACI_COLORS: [[u8; 3]; 256] = [
+    // 0-9
+    [255, 255, 255], [255, 0, 0], [255, 255, 0], [0, 255, 0], [0, 255, 255],
+    [0, 0, 255], [255, 0, 255], [255, 255, 255], [65, 65, 65], [128, 128, 128],
+    // 10-19
+    [255, 0, 0], [255, 170, 170], [189, 0, 0], [189, 126, 126], [129, 0, 0],
+    [129, 86, 86], [104, 0, 0], [104, 69, 69], [79, 0, 0], [79, 53, 53],
+    // 20-29
+    [255, 63, 0], [255, 191, 170], [189, 46, 0], [189, 141, 126], [129, 31, 0],
+    [129, 96, 86], [104, 25, 0], [104, 78, 69], [79, 19, 0], [79, 59, 53],
+    // 30-39
+    [255, 127, 0], [255, 212, 170], [189, 94, 0], [189, 157, 126], [129, 64, 0],
+    [129, 107, 86], [104, 52, 0], [104, 86, 69], [79, 39, 0], [79, 66, 53],
+    // 40-49
+    [255, 191, 0], [255, 234, 170], [189, 141, 0], [189, 173, 126], [129, 96, 0],
+    [129, 118, 86], [104, 78, 0], [104, 95, 69], [79, 59, 0], [79, 73, 53],
+    // 50-59
+    [255, 255, 0], [255, 255, 170], [189, 189, 0], [189, 189, 126], [129, 129, 0],
+    [129, 129, 86], [104, 104, 0], [104, 104, 69], [79, 79, 0], [79, 79, 53],
+    // 60-69
+    [191, 255, 0], [234, 255, 170], [141, 189, 0], [173, 189, 126], [96, 129, 0],
+    [118, 129, 86], [78, 104, 0], [95, 104, 69], [59, 79, 0], [73, 79, 53],
+    // 70-79
+    [127, 255, 0], [212, 255, 170], [94, 189, 0], [157, 189, 126], [64, 129, 0],
+    [107, 129, 86], [52, 104, 0], [86, 104, 69], [39, 79, 0], [66, 79, 53],
+    // 80-89
+    [63, 255, 0], [191, 255, 170], [46, 189, 0], [141, 189, 126], [31, 129, 0],
+    [96, 129, 86], [25, 104, 0], [78, 104, 69], [19, 79, 0], [59, 79, 53],
+    // 90-99
+    [0, 255, 0], [170, 255, 170], [0, 189, 0], [126, 189, 126], [0, 129, 0],
+    [86, 129, 86], [0, 104, 0], [69, 104, 69], [0, 79, 0], [53, 79, 53],
+    // 100-109
+    [0, 255, 63], [170, 255, 191], [0, 189, 46], [126, 189, 141], [0, 129, 31],
+    [86, 129, 96], [0, 104, 25], [69, 104, 78], [0, 79, 19], [53, 79, 59],
+    // 110-119
+    [0, 255, 127], [170, 255, 212], [0, 189, 94], [126, 189, 157], [0, 129, 64],
+    [86, 129, 107], [0, 104, 52], [69, 104, 86], [0, 79, 39], [53, 79, 66],
+    // 120-129
+    [0, 255, 191], [170, 255, 234], [0, 189, 141], [126, 189, 173], [0, 129, 96],
+    [86, 129, 118], [0, 104, 78], [69, 104, 95], [0, 79, 59], [53, 79, 73],
+    // 130-139
+    [0, 255, 255], [170, 255, 255], [0, 189, 189], [126, 189, 189], [0, 129, 129],
+    [86, 129, 129], [0, 104, 104], [69, 104, 104], [0, 79, 79], [53, 79, 79],
+    // 140-149
+    [0, 191, 255], [170, 234, 255], [0, 141, 189], [126, 173, 189], [0, 96, 129],
+    [86, 118, 129], [0, 78, 104], [69, 95, 104], [0, 59, 79], [53, 73, 79],
+    // 150-159
+    [0, 127, 255], [170, 212, 255], [0, 94, 189], [126, 157, 189], [0, 64, 129],
+    [86, 107, 129], [0, 52, 104], [69, 86, 104], [0, 39, 79], [53, 66, 79],
+    // 160-169
+    [0, 63, 255], [170, 191, 255], [0, 46, 189], [126, 141, 189], [0, 31, 129],
+    [86, 96, 129], [0, 25, 104], [69, 78, 104], [0, 19, 79], [53, 59, 79],
+    // 170-179
+    [0, 0, 255], [170, 170, 255], [0, 0, 189], [126, 126, 189], [0, 0, 129],
+    [86, 86, 129], [0, 0, 104], [69, 69, 104], [0, 0, 79], [53, 53, 79],
+    // 180-189
+    [63, 0, 255], [191, 170, 255], [46, 0, 189], [141, 126, 189], [31, 0, 129],
+    [96, 86, 129], [25, 0, 104], [78, 69, 104], [19, 0, 79], [59, 53, 79],
+    // 190-199
+    [127, 0, 255], [212, 170, 255], [94, 0, 189], [157, 126, 189], [64, 0, 129],
+    [107, 86, 129], [52, 0, 104], [86, 69, 104], [39, 0, 79], [66, 53, 79],
+    // 200-209
+    [191, 0, 255], [234, 170, 255], [141, 0, 189], [173, 126, 189], [96, 0, 129],
+    [118, 86, 129], [78, 0, 104], [95, 69, 104], [59, 0, 79], [73, 53, 79],
+    // 210-219
+    [255, 0, 255], [255, 170, 255], [189, 0, 189], [189, 126, 189], [129, 0, 129],
+    [129, 86, 129], [104, 0, 104], [104, 69, 104], [79, 0, 79], [79, 53, 79],
+    // 220-229
+    [255, 0, 191], [255, 170, 234], [189, 0, 141], [189, 126, 173], [129, 0, 96],
+    [129, 86, 118], [104, 0, 78], [104, 69, 95], [79, 0, 59], [79, 53, 73],
+    // 230-239
+    [255, 0, 127], [255, 170, 212], [189, 0, 94], [189, 126, 157], [129, 0, 64],
+    [129, 86, 107], [104, 0, 52], [104, 69, 86], [79, 0, 39], [79, 53, 66],
+    // 240-249
+    [255, 0, 63], [255, 170, 191], [189, 0, 46], [189, 126, 141], [129, 0, 31],
+    [129, 86, 96], [104, 0, 25], [104, 69, 78], [79, 0, 19], [79, 53, 59],
+    // 250-255
+    [51, 51, 51], [80, 80, 80], [105, 105, 105], [130, 130, 130], [190, 190, 190],
+    [255, 255, 255],
+];
+
+/// 实体的颜色设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CadColor {
+    /// 随层
+    #[default]
+    ByLayer,
+    /// 随块：块中的实体使用引用块的实体的颜色
+    ByBlock,
+    /// AutoCAD 颜色索引（ACI，1-255）
+    Index(u8),
+    /// 24 位真彩色
+    TrueColor([u8; 3]),
+}
+
+impl CadColor {
+    /// 读取 DXF 实体的颜色，真彩色优先
+    pub fn from_dxf(common: &EntityCommon) -> Self {
+        if common.color_24_bit != 0 {
+            let rgb = common.color_24_bit as u32;
+            return CadColor::TrueColor([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]);
+        }
+        if common.color.is_by_block() {
+            CadColor::ByBlock
+        } else if let Some(index) = common.color.index() {
+            CadColor::Index(index)
+        } else {
+            CadColor::ByLayer
+        }
+    }
+
+    /// 写入 DXF 实体的颜色
+    pub fn to_dxf(self, common: &mut EntityCommon) {
+        common.color_24_bit = 0;
+        common.color = match self {
+            CadColor::ByLayer => dxf::Color::by_layer(),
+            CadColor::ByBlock => dxf::Color::by_block(),
+            CadColor::Index(index) => dxf::Color::from_index(index),
+            CadColor::TrueColor([r, g, b]) => {
+                common.color_24_bit = (r as i32) << 16 | (g as i32) << 8 | b as i32;
+                // 不支持真彩色的程序使用最接近的 ACI 颜色
+                dxf::Color::from_index(nearest_aci([r, g, b]))
+            }
+        };
+    }
+
+    /// 按随层/随块规则得到显示颜色
+    pub fn resolve(self, layer_color: Color, block_color: Color) -> Color {
+        match self {
+            CadColor::ByLayer => layer_color,
+            CadColor::ByBlock => block_color,
+            CadColor::Index(index) => aci_color(index),
+            CadColor::TrueColor([r, g, b]) => Color::srgb_u8(r, g, b),
+        }
+    }
+}
+
+/// AutoCAD 颜色索引对应的颜色
+pub fn aci_color(index: u8) -> Color {
+    let [r, g, b] = aci_rgb(index);
+    Color::srgb_u8(r, g, b)
+}
+
+fn aci_rgb(index: u8) -> [u8; 3] {
+    ACI_COLORS[index as usize]
+}
+
+/// 与给定颜色最接近的 ACI 颜色
+fn nearest_aci(rgb: [u8; 3]) -> u8 {
+    let distance = |other: [u8; 3]| -> i32 {
+        (0..3)
+            .map(|i| (rgb[i] as i32 - other[i] as i32).pow(2))
+            .sum()
+    };
+    (1..=255)
+        .min_by_key(|index| distance(aci_rgb(*index)))
+        .unwrap_or(7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aci_rgb_uses_the_autocad_palette() {
+        assert_eq!(aci_rgb(1), [255, 0, 0]);
+        assert_eq!(aci_rgb(5), [0, 0, 255]);
+        assert_eq!(aci_rgb(7), [255, 255, 255]);
+        assert_eq!(aci_rgb(8), [65, 65, 65]);
+        assert_eq!(aci_rgb(250), [51, 51, 51]);
+        assert_eq!(aci_rgb(255), [255, 255, 255]);
+    }
+
+    #[test]
+    fn nearest_aci_finds_exact_colors() {
+        assert_eq!(nearest_aci([255, 0, 0]), 1);
+        assert_eq!(nearest_aci([0, 0, 255]), 5);
+        assert_eq!(nearest_aci([128, 128, 128]), 9);
+    }
+}
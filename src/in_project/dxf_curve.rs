@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use super::dxf_renderer::dxf_to_world;
+use super::dxf_tessellation::CurveTolerance;
+
+/// 自适应细分的最大递归深度
+const MAX_SUBDIVISION_DEPTH: u32 = 12;
+
+/// 样条曲线实体数据（SPLINE，NURBS）
+#[derive(Component, Debug, Clone)]
+pub struct SplineEntity {
+    /// 阶数
+    pub degree: usize,
+    pub control_points: Vec<Vec3>,
+    /// 节点向量，长度为控制点数 + 阶数 + 1
+    pub knots: Vec<f32>,
+    /// 控制点权重，为空表示全为 1
+    pub weights: Vec<f32>,
+    /// 拟合点，没有控制点时曲线经过这些点
+    pub fit_points: Vec<Vec3>,
+    pub closed: bool,
+}
+
+/// 椭圆实体数据（ELLIPSE），起止参数相差 2π 时为完整椭圆
+#[derive(Component, Debug, Clone)]
+pub struct EllipseEntity {
+    pub center: Vec3,
+    /// 长轴端点相对圆心的向量
+    pub major_axis: Vec3,
+    /// 短轴端点相对圆心的向量
+    pub minor_axis: Vec3,
+    /// 起始参数（弧度）
+    pub start_param: f32,
+    /// 终止参数（弧度）
+    pub end_param: f32,
+}
+
+/// DXF 样条曲线转换为样条实体
+pub fn spline_from_dxf(spline: &dxf::entities::Spline) -> Option<SplineEntity> {
+    let w = |p: &dxf::Point| dxf_to_world(p.x, p.y, p.z);
+    let entity = SplineEntity {
+        degree: spline.degree_of_curve.max(1) as usize,
+        control_points: spline.control_points.iter().map(w).collect(),
+        knots: spline.knot_values.iter().map(|k| *k as f32).collect(),
+        weights: spline.weight_values.iter().map(|k| *k as f32).collect(),
+        fit_points: spline.fit_points.iter().map(w).collect(),
+        closed: spline.is_closed(),
+    };
+    (entity.control_points.len() >= 2 || entity.fit_points.len() >= 2).then_some(entity)
+}
+
+/// DXF 椭圆转换为椭圆实体
+pub fn ellipse_from_dxf(ellipse: &dxf::entities::Ellipse) -> EllipseEntity {
+    let major = &ellipse.major_axis;
+    let normal = &ellipse.normal;
+    // 短轴 = 法向 × 长轴 × 长短轴比（DXF 坐标系）
+    let ratio = ellipse.minor_axis_ratio;
+    let minor = (
+        (normal.y * major.z - normal.z * major.y) * ratio,
+        (normal.z * major.x - normal.x * major.z) * ratio,
+        (normal.x * major.y - normal.y * major.x) * ratio,
+    );
+    EllipseEntity {
+        center: dxf_to_world(ellipse.center.x, ellipse.center.y, ellipse.center.z),
+        major_axis: dxf_to_world(major.x, major.y, major.z),
+        minor_axis: dxf_to_world(minor.0, minor.1, minor.2),
+        start_param: ellipse.start_parameter as f32,
+        end_param: ellipse.end_parameter as f32,
+    }
+}
+
+impl SplineEntity {
+    /// 按弦高误差自适应离散为折线
+    pub fn tessellate(&self, tolerance: &CurveTolerance) -> Vec<Vec3> {
+        if self.control_points.len() < 2 {
+            return self.tessellate_fit_points(tolerance);
+        }
+        let degree = self.degree.min(self.control_points.len() - 1);
+        let knots = self.valid_knots(degree);
+        let (t0, t1) = (knots[degree], knots[self.control_points.len()]);
+        // 每个节点区间至少分 4 段，避免漏掉曲线细节
+        let spans = knots.windows(2).filter(|k| k[1] > k[0]).count().max(1);
+        let mut points = tessellate(
+            |t| eval_nurbs(degree, &self.control_points, &knots, &self.weights, t),
+            t0,
+            t1,
+            spans * 4,
+            tolerance.chord,
+        );
+        if self.closed
+            && let Some(first) = points.first().copied()
+        {
+            points.push(first);
+        }
+        points
+    }
+
+    /// 只有拟合点时用 Catmull-Rom 曲线经过各拟合点
+    fn tessellate_fit_points(&self, tolerance: &CurveTolerance) -> Vec<Vec3> {
+        let mut fit = self.fit_points.clone();
+        if self.closed
+            && let Some(first) = fit.first().copied()
+        {
+            fit.push(first);
+        }
+        if fit.len() < 3 {
+            return fit;
+        }
+        let segments = fit.len() - 1;
+        let point = |i: isize| fit[i.clamp(0, segments as isize) as usize];
+        tessellate(
+            |t| {
+                let i = (t.floor() as isize).min(segments as isize - 1);
+                let u = t - i as f32;
+                let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+                let (u2, u3) = (u * u, u * u * u);
+                0.5 * (2.0 * p1
+                    + (p2 - p0) * u
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u3)
+            },
+            0.0,
+            segments as f32,
+            segments * 4,
+            tolerance.chord,
+        )
+    }
+
+    /// 节点向量无效时生成两端固定的均匀节点向量
+    fn valid_knots(&self, degree: usize) -> Vec<f32> {
+        let n = self.control_points.len();
+        let expected = n + degree + 1;
+        if self.knots.len() == expected && self.knots.windows(2).all(|k| k[1] >= k[0]) {
+            return self.knots.clone();
+        }
+        (0..expected)
+            .map(|i| i.saturating_sub(degree).min(n - degree) as f32)
+            .collect()
+    }
+}
+
+impl EllipseEntity {
+    /// 按弦高误差自适应离散为折线
+    pub fn tessellate(&self, tolerance: &CurveTolerance) -> Vec<Vec3> {
+        let mut sweep = self.end_param - self.start_param;
+        if sweep <= 0.0 {
+            sweep += TAU;
+        }
+        tessellate(
+            |t| self.center + self.major_axis * t.cos() + self.minor_axis * t.sin(),
+            self.start_param,
+            self.start_param + sweep,
+            ((sweep / TAU * 16.0).ceil() as usize).max(2),
+            tolerance.chord,
+        )
+    }
+}
+
+/// 在 [t0, t1] 上均分 segments 段，再对弦高误差超过 tolerance 的段递归二分
+fn tessellate(
+    f: impl Fn(f32) -> Vec3,
+    t0: f32,
+    t1: f32,
+    segments: usize,
+    tolerance: f32,
+) -> Vec<Vec3> {
+    fn subdivide(
+        f: &impl Fn(f32) -> Vec3,
+        (ta, pa): (f32, Vec3),
+        (tb, pb): (f32, Vec3),
+        tolerance: f32,
+        depth: u32,
+        out: &mut Vec<Vec3>,
+    ) {
+        let tm = (ta + tb) * 0.5;
+        let pm = f(tm);
+        // 中点到弦的距离
+        let chord = pb - pa;
+        let deviation = if chord.length_squared() > f32::EPSILON {
+            (pm - pa).cross(chord).length() / chord.length()
+        } else {
+            pm.distance(pa)
+        };
+        if depth < MAX_SUBDIVISION_DEPTH && deviation > tolerance {
+            subdivide(f, (ta, pa), (tm, pm), tolerance, depth + 1, out);
+            subdivide(f, (tm, pm), (tb, pb), tolerance, depth + 1, out);
+        } else {
+            out.push(pb);
+        }
+    }
+
+    let segments = segments.max(1);
+    let step = (t1 - t0) / segments as f32;
+    let mut previous = (t0, f(t0));
+    let mut out = vec![previous.1];
+    for i in 1..=segments {
+        let t = if i == segments {
+            t1
+        } else {
+            t0 + step * i as f32
+        };
+        let next = (t, f(t));
+        subdivide(&f, previous, next, tolerance, 0, &mut out);
+        previous = next;
+    }
+    out
+}
+
+/// de Boor 算法计算 NURBS 曲线上参数 t 处的点（齐次坐标）
+fn eval_nurbs(degree: usize, points: &[Vec3], knots: &[f32], weights: &[f32], t: f32) -> Vec3 {
+    let n = points.len();
+    // 找到 t 所在的节点区间 [knots[k], knots[k + 1])
+    let mut k = degree;
+    while k < n - 1 && t >= knots[k + 1] {
+        k += 1;
+    }
+    let mut d: Vec<Vec4> = (0..=degree)
+        .map(|j| {
+            let p = points[j + k - degree];
+            let w = weights.get(j + k - degree).copied().unwrap_or(1.0);
+            (p * w).extend(w)
+        })
+        .collect();
+    for r in 1..=degree {
+        for j in (r..=degree).rev() {
+            let i = j + k - degree;
+            let denom = knots[i + degree + 1 - r] - knots[i];
+            let alpha = if denom.abs() > f32::EPSILON {
+                (t - knots[i]) / denom
+            } else {
+                0.0
+            };
+            d[j] = d[j - 1] * (1.0 - alpha) + d[j] * alpha;
+        }
+    }
+    let h = d[degree];
+    if h.w.abs() > f32::EPSILON {
+        h.truncate() / h.w
+    } else {
+        h.truncate()
+    }
+}
@@ -0,0 +1,515 @@
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use dxf::entities::{DimensionBase, EntityType};
+use dxf::enums::DimensionType;
+use dxf::{Drawing, Point};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use super::dxf_block::{BlockTable, CadNode, block_children};
+use super::dxf_renderer::{
+    ArcEntity, CadEntity, CadGeometry, LineEntity, PolylineEntity, TextEntity, TextHAlign,
+    TextVAlign, dxf_to_world,
+};
+use super::layer_panel::LayerTable;
+
+/// 标注类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DimensionKind {
+    /// 线性标注（水平、垂直或旋转）
+    Linear,
+    /// 对齐标注
+    Aligned,
+    /// 角度标注
+    Angular,
+    /// 半径标注
+    Radial,
+    /// 直径标注
+    Diameter,
+    /// 坐标标注，x_type 为 true 时标注 X 坐标
+    Ordinate { x_type: bool },
+}
+
+/// 标注实体数据（DIMENSION）
+///
+/// 标注的图形（尺寸界线、尺寸线、箭头和文字）在子实体中，子实体带有 BlockPart 标记。
+#[derive(Component, Debug, Clone)]
+pub struct DimensionEntity {
+    pub kind: DimensionKind,
+    /// 尺寸线位置（DXF 定义点 10）：线性/对齐为尺寸线上的点，角度为圆弧上的点，
+    /// 半径为圆心，直径为圆上远端的点，坐标为原点
+    pub dimension_point: Vec3,
+    /// 其余定义点：线性/对齐为两条尺寸界线的起点，角度为两条边上的点和顶点，
+    /// 半径/直径为圆上的点，坐标为标注点和引线终点
+    pub points: Vec<Vec3>,
+    /// 文字中点
+    pub text_position: Vec3,
+    /// 线性标注的尺寸线方向（弧度）
+    pub rotation: f32,
+    /// 测量值，角度标注为度
+    pub measurement: f32,
+    /// 文字替代，为空时显示测量值，其中的 <> 替换为测量值
+    pub text: String,
+    /// 标注样式名
+    pub style: String,
+    /// DXF 中存放标注图形的匿名块
+    pub block: String,
+}
+
+/// 重新生成标注图形时用到的标注样式参数
+#[derive(Debug, Clone, Copy)]
+pub struct DimStyleParams {
+    /// 全局比例（DIMSCALE）
+    pub scale: f32,
+    /// 箭头大小（DIMASZ）
+    pub arrow_size: f32,
+    /// 尺寸界线起点偏移（DIMEXO）
+    pub extension_offset: f32,
+    /// 尺寸界线超出尺寸线的长度（DIMEXE）
+    pub extension_extension: f32,
+    /// 文字高度（DIMTXT）
+    pub text_height: f32,
+}
+
+impl Default for DimStyleParams {
+    fn default() -> Self {
+        // AutoCAD STANDARD 样式的默认值
+        Self {
+            scale: 1.0,
+            arrow_size: 0.18,
+            extension_offset: 0.0625,
+            extension_extension: 0.18,
+            text_height: 0.18,
+        }
+    }
+}
+
+/// 按名称查找标注样式（不区分大小写）
+pub struct DimStyles {
+    styles: HashMap<String, DimStyleParams>,
+}
+
+impl DimStyles {
+    pub fn new(drawing: &Drawing) -> Self {
+        Self {
+            styles: drawing
+                .dim_styles()
+                .map(|style| {
+                    let params = DimStyleParams {
+                        // DIMSCALE 为 0 表示按布局缩放，这里按 1 处理
+                        scale: if style.dimensioning_scale_factor > 0.0 {
+                            style.dimensioning_scale_factor as f32
+                        } else {
+                            1.0
+                        },
+                        arrow_size: style.dimensioning_arrow_size as f32,
+                        extension_offset: style.dimension_extension_line_offset as f32,
+                        extension_extension: style.dimension_extension_line_extension as f32,
+                        text_height: style.dimensioning_text_height as f32,
+                    };
+                    (style.name.to_uppercase(), params)
+                })
+                .collect(),
+        }
+    }
+
+    fn get(&self, name: &str) -> DimStyleParams {
+        self.styles
+            .get(&name.to_uppercase())
+            .or_else(|| self.styles.get("STANDARD"))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// DXF 标注实体转换为标注数据，不是标注时返回 None
+pub fn dimension_from_dxf(specific: &EntityType) -> Option<DimensionEntity> {
+    let w = |p: &Point| dxf_to_world(p.x, p.y, p.z);
+    let (base, kind, dimension_point, points, rotation) = match specific {
+        // dxf 库把对齐标注也读为 RotatedDimension，按标注类型区分
+        EntityType::RotatedDimension(d) => {
+            let aligned = d.dimension_base.dimension_type == DimensionType::Aligned;
+            (
+                &d.dimension_base,
+                if aligned {
+                    DimensionKind::Aligned
+                } else {
+                    DimensionKind::Linear
+                },
+                w(&d.dimension_base.definition_point_1),
+                vec![w(&d.definition_point_2), w(&d.definition_point_3)],
+                if aligned {
+                    0.0
+                } else {
+                    d.rotation_angle.to_radians() as f32
+                },
+            )
+        }
+        EntityType::RadialDimension(d) => (
+            &d.dimension_base,
+            DimensionKind::Radial,
+            w(&d.dimension_base.definition_point_1),
+            vec![w(&d.definition_point_2)],
+            0.0,
+        ),
+        EntityType::DiameterDimension(d) => (
+            &d.dimension_base,
+            DimensionKind::Diameter,
+            w(&d.dimension_base.definition_point_1),
+            vec![w(&d.definition_point_2)],
+            0.0,
+        ),
+        EntityType::AngularThreePointDimension(d)
+            if d.dimension_base.dimension_type == DimensionType::Angular =>
+        {
+            // 两线角度标注：13-14 为第一条线，15-10 为第二条线，16 为圆弧上的点
+            let arc_point = w(&d.definition_point_5);
+            let points = two_line_angle(
+                [w(&d.definition_point_2), w(&d.definition_point_3)],
+                [
+                    w(&d.definition_point_4),
+                    w(&d.dimension_base.definition_point_1),
+                ],
+                arc_point,
+            )?;
+            (
+                &d.dimension_base,
+                DimensionKind::Angular,
+                arc_point,
+                points,
+                0.0,
+            )
+        }
+        EntityType::AngularThreePointDimension(d) => (
+            &d.dimension_base,
+            DimensionKind::Angular,
+            w(&d.dimension_base.definition_point_1),
+            vec![
+                w(&d.definition_point_2),
+                w(&d.definition_point_3),
+                w(&d.definition_point_4),
+            ],
+            0.0,
+        ),
+        EntityType::OrdinateDimension(d) => (
+            &d.dimension_base,
+            DimensionKind::Ordinate {
+                x_type: d.dimension_base.is_ordinate_x_type,
+            },
+            w(&d.dimension_base.definition_point_1),
+            vec![w(&d.definition_point_2), w(&d.definition_point_3)],
+            0.0,
+        ),
+        _ => return None,
+    };
+
+    let mut dimension = dimension_entity(base, kind, dimension_point, points, rotation);
+    if base.actual_measurement > 0.0 && kind != DimensionKind::Angular {
+        dimension.measurement = base.actual_measurement as f32;
+    }
+    Some(dimension)
+}
+
+/// 公共的标注数据，测量值按定义点计算
+fn dimension_entity(
+    base: &DimensionBase,
+    kind: DimensionKind,
+    dimension_point: Vec3,
+    points: Vec<Vec3>,
+    rotation: f32,
+) -> DimensionEntity {
+    let mut dimension = DimensionEntity {
+        kind,
+        dimension_point,
+        points,
+        text_position: dxf_to_world(
+            base.text_mid_point.x,
+            base.text_mid_point.y,
+            base.text_mid_point.z,
+        ),
+        rotation,
+        measurement: 0.0,
+        text: base.text.clone(),
+        style: base.dimension_style_name.clone(),
+        block: base.block_name.clone(),
+    };
+    dimension.measurement = dimension.measure();
+    dimension
+}
+
+/// 两线角度标注转换为 [第一条边上的点, 第二条边上的点, 顶点]
+fn two_line_angle(line1: [Vec3; 2], line2: [Vec3; 2], arc_point: Vec3) -> Option<Vec<Vec3>> {
+    let (a1, a2) = (flat(line1[0]), flat(line1[1]));
+    let (b1, b2) = (flat(line2[0]), flat(line2[1]));
+    let (d1, d2) = (a2 - a1, b2 - b1);
+    let denom = d1.perp_dot(d2);
+    if denom.abs() < f32::EPSILON {
+        return None;
+    }
+    let vertex = a1 + d1 * (b1 - a1).perp_dot(d2) / denom;
+    // 圆弧上的点所在的象限决定两条边的方向
+    let p = flat(arc_point) - vertex;
+    let alpha = p.perp_dot(d2) / denom;
+    let beta = d1.perp_dot(p) / denom;
+    let ray1 = d1.normalize() * alpha.signum();
+    let ray2 = d2.normalize() * beta.signum();
+    let reach = |ray: Vec2, ends: [Vec2; 2]| {
+        ends.iter()
+            .map(|e| (*e - vertex).dot(ray))
+            .fold(0.0f32, f32::max)
+    };
+    let y = arc_point.y;
+    Some(vec![
+        lift(vertex + ray1 * reach(ray1, [a1, a2]), y),
+        lift(vertex + ray2 * reach(ray2, [b1, b2]), y),
+        lift(vertex, y),
+    ])
+}
+
+impl DimensionEntity {
+    /// 根据定义点计算测量值
+    pub fn measure(&self) -> f32 {
+        let point = |i: usize| self.points.get(i).copied().unwrap_or(self.dimension_point);
+        match self.kind {
+            DimensionKind::Linear => {
+                let dir = Vec2::from_angle(self.rotation);
+                (flat(point(1)) - flat(point(0))).dot(dir).abs()
+            }
+            DimensionKind::Aligned => flat(point(0)).distance(flat(point(1))),
+            DimensionKind::Angular => self.angle_arc().2.to_degrees(),
+            DimensionKind::Radial | DimensionKind::Diameter => {
+                flat(self.dimension_point).distance(flat(point(0)))
+            }
+            DimensionKind::Ordinate { x_type } => {
+                let offset = flat(point(0)) - flat(self.dimension_point);
+                if x_type { offset.x } else { offset.y }.abs()
+            }
+        }
+    }
+
+    /// 角度标注的圆弧：(起始角, 终止角, 角度)，逆时针
+    fn angle_arc(&self) -> (f32, f32, f32) {
+        let [p1, p2, vertex] =
+            [0, 1, 2].map(|i| flat(self.points.get(i).copied().unwrap_or(self.dimension_point)));
+        let angle = |p: Vec2| (p - vertex).to_angle();
+        let (a1, a2) = (angle(p1), angle(p2));
+        let sweep = (a2 - a1).rem_euclid(TAU);
+        // 圆弧上的点不在 a1 到 a2 之间时，标注的是另一侧的角
+        if (angle(flat(self.dimension_point)) - a1).rem_euclid(TAU) <= sweep {
+            (a1, a2, sweep)
+        } else {
+            (a2, a1, TAU - sweep)
+        }
+    }
+
+    /// 显示的文字，None 表示不显示
+    fn display_text(&self) -> Option<String> {
+        let value = match self.kind {
+            DimensionKind::Angular => format!("{}°", format_number(self.measurement, 0)),
+            DimensionKind::Radial => format!("R{}", format_number(self.measurement, 2)),
+            DimensionKind::Diameter => format!("%%c{}", format_number(self.measurement, 2)),
+            _ => format_number(self.measurement, 2),
+        };
+        match self.text.as_str() {
+            "" => Some(value),
+            // 单个空格表示不显示文字
+            " " => None,
+            text => Some(text.replace("<>", &value)),
+        }
+    }
+}
+
+/// 生成标注节点：有匿名块时使用块中的图形，否则按定义点和标注样式重新生成
+pub fn dimension_node(
+    blocks: &BlockTable,
+    layers: &LayerTable,
+    styles: &DimStyles,
+    cad: CadEntity,
+    dimension: DimensionEntity,
+) -> CadNode {
+    let children = match blocks
+        .get(&dimension.block)
+        .filter(|block| !block.entities.is_empty())
+    {
+        // 匿名块中的图形已经是世界坐标
+        Some(block) => block_children(blocks, layers, block, &cad, Affine3A::IDENTITY, 0),
+        None => dimension_graphics(&dimension, &styles.get(&dimension.style))
+            .into_iter()
+            .map(|geometry| CadNode {
+                cad: CadEntity {
+                    entity_type: geometry.entity_type(),
+                    selectable: false,
+                    ..cad.clone()
+                },
+                geometry,
+                children: Vec::new(),
+            })
+            .collect(),
+    };
+    CadNode {
+        cad,
+        geometry: CadGeometry::Dimension(dimension),
+        children,
+    }
+}
+
+/// 按定义点生成标注图形（在 DXF XY 平面，即世界 XZ 平面内计算）
+fn dimension_graphics(dimension: &DimensionEntity, style: &DimStyleParams) -> Vec<CadGeometry> {
+    let y = dimension.dimension_point.y;
+    let arrow_size = style.arrow_size * style.scale;
+    let offset = style.extension_offset * style.scale;
+    let extension = style.extension_extension * style.scale;
+    let point = |i: usize| {
+        flat(
+            dimension
+                .points
+                .get(i)
+                .copied()
+                .unwrap_or(dimension.dimension_point),
+        )
+    };
+    let line = |start: Vec2, end: Vec2| {
+        CadGeometry::Line(LineEntity {
+            start: lift(start, y),
+            end: lift(end, y),
+        })
+    };
+    let arrow = |tip: Vec2, dir: Vec2| {
+        let back = tip - dir * arrow_size;
+        let side = dir.perp() * arrow_size / 6.0;
+        CadGeometry::Polyline(PolylineEntity::new(
+            vec![lift(tip, y), lift(back + side, y), lift(back - side, y)],
+            true,
+        ))
+    };
+
+    let mut graphics = Vec::new();
+    let mut text_rotation = 0.0;
+    match dimension.kind {
+        DimensionKind::Linear | DimensionKind::Aligned => {
+            let (p1, p2) = (point(0), point(1));
+            let dir = if dimension.kind == DimensionKind::Linear {
+                Vec2::from_angle(dimension.rotation)
+            } else {
+                (p2 - p1).normalize_or(Vec2::X)
+            };
+            let d = flat(dimension.dimension_point);
+            let a = d + dir * (p1 - d).dot(dir);
+            let b = d + dir * (p2 - d).dot(dir);
+            // 尺寸界线：从定义点偏移 DIMEXO 开始，超出尺寸线 DIMEXE
+            for (origin, foot) in [(p1, a), (p2, b)] {
+                let n = (foot - origin).normalize_or_zero();
+                if n != Vec2::ZERO {
+                    graphics.push(line(origin + n * offset, foot + n * extension));
+                }
+            }
+            graphics.push(line(a, b));
+            let ab = (b - a).normalize_or(dir);
+            graphics.push(arrow(a, -ab));
+            graphics.push(arrow(b, ab));
+            text_rotation = readable_angle(dir.to_angle());
+        }
+        DimensionKind::Angular => {
+            let (p1, p2, vertex) = (point(0), point(1), point(2));
+            let radius = flat(dimension.dimension_point).distance(vertex);
+            let (start, end, _) = dimension.angle_arc();
+            for p in [p1, p2] {
+                let length = p.distance(vertex);
+                let u = (p - vertex).normalize_or_zero();
+                if radius > length + offset && u != Vec2::ZERO {
+                    graphics.push(line(
+                        vertex + u * (length + offset),
+                        vertex + u * (radius + extension),
+                    ));
+                }
+            }
+            graphics.push(CadGeometry::Arc(ArcEntity {
+                center: lift(vertex, y),
+                radius,
+                start_angle: start,
+                end_angle: end,
+            }));
+            // 箭头沿圆弧切线指向两端
+            graphics.push(arrow(
+                vertex + Vec2::from_angle(start) * radius,
+                -Vec2::from_angle(start).perp(),
+            ));
+            graphics.push(arrow(
+                vertex + Vec2::from_angle(end) * radius,
+                Vec2::from_angle(end).perp(),
+            ));
+        }
+        DimensionKind::Radial => {
+            let (center, p) = (flat(dimension.dimension_point), point(0));
+            let u = (p - center).normalize_or(Vec2::X);
+            let text_distance = flat(dimension.text_position).distance(center);
+            // 文字在圆外时尺寸线延伸到文字处
+            if text_distance <= center.distance(p) {
+                graphics.push(line(center, p));
+            } else {
+                graphics.push(line(p, center + u * text_distance));
+            }
+            graphics.push(arrow(p, u));
+        }
+        DimensionKind::Diameter => {
+            let (far, p) = (flat(dimension.dimension_point), point(0));
+            let u = (p - far).normalize_or(Vec2::X);
+            graphics.push(line(far, p));
+            graphics.push(arrow(p, u));
+            graphics.push(arrow(far, -u));
+        }
+        DimensionKind::Ordinate { .. } => {
+            let (feature, leader_end) = (point(0), point(1));
+            let u = (leader_end - feature).normalize_or_zero();
+            graphics.push(line(feature + u * offset, leader_end));
+        }
+    }
+
+    if let Some(content) = dimension.display_text() {
+        graphics.push(CadGeometry::Text(TextEntity {
+            position: dimension.text_position,
+            height: style.text_height * style.scale,
+            rotation: text_rotation,
+            h_align: TextHAlign::Center,
+            v_align: TextVAlign::Middle,
+            content,
+            is_mtext: true,
+            wrap_width: None,
+            fit_width: None,
+        }));
+    }
+    graphics
+}
+
+/// 世界坐标投影到 DXF XY 平面
+fn flat(v: Vec3) -> Vec2 {
+    Vec2::new(v.x, v.z)
+}
+
+/// DXF XY 平面上的点转换回世界坐标，y 为高度
+fn lift(v: Vec2, y: f32) -> Vec3 {
+    Vec3::new(v.x, y, v.y)
+}
+
+/// 文字方向转换到 (-90°, 90°]，保证文字不倒置
+fn readable_angle(angle: f32) -> f32 {
+    let mut angle = angle.rem_euclid(TAU);
+    if angle > FRAC_PI_2 + 1e-4 {
+        angle -= PI;
+    }
+    if angle > FRAC_PI_2 + 1e-4 {
+        angle -= PI;
+    }
+    angle
+}
+
+/// 按小数位数格式化并去掉末尾的 0
+fn format_number(value: f32, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
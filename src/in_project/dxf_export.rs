@@ -0,0 +1,846 @@
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use dxf::entities::{
+    AngularThreePointDimension, Arc, Circle, DiameterDimension, DimensionBase, Ellipse,
+    Entity as DxfEntity, EntityType, Insert, Line, LwPolyline, MText, OrdinateDimension,
+    RadialDimension, RotatedDimension, Solid, Spline, Text,
+};
+use dxf::enums::{
+    AcadVersion, AttachmentPoint, DimensionType, HorizontalTextJustification,
+    VerticalTextJustification,
+};
+use dxf::tables::Layer;
+use dxf::{Drawing, LwPolylineVertex, Vector};
+use std::collections::HashSet;
+use std::f64::consts::{FRAC_PI_2, TAU};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+
+use super::dxf_block::{BlockPart, transform_geometry};
+use super::dxf_codes::DxfCodes;
+use super::dxf_color::CadColor;
+use super::dxf_curve::{EllipseEntity, SplineEntity};
+use super::dxf_dimension::{DimensionEntity, DimensionKind};
+use super::dxf_hatch::HatchEntity;
+use super::dxf_ocs::entity_ocs;
+use super::dxf_renderer::{
+    CadEntity, CadGeometry, CadGeometryQuery, DxfSource, PolylineEntity, SourceDrawing, TextEntity,
+    TextHAlign, TextVAlign, world_to_dxf,
+};
+
+static EXPORT_DIALOG_RESULT: Mutex<Option<Option<PathBuf>>> = Mutex::new(None);
+
+/// 导出 DXF 消息
+#[derive(Message, Debug)]
+pub struct ExportDxfMessage {
+    pub path: PathBuf,
+}
+
+/// 导出对话框状态
+#[derive(Resource, Default)]
+pub struct ExportDxfDialog {
+    is_open: bool,
+    /// 上次导出时无法写出的填充数量，不为 0 时界面显示提示
+    pub skipped_hatches: usize,
+}
+
+impl ExportDxfDialog {
+    /// 打开导出对话框（已打开时忽略）
+    pub fn open(&mut self, directory: PathBuf) {
+        if self.is_open {
+            return;
+        }
+        self.is_open = true;
+        open_export_dialog_in_thread(directory);
+    }
+}
+
+/// 导出对话框系统 - 检查对话框结果并发出导出消息
+pub fn export_dialog_system(
+    mut dialog: ResMut<ExportDxfDialog>,
+    mut export_messages: MessageWriter<ExportDxfMessage>,
+) {
+    if !dialog.is_open {
+        return;
+    }
+    let Ok(mut guard) = EXPORT_DIALOG_RESULT.lock() else {
+        return;
+    };
+    let Some(result) = guard.take() else {
+        return;
+    };
+    dialog.is_open = false;
+    if let Some(mut path) = result {
+        if path.extension().is_none() {
+            path.set_extension("dxf");
+        }
+        export_messages.write(ExportDxfMessage { path });
+    }
+}
+
+/// 导出时可能被编辑器里的实体替换的 DXF 实体类型
+///
+/// 其余类型原样保留在源图纸中，保存时不会丢失。这些类型的实体也只有导入到编辑器中的才会替换，
+/// 没能转换的（例如找不到块定义的块参照）同样原样保留。
+fn is_imported_type(specific: &EntityType) -> bool {
+    matches!(
+        specific,
+        EntityType::Line(_)
+            | EntityType::Circle(_)
+            | EntityType::Arc(_)
+            | EntityType::LwPolyline(_)
+            | EntityType::Polyline(_)
+            | EntityType::Text(_)
+            | EntityType::MText(_)
+            | EntityType::Insert(_)
+            | EntityType::RotatedDimension(_)
+            | EntityType::RadialDimension(_)
+            | EntityType::DiameterDimension(_)
+            | EntityType::AngularThreePointDimension(_)
+            | EntityType::OrdinateDimension(_)
+            | EntityType::Spline(_)
+            | EntityType::Ellipse(_)
+            | EntityType::Solid(_)
+    )
+}
+
+/// DXF 导出系统 - 把 CAD 实体写回 DXF
+pub fn dxf_export_system(
+    mut messages: MessageReader<ExportDxfMessage>,
+    mut source: ResMut<SourceDrawing>,
+    mut dialog: ResMut<ExportDxfDialog>,
+    // 块参照的部件由块定义生成，不单独导出
+    cad_query: Query<(&CadEntity, Option<&DxfSource>, CadGeometryQuery), Without<BlockPart>>,
+) {
+    for message in messages.read() {
+        // 没有源图纸时（例如新建的场景）导出为新图纸
+        let mut drawing = source.drawing().unwrap_or_else(Drawing::new);
+        // 导入的填充按原始组码写回
+        let codes = source
+            .bytes
+            .as_deref()
+            .map(DxfCodes::parse)
+            .unwrap_or_default();
+
+        // 导入到编辑器中的源实体（包括已删除的）由编辑器中的实体重新生成，其余原样保留
+        let owned: HashSet<u64> = cad_query
+            .iter()
+            .filter_map(|(_, dxf_source, _)| dxf_source.map(|s| s.0.common.handle.0))
+            .chain(source.imported.iter().copied())
+            .collect();
+        let kept: Vec<DxfEntity> = drawing
+            .entities()
+            .filter(|ent| !is_imported_type(&ent.specific) || !owned.contains(&ent.common.handle.0))
+            .cloned()
+            .collect();
+        // 从尾部删除，避免移动元素
+        for index in (0..drawing.entities().count()).rev() {
+            drawing.remove_entity(index);
+        }
+        for ent in kept {
+            drawing.add_entity(ent);
+        }
+
+        let mut layers: HashSet<String> = drawing.layers().map(|l| l.name.clone()).collect();
+        let mut count = 0;
+        let mut skipped = 0;
+        let mut hatches = Vec::new();
+        for (cad, dxf_source, components) in &cad_query {
+            let Some(geometry) = components.geometry() else {
+                continue;
+            };
+            let Some(ent) = to_dxf_entity(cad, &geometry, dxf_source.map(|s| &s.0)) else {
+                // dxf 库不能写出 HATCH，几何数据没有改变的导入填充按原始组码写回
+                let original = match &geometry {
+                    CadGeometry::Hatch(hatch) => codes.hatches.source(hatch),
+                    _ => None,
+                };
+                match original {
+                    Some(original) if drawing.header.version >= AcadVersion::R13 => {
+                        hatches.push(hatch_codes(cad, original, &mut drawing));
+                    }
+                    _ => skipped += 1,
+                }
+                continue;
+            };
+            if layers.insert(ent.common.layer.clone()) {
+                drawing.add_layer(Layer {
+                    name: ent.common.layer.clone(),
+                    ..Default::default()
+                });
+            }
+            drawing.add_entity(ent);
+            count += 1;
+        }
+        for (hatch, _) in &hatches {
+            if layers.insert(hatch.clone()) {
+                drawing.add_layer(Layer {
+                    name: hatch.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let mut bytes = Vec::new();
+        let result = drawing
+            .save(&mut bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                let text = String::from_utf8_lossy(&bytes);
+                let text = splice_entities(&text, &hatches, drawing.header.version);
+                std::fs::write(&message.path, text).map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => println!(
+                "DXF 导出完成: {:?}, 实体: {}, 填充: {}, 无法写出的填充: {}",
+                message.path,
+                count,
+                hatches.len(),
+                skipped
+            ),
+            Err(e) => eprintln!("导出DXF失败 {:?}: {}", message.path, e),
+        }
+        dialog.skipped_hatches = skipped;
+    }
+}
+
+/// 按编辑器中的图层和颜色修改导入填充的原始组码，返回图层名和组码
+///
+/// 句柄重新分配；所有者、扩展字典和关联的边界对象在新图纸中不存在，一并去掉。
+fn hatch_codes(
+    cad: &CadEntity,
+    original: &[(i32, String)],
+    drawing: &mut Drawing,
+) -> (String, Vec<(i32, String)>) {
+    let handle = drawing.header.next_available_handle;
+    drawing.header.next_available_handle = handle.next_handle_value();
+    let mut common = dxf::entities::EntityCommon::default();
+    cad.color_spec.to_dxf(&mut common);
+    let color = match common.color.index() {
+        Some(index) => index as i16,
+        None if common.color.is_by_block() => 0,
+        None => 256,
+    };
+
+    let mut codes = vec![(0, "HATCH".to_string()), (5, format!("{:X}", handle.0))];
+    // 通用组码在第一个 AcDbEntity 之外的子类标记之前
+    let mut in_common = true;
+    let mut in_group = false;
+    for (code, value) in original {
+        if *code == 100 && value != "AcDbEntity" {
+            in_common = false;
+        }
+        match code {
+            102 => in_group = value.starts_with('{'),
+            _ if in_group => {}
+            5 | 330 | 360 => {}
+            8 if in_common => {
+                codes.push((8, cad.layer.clone()));
+                codes.push((62, color.to_string()));
+                if common.color_24_bit != 0 {
+                    codes.push((420, common.color_24_bit.to_string()));
+                }
+            }
+            62 | 420 if in_common => {}
+            // 不再关联边界对象
+            71 | 97 => codes.push((*code, "0".to_string())),
+            _ => codes.push((*code, value.clone())),
+        }
+    }
+    (cad.layer.clone(), codes)
+}
+
+/// 把组码插入到导出文本的 ENTITIES 段末尾
+///
+/// 组码的格式和 dxf 库写出的一致；R2004 及更早的版本把非 ASCII 字符转义为 \U+XXXX。
+fn splice_entities(
+    text: &str,
+    entities: &[(String, Vec<(i32, String)>)],
+    version: AcadVersion,
+) -> String {
+    let Some(start) = text.find("  2\r\nENTITIES\r\n") else {
+        return text.to_string();
+    };
+    let Some(end) = text[start..].find("  0\r\nENDSEC\r\n").map(|n| start + n) else {
+        return text.to_string();
+    };
+    let mut out = String::with_capacity(text.len());
+    out.push_str(&text[..end]);
+    for (_, codes) in entities {
+        for (code, value) in codes {
+            out.push_str(&format!("{: >3}\r\n", code));
+            if version <= AcadVersion::R2004 {
+                for c in value.chars() {
+                    if c.is_ascii() {
+                        out.push(c);
+                    } else {
+                        out.push_str(&format!("\\U+{:04X}", c as u32));
+                    }
+                }
+            } else {
+                out.push_str(value);
+            }
+            out.push_str("\r\n");
+        }
+    }
+    out.push_str(&text[end..]);
+    out
+}
+
+/// CAD 实体转换为 DXF 实体；有源实体时在其基础上修改几何数据
+///
+/// dxf 库不能写出 HATCH，填充（源实体是 SOLID 的除外）返回 None。
+fn to_dxf_entity(
+    cad: &CadEntity,
+    geometry: &CadGeometry,
+    source: Option<&DxfEntity>,
+) -> Option<DxfEntity> {
+    // 沿用源实体的拉伸方向时，几何数据要先变换回源实体的 OCS
+    let ocs_geometry = source.and_then(|src| {
+        let ocs = entity_ocs(&src.specific)?;
+        keeps_ocs(geometry, &src.specific)
+            .then(|| transform_geometry(geometry.clone(), &ocs.inverse()))
+    });
+    let geometry = ocs_geometry.as_ref().unwrap_or(geometry);
+    let specific = match geometry {
+        CadGeometry::Line(line) => EntityType::Line(Line {
+            p1: world_to_dxf(line.start),
+            p2: world_to_dxf(line.end),
+            ..match source.map(|s| &s.specific) {
+                Some(EntityType::Line(src)) => src.clone(),
+                _ => Line::default(),
+            }
+        }),
+        CadGeometry::Circle(circle) => EntityType::Circle(Circle {
+            center: world_to_dxf(circle.center),
+            radius: circle.radius as f64,
+            ..match source.map(|s| &s.specific) {
+                Some(EntityType::Circle(src)) => src.clone(),
+                _ => Circle::default(),
+            }
+        }),
+        CadGeometry::Arc(arc) => EntityType::Arc(Arc {
+            center: world_to_dxf(arc.center),
+            radius: arc.radius as f64,
+            start_angle: (arc.start_angle as f64).to_degrees(),
+            end_angle: (arc.end_angle as f64).to_degrees(),
+            ..match source.map(|s| &s.specific) {
+                Some(EntityType::Arc(src)) => src.clone(),
+                _ => Arc::default(),
+            }
+        }),
+        CadGeometry::Polyline(pl) => polyline_to_dxf(pl, source.map(|s| &s.specific)),
+        CadGeometry::Text(text) if text.is_mtext => {
+            EntityType::MText(mtext_to_dxf(text, source.map(|s| &s.specific)))
+        }
+        CadGeometry::Text(text) => EntityType::Text(text_to_dxf(text, source.map(|s| &s.specific))),
+        CadGeometry::Insert(insert) => EntityType::Insert(Insert {
+            name: insert.block.clone(),
+            location: world_to_dxf(insert.position),
+            x_scale_factor: insert.scale.x as f64,
+            y_scale_factor: insert.scale.y as f64,
+            z_scale_factor: insert.scale.z as f64,
+            rotation: (insert.rotation as f64).to_degrees(),
+            column_count: i16::try_from(insert.columns).unwrap_or(i16::MAX),
+            row_count: i16::try_from(insert.rows).unwrap_or(i16::MAX),
+            column_spacing: insert.column_spacing as f64,
+            row_spacing: insert.row_spacing as f64,
+            ..match source.map(|s| &s.specific) {
+                Some(EntityType::Insert(src)) => src.clone(),
+                _ => Insert::default(),
+            }
+        }),
+        CadGeometry::Dimension(dimension) => {
+            dimension_to_dxf(dimension, source.map(|s| &s.specific))
+        }
+        CadGeometry::Spline(spline) => {
+            EntityType::Spline(spline_to_dxf(spline, source.map(|s| &s.specific)))
+        }
+        CadGeometry::Ellipse(ellipse) => EntityType::Ellipse(ellipse_to_dxf(ellipse)),
+        // 源实体是 SOLID 时写回 SOLID
+        CadGeometry::Hatch(hatch) => match source.map(|s| &s.specific) {
+            Some(EntityType::Solid(src)) => EntityType::Solid(solid_to_dxf(hatch, src)?),
+            _ => return None,
+        },
+    };
+
+    let mut ent = match source {
+        Some(src) => DxfEntity {
+            common: src.common.clone(),
+            specific,
+        },
+        // 新绘制的实体写出创建时的线型比例（CELTSCALE）
+        None => {
+            let mut ent = DxfEntity::new(specific);
+            ent.common.line_type_scale = cad.line_type_scale as f64;
+            ent
+        }
+    };
+    ent.common.layer = cad.layer.clone();
+    // LWPOLYLINE 的标高（38 组码）存放在公共数据中
+    if let (CadGeometry::Polyline(pl), EntityType::LwPolyline(_)) = (geometry, &ent.specific) {
+        ent.common.elevation = pl.vertices.first().map_or(0.0, |v| v.y as f64);
+    }
+    // 颜色没有改变时保留源实体的颜色值
+    if CadColor::from_dxf(&ent.common) != cad.color_spec {
+        cad.color_spec.to_dxf(&mut ent.common);
+    }
+    Some(ent)
+}
+
+/// 导出的实体是否以源实体为基础（从而保留源实体的拉伸方向）
+fn keeps_ocs(geometry: &CadGeometry, source: &EntityType) -> bool {
+    matches!(
+        (geometry, source),
+        (CadGeometry::Circle(_), EntityType::Circle(_))
+            | (CadGeometry::Arc(_), EntityType::Arc(_))
+            | (
+                CadGeometry::Polyline(_),
+                EntityType::LwPolyline(_) | EntityType::Polyline(_)
+            )
+            | (CadGeometry::Insert(_), EntityType::Insert(_))
+            | (CadGeometry::Hatch(_), EntityType::Solid(_))
+    ) || matches!((geometry, source), (CadGeometry::Text(text), EntityType::Text(_)) if !text.is_mtext)
+}
+
+/// 多段线：源实体是顶点数相同的 POLYLINE 时原样更新顶点，否则写为 LWPOLYLINE
+fn polyline_to_dxf(pl: &PolylineEntity, source: Option<&EntityType>) -> EntityType {
+    if let Some(EntityType::Polyline(src)) = source
+        && src.vertices().count() == pl.vertices.len()
+    {
+        let mut polyline = src.clone();
+        let elevation = polyline.location.z;
+        for (index, (vertex, v)) in polyline.vertices_mut().zip(&pl.vertices).enumerate() {
+            vertex.location = world_to_dxf(*v);
+            vertex.location.z -= elevation;
+            vertex.bulge = pl.bulge(index) as f64;
+            [vertex.starting_width, vertex.ending_width] = pl.width(index).map(|w| w as f64);
+        }
+        polyline.set_is_closed(pl.closed);
+        return EntityType::Polyline(polyline);
+    }
+
+    let mut lwpolyline = match source {
+        Some(EntityType::LwPolyline(src)) => src.clone(),
+        // 顶点数变化的二维多段线改写为 LWPOLYLINE，保留拉伸方向
+        Some(EntityType::Polyline(src)) if !src.is_3d_polyline() => LwPolyline {
+            extrusion_direction: src.normal.clone(),
+            ..Default::default()
+        },
+        _ => LwPolyline::default(),
+    };
+    // 宽度按顶点写出
+    lwpolyline.constant_width = 0.0;
+    lwpolyline.vertices = pl
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(index, v)| {
+            let p = world_to_dxf(*v);
+            let [starting_width, ending_width] = pl.width(index).map(|w| w as f64);
+            LwPolylineVertex {
+                x: p.x,
+                y: p.y,
+                bulge: pl.bulge(index) as f64,
+                starting_width,
+                ending_width,
+                ..Default::default()
+            }
+        })
+        .collect();
+    lwpolyline.set_is_closed(pl.closed);
+    EntityType::LwPolyline(lwpolyline)
+}
+
+/// SOLID：边界按 1-2-4-3 的顺序保存角点，三角形时第 3、4 点重合
+fn solid_to_dxf(hatch: &HatchEntity, source: &Solid) -> Option<Solid> {
+    let corners: Vec<_> = hatch
+        .loops
+        .first()?
+        .iter()
+        .map(|v| world_to_dxf(*v))
+        .collect();
+    let (third, fourth) = match corners.as_slice() {
+        [_, _, c] => (c.clone(), c.clone()),
+        [_, _, d, c] => (c.clone(), d.clone()),
+        _ => return None,
+    };
+    Some(Solid {
+        first_corner: corners[0].clone(),
+        second_corner: corners[1].clone(),
+        third_corner: third,
+        fourth_corner: fourth,
+        ..source.clone()
+    })
+}
+
+/// 单行文字：按对齐方式写回对齐点
+fn text_to_dxf(text: &TextEntity, source: Option<&EntityType>) -> Text {
+    let mut t = match source {
+        Some(EntityType::Text(src)) => src.clone(),
+        _ => Text::default(),
+    };
+    t.value = text.content.clone();
+    t.text_height = text.height as f64;
+    t.rotation = (text.rotation as f64).to_degrees();
+    let position = world_to_dxf(text.position);
+
+    // 对齐/布满：第二对齐点在文字末端
+    if let Some(fit_width) = text.fit_width {
+        if !matches!(
+            t.horizontal_text_justification,
+            HorizontalTextJustification::Aligned | HorizontalTextJustification::Fit
+        ) {
+            t.horizontal_text_justification = HorizontalTextJustification::Fit;
+        }
+        let (sin, cos) = (text.rotation as f64).sin_cos();
+        t.second_alignment_point = dxf::Point::new(
+            position.x + cos * fit_width as f64,
+            position.y + sin * fit_width as f64,
+            position.z,
+        );
+        t.location = position;
+        return t;
+    }
+
+    t.horizontal_text_justification = match (text.h_align, text.v_align) {
+        (TextHAlign::Center, TextVAlign::Middle)
+            if t.horizontal_text_justification == HorizontalTextJustification::Middle =>
+        {
+            HorizontalTextJustification::Middle
+        }
+        (TextHAlign::Left, _) => HorizontalTextJustification::Left,
+        (TextHAlign::Center, _) => HorizontalTextJustification::Center,
+        (TextHAlign::Right, _) => HorizontalTextJustification::Right,
+    };
+    t.vertical_text_justification = match text.v_align {
+        TextVAlign::Baseline => VerticalTextJustification::Baseline,
+        TextVAlign::Bottom => VerticalTextJustification::Bottom,
+        TextVAlign::Middle => VerticalTextJustification::Middle,
+        TextVAlign::Top => VerticalTextJustification::Top,
+    };
+    if t.horizontal_text_justification == HorizontalTextJustification::Middle {
+        t.vertical_text_justification = VerticalTextJustification::Baseline;
+    }
+    // 非默认对齐时以第二对齐点定位，第一对齐点由 CAD 程序重新计算
+    if text.h_align != TextHAlign::Left || text.v_align != TextVAlign::Baseline {
+        t.second_alignment_point = position.clone();
+    }
+    t.location = position;
+    t
+}
+
+/// 多行文字
+fn mtext_to_dxf(text: &TextEntity, source: Option<&EntityType>) -> MText {
+    let mut t = match source {
+        Some(EntityType::MText(src)) => src.clone(),
+        _ => MText::default(),
+    };
+    t.insertion_point = world_to_dxf(text.position);
+    t.initial_text_height = text.height as f64;
+    t.reference_rectangle_width = text.wrap_width.unwrap_or(0.0) as f64;
+    t.attachment_point = match (text.v_align, text.h_align) {
+        (TextVAlign::Top, TextHAlign::Left) => AttachmentPoint::TopLeft,
+        (TextVAlign::Top, TextHAlign::Center) => AttachmentPoint::TopCenter,
+        (TextVAlign::Top, TextHAlign::Right) => AttachmentPoint::TopRight,
+        (TextVAlign::Middle, TextHAlign::Left) => AttachmentPoint::MiddleLeft,
+        (TextVAlign::Middle, TextHAlign::Center) => AttachmentPoint::MiddleCenter,
+        (TextVAlign::Middle, TextHAlign::Right) => AttachmentPoint::MiddleRight,
+        (_, TextHAlign::Left) => AttachmentPoint::BottomLeft,
+        (_, TextHAlign::Center) => AttachmentPoint::BottomCenter,
+        (_, TextHAlign::Right) => AttachmentPoint::BottomRight,
+    };
+    t.extended_text.clear();
+    t.text = text.content.clone();
+    let (sin, cos) = (text.rotation as f64).sin_cos();
+    t.x_axis_direction = Vector::new(cos, sin, 0.0);
+    t.rotation_angle = text.rotation as f64;
+    t
+}
+
+/// 样条曲线
+fn spline_to_dxf(spline: &SplineEntity, source: Option<&EntityType>) -> Spline {
+    let mut s = match source {
+        Some(EntityType::Spline(src)) => src.clone(),
+        _ => Spline::default(),
+    };
+    s.degree_of_curve = spline.degree as i32;
+    s.control_points = spline
+        .control_points
+        .iter()
+        .map(|v| world_to_dxf(*v))
+        .collect();
+    s.knot_values = spline.knots.iter().map(|k| *k as f64).collect();
+    s.weight_values = spline.weights.iter().map(|w| *w as f64).collect();
+    s.fit_points = spline.fit_points.iter().map(|v| world_to_dxf(*v)).collect();
+    s.set_is_closed(spline.closed);
+    s.set_is_rational(!spline.weights.is_empty());
+    s
+}
+
+/// 椭圆：法向由长短轴确定
+///
+/// 块参照的非均匀缩放会让两个轴不再垂直，此时它们是一对共轭半径 a、b，
+/// 参数 t 满足 tan 2t = 2a·b / (|a|² − |b|²) 处的半径才是主轴，起止参数随之减去 t。
+/// DXF 的长短轴比不能大于 1，短轴更长时两轴互换。
+fn ellipse_to_dxf(ellipse: &EllipseEntity) -> Ellipse {
+    let a = world_to_dxf(ellipse.major_axis);
+    let b = world_to_dxf(ellipse.minor_axis);
+    let (a, b) = (DVec3::new(a.x, a.y, a.z), DVec3::new(b.x, b.y, b.z));
+    let mut shift = 0.5 * (2.0 * a.dot(b)).atan2(a.length_squared() - b.length_squared());
+    let (sin, cos) = shift.sin_cos();
+    let mut major = a * cos + b * sin;
+    let mut minor = b * cos - a * sin;
+    if minor.length() > major.length() {
+        (major, minor) = (minor, -major);
+        shift += FRAC_PI_2;
+    }
+    let normal = major.cross(minor).normalize_or(DVec3::Z);
+
+    // 终止参数小于等于起始参数时跨过 0，与离散时的处理一致
+    let start = ellipse.start_param as f64;
+    let mut sweep = ellipse.end_param as f64 - start;
+    if sweep <= 0.0 {
+        sweep += TAU;
+    }
+    let (start_parameter, end_parameter) = if sweep >= TAU - 1e-6 {
+        (0.0, TAU)
+    } else {
+        let start = (start - shift).rem_euclid(TAU);
+        (start, (start + sweep).rem_euclid(TAU))
+    };
+    Ellipse {
+        center: world_to_dxf(ellipse.center),
+        major_axis: Vector::new(major.x, major.y, major.z),
+        normal: Vector::new(normal.x, normal.y, normal.z),
+        minor_axis_ratio: minor.length() / major.length().max(f64::EPSILON),
+        start_parameter,
+        end_parameter,
+    }
+}
+
+/// 标注：写回定义点，标注图形沿用源图纸中的匿名块
+fn dimension_to_dxf(dimension: &DimensionEntity, source: Option<&EntityType>) -> EntityType {
+    let point = |i: usize| {
+        world_to_dxf(
+            dimension
+                .points
+                .get(i)
+                .copied()
+                .unwrap_or(dimension.dimension_point),
+        )
+    };
+    let mut base = match source {
+        Some(EntityType::RotatedDimension(d)) => d.dimension_base.clone(),
+        Some(EntityType::RadialDimension(d)) => d.dimension_base.clone(),
+        Some(EntityType::DiameterDimension(d)) => d.dimension_base.clone(),
+        Some(EntityType::AngularThreePointDimension(d)) => d.dimension_base.clone(),
+        Some(EntityType::OrdinateDimension(d)) => d.dimension_base.clone(),
+        _ => DimensionBase::default(),
+    };
+    base.definition_point_1 = world_to_dxf(dimension.dimension_point);
+    base.text_mid_point = world_to_dxf(dimension.text_position);
+    base.actual_measurement = match dimension.kind {
+        // 角度标注的测量值以弧度保存
+        DimensionKind::Angular => (dimension.measurement as f64).to_radians(),
+        _ => dimension.measurement as f64,
+    };
+    base.text = dimension.text.clone();
+    base.dimension_style_name = dimension.style.clone();
+    base.block_name = dimension.block.clone();
+
+    match dimension.kind {
+        DimensionKind::Linear => {
+            base.dimension_type = DimensionType::RotatedHorizontalOrVertical;
+            EntityType::RotatedDimension(RotatedDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                definition_point_3: point(1),
+                rotation_angle: (dimension.rotation as f64).to_degrees(),
+                ..match source {
+                    Some(EntityType::RotatedDimension(src)) => src.clone(),
+                    _ => RotatedDimension::default(),
+                }
+            })
+        }
+        // 对齐标注也写为 RotatedDimension，由标注类型区分
+        DimensionKind::Aligned => {
+            base.dimension_type = DimensionType::Aligned;
+            EntityType::RotatedDimension(RotatedDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                definition_point_3: point(1),
+                rotation_angle: 0.0,
+                ..match source {
+                    Some(EntityType::RotatedDimension(src)) => src.clone(),
+                    _ => RotatedDimension::default(),
+                }
+            })
+        }
+        // 两线角度标注也写为三点角度标注
+        DimensionKind::Angular => {
+            base.dimension_type = DimensionType::AngularThreePoint;
+            EntityType::AngularThreePointDimension(AngularThreePointDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                definition_point_3: point(1),
+                definition_point_4: point(2),
+                ..match source {
+                    Some(EntityType::AngularThreePointDimension(src)) => src.clone(),
+                    _ => AngularThreePointDimension::default(),
+                }
+            })
+        }
+        DimensionKind::Radial => {
+            base.dimension_type = DimensionType::Radius;
+            EntityType::RadialDimension(RadialDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                ..match source {
+                    Some(EntityType::RadialDimension(src)) => src.clone(),
+                    _ => RadialDimension::default(),
+                }
+            })
+        }
+        DimensionKind::Diameter => {
+            base.dimension_type = DimensionType::Diameter;
+            EntityType::DiameterDimension(DiameterDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                ..match source {
+                    Some(EntityType::DiameterDimension(src)) => src.clone(),
+                    _ => DiameterDimension::default(),
+                }
+            })
+        }
+        DimensionKind::Ordinate { x_type } => {
+            base.dimension_type = DimensionType::Ordinate;
+            base.is_ordinate_x_type = x_type;
+            EntityType::OrdinateDimension(OrdinateDimension {
+                dimension_base: base,
+                definition_point_2: point(0),
+                definition_point_3: point(1),
+            })
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_export_dialog_in_thread(directory: PathBuf) {
+    thread::spawn(move || {
+        let result = rfd::FileDialog::new()
+            .set_title("导出 DXF")
+            .set_directory(&directory)
+            .add_filter("DXF 文件", &["dxf"])
+            .set_file_name("未命名.dxf")
+            .save_file();
+
+        if let Ok(mut dialog_result) = EXPORT_DIALOG_RESULT.lock() {
+            *dialog_result = Some(result);
+        }
+    });
+}
+
+/// 浏览器中没有文件对话框，按取消处理，避免点击导出时崩溃
+#[cfg(target_arch = "wasm32")]
+fn open_export_dialog_in_thread(_directory: PathBuf) {
+    eprintln!("导出DXF失败: 浏览器中暂不支持导出 DXF");
+    if let Ok(mut dialog_result) = EXPORT_DIALOG_RESULT.lock() {
+        *dialog_result = Some(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn splice_hatch_before_entities_end() {
+        let text = "  0\r\nSECTION\r\n  2\r\nENTITIES\r\n  0\r\nENDSEC\r\n  0\r\nSECTION\r\n  2\r\nOBJECTS\r\n  0\r\nENDSEC\r\n";
+        let hatch = (
+            "墙".to_string(),
+            vec![(0, "HATCH".to_string()), (8, "墙".to_string())],
+        );
+        let spliced = splice_entities(text, std::slice::from_ref(&hatch), AcadVersion::R2000);
+        assert_eq!(
+            spliced,
+            "  0\r\nSECTION\r\n  2\r\nENTITIES\r\n  0\r\nHATCH\r\n  8\r\n\\U+5899\r\n  0\r\nENDSEC\r\n  0\r\nSECTION\r\n  2\r\nOBJECTS\r\n  0\r\nENDSEC\r\n"
+        );
+        let spliced = splice_entities(text, &[hatch], AcadVersion::R2007);
+        assert!(spliced.contains("  8\r\n墙\r\n  0\r\nENDSEC"));
+    }
+
+    /// DXF 椭圆在参数 t 处的点
+    fn ellipse_point(ellipse: &Ellipse, t: f64) -> DVec3 {
+        let v = |v: &Vector| DVec3::new(v.x, v.y, v.z);
+        let major = v(&ellipse.major_axis);
+        let minor = v(&ellipse.normal).cross(major) * ellipse.minor_axis_ratio;
+        let c = &ellipse.center;
+        DVec3::new(c.x, c.y, c.z) + major * t.cos() + minor * t.sin()
+    }
+
+    /// 编辑器椭圆在参数 t 处的点（DXF 坐标）
+    fn entity_point(ellipse: &EllipseEntity, t: f32) -> DVec3 {
+        let p = world_to_dxf(
+            ellipse.center + ellipse.major_axis * t.cos() + ellipse.minor_axis * t.sin(),
+        );
+        DVec3::new(p.x, p.y, p.z)
+    }
+
+    #[test]
+    fn ellipse_from_conjugate_axes() {
+        // 单位圆经过非均匀缩放和剪切后的椭圆弧，两个轴不垂直且短轴更长
+        let ellipse = EllipseEntity {
+            center: Vec3::new(1.0, 0.0, 2.0),
+            major_axis: Vec3::new(1.0, 0.0, 0.5),
+            minor_axis: Vec3::new(0.5, 0.0, 3.0),
+            start_param: 0.3,
+            end_param: 2.0,
+        };
+        let dxf = ellipse_to_dxf(&ellipse);
+        assert!(dxf.minor_axis_ratio <= 1.0);
+
+        // 主轴端点离圆心最远
+        let major = ellipse_point(&dxf, 0.0) - ellipse_point(&dxf, PI);
+        for i in 0..64 {
+            let t = i as f32 / 64.0 * TAU as f32;
+            let diameter = entity_point(&ellipse, t) - entity_point(&ellipse, t + PI as f32);
+            assert!(diameter.length() <= major.length() + 1e-4);
+        }
+
+        // 起止点不变
+        let close = |a: DVec3, b: DVec3| (a - b).length() < 1e-4;
+        assert!(close(
+            ellipse_point(&dxf, dxf.start_parameter),
+            entity_point(&ellipse, ellipse.start_param)
+        ));
+        assert!(close(
+            ellipse_point(&dxf, dxf.end_parameter),
+            entity_point(&ellipse, ellipse.end_param)
+        ));
+        // 中间的点也在同一段弧上
+        let mut sweep = dxf.end_parameter - dxf.start_parameter;
+        if sweep <= 0.0 {
+            sweep += TAU;
+        }
+        assert!(close(
+            ellipse_point(&dxf, dxf.start_parameter + sweep / 2.0),
+            entity_point(&ellipse, 1.15)
+        ));
+    }
+
+    #[test]
+    fn full_ellipse_stays_full() {
+        let ellipse = EllipseEntity {
+            center: Vec3::ZERO,
+            major_axis: Vec3::new(2.0, 0.0, 1.0),
+            minor_axis: Vec3::new(0.0, 0.0, 1.0),
+            start_param: 0.0,
+            end_param: TAU as f32,
+        };
+        let dxf = ellipse_to_dxf(&ellipse);
+        assert_eq!((dxf.start_parameter, dxf.end_parameter), (0.0, TAU));
+    }
+}
@@ -0,0 +1,557 @@
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use dxf::entities::EntityCommon;
+use dxf::{Handle, Vector};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use super::dxf_codes::parse_common;
+use super::dxf_curve::SplineEntity;
+use super::dxf_ocs::ocs_to_world;
+use super::dxf_renderer::{PolylineEntity, dxf_to_world};
+use super::dxf_tessellation::CurveTolerance;
+
+/// 圆弧和椭圆弧边界每整圈离散的段数
+const ARC_SEGMENTS: usize = 64;
+/// 一族图案线的条数超过这个值时不绘制该线族，避免缩小时生成过多线段
+const MAX_PATTERN_LINES: usize = 5000;
+/// 一条图案线上图案重复次数超过这个值时按实线绘制
+const MAX_DASHES: f32 = 1000.0;
+/// 点（长度为 0 的元素）按图案长度的这个比例绘制成短线
+const DOT_RATIO: f32 = 0.02;
+
+/// 边界环标记：外部边界
+const LOOP_EXTERNAL: i64 = 1;
+/// 边界环标记：多段线边界
+const LOOP_POLYLINE: i64 = 2;
+/// 边界环标记：最外层边界
+const LOOP_OUTERMOST: i64 = 16;
+/// 孤岛检测样式：忽略内部孤岛
+const STYLE_IGNORE: i64 = 2;
+
+/// 填充实体数据（HATCH）
+///
+/// 边界环已离散为闭合折线（不重复首点），填充区域按奇偶规则确定，内部的环即孤岛。
+#[derive(Component, Debug, Clone)]
+pub struct HatchEntity {
+    /// 图案名（实体填充为 SOLID）
+    pub pattern: String,
+    pub solid: bool,
+    pub loops: Vec<Vec<Vec3>>,
+    /// 图案线族，已按填充的角度和比例变换
+    pub lines: Vec<HatchLine>,
+    /// 源 DXF 中 HATCH 的句柄，导出时据此写回原始组码（不是导入的 HATCH 时为 None）
+    pub handle: Option<Handle>,
+}
+
+/// 填充图案中的一族平行线
+#[derive(Debug, Clone)]
+pub struct HatchLine {
+    /// 第 0 条线经过的点，图案从这里开始排布
+    pub base: Vec3,
+    /// 线的方向（单位向量）
+    pub direction: Vec3,
+    /// 相邻两条线之间的偏移
+    pub offset: Vec3,
+    /// 图案元素：正数为线段长度，负数为空白长度，0 为点；为空表示实线
+    pub dashes: Vec<f32>,
+}
+
+impl HatchEntity {
+    /// 第一个边界环的顶点平均值
+    pub fn center(&self) -> Vec3 {
+        let points = self
+            .loops
+            .first()
+            .map_or(&[][..], |points| points.as_slice());
+        points.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / points.len().max(1) as f32
+    }
+
+    /// 应用仿射变换，平行线族变换后仍是平行线族
+    pub fn transform(&mut self, m: &Affine3A) {
+        for v in self.loops.iter_mut().flatten() {
+            *v = m.transform_point3(*v);
+        }
+        for line in &mut self.lines {
+            let direction = m.transform_vector3(line.direction);
+            let scale = direction.length();
+            line.base = m.transform_point3(line.base);
+            line.direction = direction.normalize_or_zero();
+            line.offset = m.transform_vector3(line.offset);
+            for dash in &mut line.dashes {
+                *dash *= scale;
+            }
+        }
+    }
+
+    /// 所有边界环的边
+    fn edges(&self) -> Vec<(Vec3, Vec3)> {
+        self.loops
+            .iter()
+            .filter(|points| points.len() >= 3)
+            .flat_map(|points| {
+                (0..points.len()).map(|i| (points[i], points[(i + 1) % points.len()]))
+            })
+            .collect()
+    }
+
+    /// 填充区域的三角形（每 3 个点一个三角形）
+    ///
+    /// 在 XZ 平面内按顶点的 z 坐标切成条带，每个条带内穿过的边按奇偶规则两两配对成梯形。
+    pub fn fill_triangles(&self) -> Vec<Vec3> {
+        let edges: Vec<(Vec3, Vec3)> = self
+            .edges()
+            .into_iter()
+            .filter(|(a, b)| a.z != b.z)
+            .collect();
+        let mut levels: Vec<f32> = self.loops.iter().flatten().map(|v| v.z).collect();
+        levels.sort_by(f32::total_cmp);
+        levels.dedup();
+
+        let mut triangles = Vec::new();
+        for band in levels.windows(2) {
+            let (z0, z1) = (band[0], band[1]);
+            let middle = (z0 + z1) * 0.5;
+            // 穿过条带的边在条带中线、下边界和上边界处的交点
+            let mut crossings: Vec<(f32, Vec3, Vec3)> = edges
+                .iter()
+                .filter(|(a, b)| a.z.min(b.z) <= z0 && a.z.max(b.z) >= z1)
+                .map(|(a, b)| {
+                    let at = |z: f32| a.lerp(*b, (z - a.z) / (b.z - a.z));
+                    (at(middle).x, at(z0), at(z1))
+                })
+                .collect();
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+            for pair in crossings.chunks_exact(2) {
+                let ((_, left_0, left_1), (_, right_0, right_1)) = (pair[0], pair[1]);
+                triangles.extend([left_0, right_0, right_1, left_0, right_1, left_1]);
+            }
+        }
+        triangles
+    }
+
+    /// 图案线按奇偶规则裁剪到填充区域内的线段
+    pub fn pattern_segments(&self) -> Vec<(Vec3, Vec3)> {
+        let edges = self.edges();
+        let mut segments = Vec::new();
+        for line in &self.lines {
+            // XZ 平面内线的法向，相邻两条线沿法向的间距
+            let normal = Vec3::new(-line.direction.z, 0.0, line.direction.x);
+            let spacing = line.offset.dot(normal);
+            if spacing.abs() <= f32::EPSILON {
+                continue;
+            }
+            // 与边界相交的线的序号范围
+            let (min, max) = self
+                .loops
+                .iter()
+                .flatten()
+                .map(|v| (*v - line.base).dot(normal) / spacing)
+                .fold((f32::MAX, f32::MIN), |(min, max), k| {
+                    (min.min(k), max.max(k))
+                });
+            let (first, last) = (min.ceil() as i64, max.floor() as i64);
+            if last < first || (last - first) as usize > MAX_PATTERN_LINES {
+                continue;
+            }
+            for k in first..=last {
+                let origin = line.base + line.offset * k as f32;
+                let mut hits: Vec<f32> = edges
+                    .iter()
+                    .filter_map(|(a, b)| {
+                        let (ha, hb) = ((*a - origin).dot(normal), (*b - origin).dot(normal));
+                        ((ha > 0.0) != (hb > 0.0))
+                            .then(|| (a.lerp(*b, ha / (ha - hb)) - origin).dot(line.direction))
+                    })
+                    .collect();
+                hits.sort_by(f32::total_cmp);
+                for pair in hits.chunks_exact(2) {
+                    dash_segments(origin, line, pair[0], pair[1], &mut segments);
+                }
+            }
+        }
+        segments
+    }
+}
+
+/// 在线上 [start, end] 区间内按图案生成线段，图案从线的起点开始重复
+fn dash_segments(
+    origin: Vec3,
+    line: &HatchLine,
+    start: f32,
+    end: f32,
+    segments: &mut Vec<(Vec3, Vec3)>,
+) {
+    let at = |t: f32| origin + line.direction * t;
+    let period: f32 = line.dashes.iter().map(|length| length.abs()).sum();
+    if period <= f32::EPSILON || (end - start) / period > MAX_DASHES {
+        segments.push((at(start), at(end)));
+        return;
+    }
+    let dot = period * DOT_RATIO;
+    let mut position = (start / period).floor() * period;
+    while position < end {
+        for length in &line.dashes {
+            if *length >= 0.0 {
+                let drawn = if *length == 0.0 { dot } else { *length };
+                let (a, b) = (position.max(start), (position + drawn).min(end));
+                if a < b {
+                    segments.push((at(a), at(b)));
+                }
+            }
+            position += length.abs();
+        }
+    }
+}
+
+/// DXF 文件中的填充 - dxf 库不读取 HATCH，由 DxfCodes 按组码解析
+#[derive(Default)]
+pub struct HatchTable {
+    /// 实体段中的填充
+    pub entities: Vec<(EntityCommon, HatchEntity)>,
+    /// 块定义中的填充，键为大写的块名
+    blocks: HashMap<String, Vec<(EntityCommon, HatchEntity)>>,
+    /// 实体段中填充的原始组码，键为句柄，值为在 entities 中的序号和组码
+    sources: HashMap<u64, (usize, Vec<(i32, String)>)>,
+}
+
+impl HatchTable {
+    /// 块定义中的填充（块名不区分大小写）
+    pub fn block(&self, name: &str) -> &[(EntityCommon, HatchEntity)] {
+        self.blocks
+            .get(&name.to_uppercase())
+            .map_or(&[], |hatches| hatches.as_slice())
+    }
+
+    /// 加入一个 HATCH 对象，section 是所在的段，block 是所在的块定义（大写）
+    pub(super) fn add(&mut self, section: &str, block: &str, body: &[(i32, &str)]) {
+        match (section, parse_hatch(body)) {
+            ("ENTITIES", Some(hatch)) => {
+                if let Some(handle) = hatch.1.handle {
+                    let codes = body.iter().map(|(c, v)| (*c, v.to_string())).collect();
+                    self.sources.insert(handle.0, (self.entities.len(), codes));
+                }
+                self.entities.push(hatch);
+            }
+            ("BLOCKS", Some(hatch)) => self
+                .blocks
+                .entry(block.to_string())
+                .or_default()
+                .push(hatch),
+            _ => {}
+        }
+    }
+
+    /// 导入后几何数据没有改变的填充的原始组码
+    pub fn source(&self, hatch: &HatchEntity) -> Option<&[(i32, String)]> {
+        let (index, codes) = self.sources.get(&hatch.handle?.0)?;
+        (self.entities[*index].1.loops == hatch.loops).then_some(codes.as_slice())
+    }
+}
+
+/// 按顺序读取组码，组码不符时不前进
+struct Cursor<'a> {
+    pairs: &'a [(i32, &'a str)],
+    index: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self, ahead: usize) -> Option<i32> {
+        self.pairs.get(self.index + ahead).map(|(code, _)| *code)
+    }
+
+    fn next(&mut self) -> Option<(i32, &'a str)> {
+        let pair = self.pairs.get(self.index).copied();
+        self.index += 1;
+        pair
+    }
+
+    fn take(&mut self, code: i32) -> Option<&'a str> {
+        if self.peek(0)? != code {
+            return None;
+        }
+        self.next().map(|(_, value)| value)
+    }
+
+    fn float(&mut self, code: i32) -> Option<f64> {
+        self.take(code)?.parse().ok()
+    }
+
+    fn int(&mut self, code: i32) -> Option<i64> {
+        self.take(code)?.parse().ok()
+    }
+
+    /// 二维点，y 的组码是 x 的组码加 10
+    fn point(&mut self, code: i32) -> Option<Vec2> {
+        let x = self.float(code)?;
+        let y = self.float(code + 10)?;
+        Some(Vec2::new(x as f32, y as f32))
+    }
+}
+
+/// 图案定义中的一行（OCS）
+struct PatternLine {
+    angle: f32,
+    base: Vec2,
+    offset: Vec2,
+    dashes: Vec<f32>,
+}
+
+/// 解析一个 HATCH 的组码，边界无法解析时返回 None
+fn parse_hatch(pairs: &[(i32, &str)]) -> Option<(EntityCommon, HatchEntity)> {
+    // 子类标记 AcDbHatch 之前是通用组码
+    let split = pairs
+        .iter()
+        .position(|(code, value)| *code == 100 && *value == "AcDbHatch")?;
+    let common = parse_common(&pairs[..split]);
+    let mut cursor = Cursor {
+        pairs: &pairs[split + 1..],
+        index: 0,
+    };
+
+    let mut elevation = 0.0;
+    let mut extrusion = Vector {
+        x: 0.0,
+        y: 0.0,
+        z: 1.0,
+    };
+    let mut pattern = String::new();
+    let mut solid = false;
+    // 边界数量（91）之前是标高、拉伸方向、图案名等
+    let loop_count = loop {
+        let (code, value) = cursor.next()?;
+        let number = value.parse::<f64>().unwrap_or(0.0);
+        match code {
+            30 => elevation = number,
+            210 => extrusion.x = number,
+            220 => extrusion.y = number,
+            230 => extrusion.z = number,
+            2 => pattern = value.to_string(),
+            70 => solid = number != 0.0,
+            91 => break number as usize,
+            _ => {}
+        }
+    };
+
+    let mut loops = Vec::new();
+    for _ in 0..loop_count {
+        let flags = cursor.int(92)?;
+        let points = if flags & LOOP_POLYLINE != 0 {
+            polyline_loop(&mut cursor)?
+        } else {
+            edge_loop(&mut cursor)?
+        };
+        // 关联的边界对象
+        for _ in 0..cursor.int(97).unwrap_or(0) {
+            cursor.take(330);
+        }
+        if points.len() >= 3 {
+            loops.push((flags, points));
+        }
+    }
+
+    let mut style = 0;
+    let mut pattern_lines: Vec<PatternLine> = Vec::new();
+    while let Some((code, value)) = cursor.next() {
+        let number = value.parse::<f64>().unwrap_or(0.0);
+        match (code, pattern_lines.last_mut()) {
+            (75, _) => style = number as i64,
+            // 渐变填充按实体填充显示
+            (450, _) => solid |= number != 0.0,
+            (53, _) => pattern_lines.push(PatternLine {
+                angle: number.to_radians() as f32,
+                base: Vec2::ZERO,
+                offset: Vec2::ZERO,
+                dashes: Vec::new(),
+            }),
+            (43, Some(line)) => line.base.x = number as f32,
+            (44, Some(line)) => line.base.y = number as f32,
+            (45, Some(line)) => line.offset.x = number as f32,
+            (46, Some(line)) => line.offset.y = number as f32,
+            (49, Some(line)) => line.dashes.push(number as f32),
+            _ => {}
+        }
+    }
+
+    // 忽略孤岛时只保留外部边界
+    let outer = |flags: i64| flags & (LOOP_EXTERNAL | LOOP_OUTERMOST) != 0;
+    if style == STYLE_IGNORE && loops.iter().any(|(flags, _)| outer(*flags)) {
+        loops.retain(|(flags, _)| outer(*flags));
+    }
+    if loops.is_empty() {
+        return None;
+    }
+
+    // 边界和图案都在 OCS 中，按标高和拉伸方向变换到世界坐标
+    let ocs = ocs_to_world(&extrusion).unwrap_or(Affine3A::IDENTITY);
+    let point = |p: Vec2| ocs.transform_point3(dxf_to_world(p.x as f64, p.y as f64, elevation));
+    let vector = |v: Vec2| ocs.transform_vector3(dxf_to_world(v.x as f64, v.y as f64, 0.0));
+    let hatch = HatchEntity {
+        pattern,
+        solid,
+        loops: loops
+            .into_iter()
+            .map(|(_, points)| points.into_iter().map(point).collect())
+            .collect(),
+        lines: if solid {
+            Vec::new()
+        } else {
+            pattern_lines
+                .into_iter()
+                .map(|line| HatchLine {
+                    base: point(line.base),
+                    direction: vector(Vec2::from_angle(line.angle)).normalize_or_zero(),
+                    offset: vector(line.offset),
+                    dashes: line.dashes,
+                })
+                .collect()
+        },
+        handle: (!common.handle.is_empty()).then_some(common.handle),
+    };
+    Some((common, hatch))
+}
+
+/// 多段线边界：顶点和可选的凸度
+fn polyline_loop(cursor: &mut Cursor) -> Option<Vec<Vec2>> {
+    let has_bulge = cursor.int(72).unwrap_or(0) != 0;
+    // 是否闭合，边界总是闭合的
+    cursor.int(73);
+    let count = cursor.int(93)?;
+    let mut vertices = Vec::new();
+    let mut bulges = Vec::new();
+    for _ in 0..count {
+        let p = cursor.point(10)?;
+        let bulge = if has_bulge {
+            cursor.float(42).unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        vertices.push(dxf_to_world(p.x as f64, p.y as f64, 0.0));
+        bulges.push(bulge as f32);
+    }
+    let pl = PolylineEntity {
+        vertices,
+        closed: true,
+        bulges,
+        widths: Vec::new(),
+    };
+    // 边界在导入时离散，精度按边界大小确定；离散后的折线以首点结束
+    let mut points: Vec<Vec2> = pl
+        .tessellate(&CurveTolerance::relative(&pl.vertices))
+        .into_iter()
+        .map(|v| Vec2::new(v.x, v.z))
+        .collect();
+    points.pop();
+    Some(points)
+}
+
+/// 由直线、圆弧、椭圆弧和样条曲线首尾相接组成的边界
+fn edge_loop(cursor: &mut Cursor) -> Option<Vec<Vec2>> {
+    let count = cursor.int(93)?;
+    let mut points: Vec<Vec2> = Vec::new();
+    for _ in 0..count {
+        let edge = match cursor.int(72)? {
+            1 => vec![cursor.point(10)?, cursor.point(11)?],
+            2 => {
+                let center = cursor.point(10)?;
+                let radius = cursor.float(40)? as f32;
+                let (start, end) = (cursor.float(50)?, cursor.float(51)?);
+                let ccw = cursor.int(73).unwrap_or(1) != 0;
+                arc_edge(center, Vec2::X * radius, 1.0, start, end, ccw)
+            }
+            3 => {
+                let center = cursor.point(10)?;
+                let major = cursor.point(11)?;
+                let ratio = cursor.float(40)? as f32;
+                let (start, end) = (cursor.float(50)?, cursor.float(51)?);
+                let ccw = cursor.int(73).unwrap_or(1) != 0;
+                arc_edge(center, major, ratio, start, end, ccw)
+            }
+            4 => spline_edge(cursor)?,
+            _ => return None,
+        };
+        for p in edge {
+            if points.last() != Some(&p) {
+                points.push(p);
+            }
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    Some(points)
+}
+
+/// 椭圆弧边（圆弧是长短轴比为 1 的特例），角度为度；顺时针时角度按反方向度量
+fn arc_edge(center: Vec2, major: Vec2, ratio: f32, start: f64, end: f64, ccw: bool) -> Vec<Vec2> {
+    let minor = major.perp() * ratio;
+    let (mut start, mut end) = (start.to_radians() as f32, end.to_radians() as f32);
+    if !ccw {
+        (start, end) = (-start, -end);
+    }
+    let mut sweep = end - start;
+    if ccw && sweep <= 0.0 {
+        sweep += TAU;
+    } else if !ccw && sweep >= 0.0 {
+        sweep -= TAU;
+    }
+    let segments = ((sweep.abs() / TAU * ARC_SEGMENTS as f32).ceil() as usize).max(2);
+    (0..=segments)
+        .map(|i| {
+            let t = start + sweep * i as f32 / segments as f32;
+            center + major * t.cos() + minor * t.sin()
+        })
+        .collect()
+}
+
+/// 样条曲线边
+fn spline_edge(cursor: &mut Cursor) -> Option<Vec<Vec2>> {
+    let to_world = |p: Vec2| dxf_to_world(p.x as f64, p.y as f64, 0.0);
+    let degree = cursor.int(94)? as usize;
+    let rational = cursor.int(73).unwrap_or(0) != 0;
+    // 是否周期
+    cursor.int(74);
+    let knot_count = cursor.int(95)?;
+    let control_count = cursor.int(96)?;
+    let knots = (0..knot_count)
+        .map(|_| cursor.float(40).map(|k| k as f32))
+        .collect::<Option<Vec<f32>>>()?;
+    let mut control_points = Vec::new();
+    let mut weights = Vec::new();
+    for _ in 0..control_count {
+        control_points.push(to_world(cursor.point(10)?));
+        if rational {
+            weights.push(cursor.float(42).unwrap_or(1.0) as f32);
+        }
+    }
+    // R2010 起有拟合点数量（97），与边界后的关联对象数量（也是 97）按后面的组码区分
+    let mut fit_points = Vec::new();
+    if cursor.peek(0) == Some(97) && !matches!(cursor.peek(1), Some(330 | 92 | 75) | None) {
+        for _ in 0..cursor.int(97)? {
+            fit_points.push(to_world(cursor.point(11)?));
+        }
+        // 起点和终点切向
+        cursor.point(12);
+        cursor.point(13);
+    }
+    let tolerance = CurveTolerance::relative(if control_points.is_empty() {
+        &fit_points
+    } else {
+        &control_points
+    });
+    let spline = SplineEntity {
+        degree: degree.max(1),
+        control_points,
+        knots,
+        weights,
+        fit_points,
+        closed: false,
+    };
+    Some(
+        spline
+            .tessellate(&tolerance)
+            .into_iter()
+            .map(|v| Vec2::new(v.x, v.z))
+            .collect(),
+    )
+}
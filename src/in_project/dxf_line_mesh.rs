@@ -0,0 +1,141 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use super::dxf_renderer::{ChunkDrawData, DrawChunk, DxfDrawData, arc_points};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+use crate::editor::{Editor, EditorPart};
+
+/// 单个线网格的最大顶点数（偶数），超过时拆成多个网格
+const MAX_CHUNK_VERTICES: usize = 1 << 20;
+
+/// 按图层和颜色分块生成的网格（线网格或填充网格），图层开关时只切换可见性
+#[derive(Component, Debug, Clone)]
+pub struct DxfLayerMesh {
+    pub chunk: DrawChunk,
+}
+
+/// 线网格标记
+#[derive(Component)]
+pub struct DxfLineMesh;
+
+/// 线网格系统 - 绘制数据变化时重建有变化的分块的线网格
+///
+/// 每个分块（图层中同一颜色）的线条合并为一个 LineList 网格，只在分块变化时重建，
+/// 不再每帧把所有线段提交给 Gizmos。
+#[allow(clippy::too_many_arguments)]
+pub fn dxf_line_mesh_system(
+    mut commands: Commands,
+    draw_data: Res<DxfDrawData>,
+    tolerance: Res<CurveTolerance>,
+    layers: Res<LayerTable>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    line_query: Query<(Entity, &DxfLayerMesh), With<DxfLineMesh>>,
+    editor_query: Query<Entity, With<Editor>>,
+) {
+    if !draw_data.is_changed() {
+        return;
+    }
+    for (entity, mesh) in &line_query {
+        if draw_data.dirty.contains(&mesh.chunk) {
+            commands.entity(entity).despawn();
+        }
+    }
+    let Some(editor) = editor_query.iter().next() else {
+        return;
+    };
+
+    for chunk in &draw_data.dirty {
+        let Some(data) = draw_data.chunks.get(chunk) else {
+            continue;
+        };
+        let vertices = chunk_segments(data, &tolerance);
+        if vertices.is_empty() {
+            continue;
+        }
+        let visibility = layer_visibility(&layers, &chunk.layer);
+        let material = materials.add(StandardMaterial {
+            base_color: data.color,
+            unlit: true,
+            ..default()
+        });
+        for part in vertices.chunks(MAX_CHUNK_VERTICES) {
+            let mesh = Mesh::new(PrimitiveTopology::LineList, RenderAssetUsages::default())
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, part.to_vec());
+            let id = commands
+                .spawn((
+                    EditorPart,
+                    DxfLineMesh,
+                    DxfLayerMesh {
+                        chunk: chunk.clone(),
+                    },
+                    Mesh3d(meshes.add(mesh)),
+                    MeshMaterial3d(material.clone()),
+                    Transform::default(),
+                    visibility,
+                ))
+                .id();
+            commands.entity(editor).add_child(id);
+        }
+    }
+}
+
+/// 图层网格可见性系统 - 图层打开、关闭或冻结时切换网格的可见性，不重建网格
+pub fn dxf_layer_mesh_visibility_system(
+    layers: Res<LayerTable>,
+    mut mesh_query: Query<(&DxfLayerMesh, &mut Visibility)>,
+) {
+    if !layers.is_changed() {
+        return;
+    }
+    for (mesh, mut visibility) in &mut mesh_query {
+        visibility.set_if_neq(layer_visibility(&layers, &mesh.chunk.layer));
+    }
+}
+
+/// 图层网格的可见性
+pub(super) fn layer_visibility(layers: &LayerTable, layer: &str) -> Visibility {
+    if layers.is_visible(layer) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+/// 分块中的线条（粗线除外）拆成线段，每 2 个点一条线段
+fn chunk_segments(data: &ChunkDrawData, tolerance: &CurveTolerance) -> Vec<[f32; 3]> {
+    let mut vertices = Vec::new();
+    for entity in data.entities.values() {
+        for (start, end) in &entity.lines {
+            vertices.extend([start.to_array(), end.to_array()]);
+        }
+        for (center, radius) in &entity.circles {
+            let points = arc_points(*center, *radius, 0.0, TAU, tolerance);
+            push_strip(&mut vertices, &points);
+        }
+        for (center, radius, start_angle, end_angle) in &entity.arcs {
+            let points = arc_points(*center, *radius, *start_angle, *end_angle, tolerance);
+            push_strip(&mut vertices, &points);
+        }
+        for (points, closed) in &entity.polylines {
+            push_strip(&mut vertices, points);
+            if *closed && points.len() > 2 {
+                vertices.extend([points[points.len() - 1].to_array(), points[0].to_array()]);
+            }
+        }
+        for points in &entity.curves {
+            push_strip(&mut vertices, points);
+        }
+    }
+    vertices
+}
+
+/// 折线的每一段作为一条线段加入
+fn push_strip(vertices: &mut Vec<[f32; 3]>, points: &[Vec3]) {
+    for pair in points.windows(2) {
+        vertices.extend([pair[0].to_array(), pair[1].to_array()]);
+    }
+}
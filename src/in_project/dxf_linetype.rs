@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use dxf::Drawing;
+use serde::{Deserialize, Serialize};
+
+/// 图案重复次数超过这个值时按实线绘制，避免缩小时生成过多线段
+const MAX_DASHES: f32 = 10000.0;
+/// 点（长度为 0 的元素）按图案长度的这个比例绘制成短线
+const DOT_RATIO: f32 = 0.02;
+
+/// 一种线型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineType {
+    pub name: String,
+    /// 图案元素：正数为线段长度，负数为空白长度，0 为点
+    pub pattern: Vec<f32>,
+}
+
+/// 线型表 - 导入 DXF 时从 LTYPE 表生成，随场景保存
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct LineTypeTable {
+    pub line_types: Vec<LineType>,
+    /// 全局线型比例（LTSCALE）
+    pub global_scale: f32,
+    /// 新建实体的线型比例（CELTSCALE）
+    pub entity_scale: f32,
+}
+
+impl Default for LineTypeTable {
+    fn default() -> Self {
+        Self {
+            line_types: Vec::new(),
+            global_scale: 1.0,
+            entity_scale: 1.0,
+        }
+    }
+}
+
+impl LineTypeTable {
+    /// 从 DXF 的 LTYPE 表和 LTSCALE、CELTSCALE 生成
+    pub fn from_drawing(drawing: &Drawing) -> Self {
+        let positive = |scale: f64| if scale > 0.0 { scale as f32 } else { 1.0 };
+        Self {
+            line_types: drawing
+                .line_types()
+                .map(|line_type| LineType {
+                    name: line_type.name.clone(),
+                    pattern: line_type
+                        .dash_dot_space_lengths
+                        .iter()
+                        .map(|length| *length as f32)
+                        .collect(),
+                })
+                .collect(),
+            global_scale: positive(drawing.header.line_type_scale),
+            entity_scale: positive(drawing.header.current_entity_line_type_scale),
+        }
+    }
+
+    /// 线型的图案（线型名不区分大小写），实线或线型不存在时返回 None
+    pub fn pattern(&self, name: &str) -> Option<&[f32]> {
+        self.line_types
+            .iter()
+            .find(|line_type| line_type.name.eq_ignore_ascii_case(name))
+            .map(|line_type| line_type.pattern.as_slice())
+            .filter(|pattern| pattern.iter().any(|length| *length != 0.0))
+    }
+}
+
+/// 沿折线按图案生成线段，图案沿整条折线连续排布，不在每个顶点重新开始
+///
+/// 返回的每一段是一条连续的折线；图案太密时返回整条折线。
+pub fn dash_polyline(points: &[Vec3], pattern: &[f32], scale: f32) -> Vec<Vec<Vec3>> {
+    let pattern: Vec<f32> = pattern.iter().map(|length| length * scale).collect();
+    let period: f32 = pattern.iter().map(|length| length.abs()).sum();
+    let total: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    if period <= f32::EPSILON || total / period > MAX_DASHES {
+        return vec![points.to_vec()];
+    }
+    let dot = period * DOT_RATIO;
+
+    let mut dashes = Vec::new();
+    let mut current: Vec<Vec3> = Vec::new();
+    // 当前图案元素的序号和剩余长度
+    let mut index = 0;
+    let mut remaining = element_length(pattern[0], dot);
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = start.distance(end);
+        let mut offset = 0.0;
+        while offset < length {
+            let drawing = pattern[index] >= 0.0;
+            let step = remaining.min(length - offset);
+            if drawing {
+                if current.is_empty() {
+                    current.push(start.lerp(end, offset / length));
+                }
+                current.push(start.lerp(end, (offset + step) / length));
+            }
+            offset += step;
+            remaining -= step;
+            if remaining <= f32::EPSILON {
+                // 当前元素结束，切换到下一个元素
+                if drawing && current.len() >= 2 {
+                    dashes.push(std::mem::take(&mut current));
+                }
+                current.clear();
+                index = (index + 1) % pattern.len();
+                remaining = element_length(pattern[index], dot);
+            }
+        }
+    }
+    if current.len() >= 2 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+fn element_length(length: f32, dot: f32) -> f32 {
+    if length == 0.0 { dot } else { length.abs() }
+}
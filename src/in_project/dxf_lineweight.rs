@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+/// 默认线宽（LWDEFAULT，毫米）
+pub const DEFAULT_LINE_WEIGHT: f32 = 0.25;
+/// 各档线宽的上限（毫米），不超过默认线宽的按默认 Gizmos 绘制
+const LEVEL_LIMITS: [f32; 3] = [0.5, 1.0, f32::MAX];
+/// 各档线宽的屏幕宽度（像素）
+const LEVEL_WIDTHS: [f32; 3] = [3.0, 4.5, 6.0];
+
+/// 线宽 Gizmos 配置组，LEVEL 从 1 开始，每档使用不同的线宽
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct LineWeightGizmos<const LEVEL: usize>;
+
+/// 线宽显示开关（LWT），关闭时所有实体按细线绘制
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct LineWeightDisplay {
+    pub enabled: bool,
+}
+
+/// 线宽所在的档位，0 表示按默认 Gizmos 绘制
+pub fn line_weight_level(weight: f32) -> usize {
+    if weight <= DEFAULT_LINE_WEIGHT {
+        return 0;
+    }
+    LEVEL_LIMITS
+        .iter()
+        .position(|limit| weight <= *limit)
+        .map_or(LEVEL_LIMITS.len(), |index| index + 1)
+}
+
+/// 第 level 档的 Gizmos 配置
+pub fn line_weight_config(level: usize) -> GizmoConfig {
+    GizmoConfig {
+        line: GizmoLineConfig {
+            width: LEVEL_WIDTHS[level - 1],
+            ..default()
+        },
+        ..default()
+    }
+}
+
+/// DXF 线宽值（百分之一毫米）转换为毫米；随层、随块返回 None，默认线宽按 LWDEFAULT
+pub fn line_weight_from_dxf(value: i16) -> Option<f32> {
+    match value {
+        -1 | -2 => None,
+        value if value < 0 => Some(DEFAULT_LINE_WEIGHT),
+        value => Some(value as f32 / 100.0),
+    }
+}
@@ -0,0 +1,335 @@
+use bevy::math::Affine3A;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future};
+use bevy_panorbit_camera::PanOrbitCamera;
+use dxf::Drawing;
+use dxf::entities::{Entity as DxfEntity, EntityType};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use super::dxf_block::{BlockTable, CadNode, insert_node, spawn_cad_node};
+use super::dxf_codes::DxfCodes;
+use super::dxf_dimension::{DimStyles, dimension_from_dxf, dimension_node};
+use super::dxf_linetype::LineTypeTable;
+use super::dxf_lineweight::LineWeightDisplay;
+use super::dxf_renderer::{
+    CadEntity, CadEntityType, CadGeometry, DxfSource, LoadDxfMessage, SourceDrawing,
+    geometry_from_dxf,
+};
+use super::history::EditHistory;
+use super::layer_panel::LayerTable;
+use super::zoom::ZoomMessage;
+use crate::editor::Editor;
+
+/// 加载进度 - 后台任务写入，界面读取
+#[derive(Default)]
+struct LoadProgress {
+    /// 已转换的实体数
+    converted: AtomicUsize,
+    /// 实体总数，解析文件时为 0
+    total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl LoadProgress {
+    /// 转换进度（0..1），还在解析文件时为 None
+    fn fraction(&self) -> Option<f32> {
+        let total = self.total.load(Ordering::Relaxed);
+        (total > 0).then(|| self.converted.load(Ordering::Relaxed) as f32 / total as f32)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// 各类实体的数量
+#[derive(Default)]
+struct EntityCounts {
+    line: usize,
+    circle: usize,
+    arc: usize,
+    polyline: usize,
+    text: usize,
+    insert: usize,
+    dimension: usize,
+    curve: usize,
+    hatch: usize,
+}
+
+impl EntityCounts {
+    fn add(&mut self, entity_type: &CadEntityType) {
+        match entity_type {
+            CadEntityType::Line => self.line += 1,
+            CadEntityType::Circle => self.circle += 1,
+            CadEntityType::Arc => self.arc += 1,
+            CadEntityType::Polyline => self.polyline += 1,
+            CadEntityType::Text => self.text += 1,
+            CadEntityType::Insert => self.insert += 1,
+            CadEntityType::Dimension => self.dimension += 1,
+            CadEntityType::Spline | CadEntityType::Ellipse => self.curve += 1,
+            CadEntityType::Hatch => self.hatch += 1,
+        }
+    }
+}
+
+/// 后台加载的结果
+struct LoadedDxf {
+    /// 源文件内容，导出时重新解析
+    bytes: Vec<u8>,
+    layers: LayerTable,
+    line_types: LineTypeTable,
+    line_weight_display: bool,
+    /// 顶层实体及其源 DXF 实体（填充没有源实体）
+    nodes: Vec<(CadNode, Option<DxfEntity>)>,
+    counts: EntityCounts,
+}
+
+/// 正在后台进行的加载
+struct PendingLoad {
+    path: PathBuf,
+    task: Task<Option<LoadedDxf>>,
+    progress: Arc<LoadProgress>,
+}
+
+/// DXF 加载任务 - 同一时间只有一个，新的加载会取消正在进行的加载
+#[derive(Resource, Default)]
+pub struct DxfLoadTask {
+    pending: Option<PendingLoad>,
+}
+
+impl DxfLoadTask {
+    /// 正在加载的文件和转换进度（解析文件时为 None）
+    pub fn status(&self) -> Option<(&Path, Option<f32>)> {
+        self.pending
+            .as_ref()
+            .map(|pending| (pending.path.as_path(), pending.progress.fraction()))
+    }
+
+    /// 取消加载，编辑器保持原来的内容
+    ///
+    /// 任务被丢弃；已经在运行的转换看到取消标记后提前结束。
+    pub fn cancel(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.progress.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// DXF 加载系统 - 在后台任务中解析 DXF 并转换为 CAD 实体
+pub fn dxf_load_system(
+    mut messages: MessageReader<LoadDxfMessage>,
+    mut load_task: ResMut<DxfLoadTask>,
+) {
+    // 只处理最后一条，连续打开多个文件时以最后一个为准
+    let Some(message) = messages.read().last() else {
+        return;
+    };
+    load_task.cancel();
+    let path = message.path.clone();
+    let progress = Arc::new(LoadProgress::default());
+    let task = AsyncComputeTaskPool::get().spawn({
+        let path = path.clone();
+        let progress = progress.clone();
+        async move { load_dxf(&path, &progress) }
+    });
+    load_task.pending = Some(PendingLoad {
+        path,
+        task,
+        progress,
+    });
+}
+
+/// DXF 加载完成系统 - 后台加载完成后用结果替换编辑器中的 CAD 实体
+#[allow(clippy::too_many_arguments)]
+pub fn dxf_load_apply_system(
+    mut commands: Commands,
+    mut load_task: ResMut<DxfLoadTask>,
+    mut source: ResMut<SourceDrawing>,
+    mut layer_table: ResMut<LayerTable>,
+    mut line_types: ResMut<LineTypeTable>,
+    mut line_weight_display: ResMut<LineWeightDisplay>,
+    mut zoom_messages: MessageWriter<ZoomMessage>,
+    mut history: ResMut<EditHistory>,
+    children_query: Query<&Children, With<Editor>>,
+    mut editor_query: Query<(Entity, &mut Editor)>,
+    // 用 Has<T> 或 Query 过滤相机和灯光
+    to_keep_query: Query<(Has<PanOrbitCamera>, Has<DirectionalLight>)>,
+) {
+    let Some(pending) = load_task.pending.as_mut() else {
+        return;
+    };
+    let Some(result) = block_on(future::poll_once(&mut pending.task)) else {
+        return;
+    };
+    let Some(pending) = load_task.pending.take() else {
+        return;
+    };
+    // 失败的原因已在任务中输出
+    let Some(loaded) = result else {
+        return;
+    };
+
+    // 获取 Editor 实体
+    let Some((editor_entity, mut editor)) = editor_query.iter_mut().next() else {
+        eprintln!("未找到 Editor 实体");
+        return;
+    };
+
+    // 清理当前的 CAD 实体
+    for children in &children_query {
+        for &child in children {
+            // 检查这个子实体是否需要保留
+            if let Ok((is_camera, is_light)) = to_keep_query.get(child)
+                && !is_camera
+                && !is_light
+            {
+                // 如果既不是相机也不是灯光，则删除
+                commands.entity(child).despawn();
+            }
+        }
+    }
+
+    // 创建CAD实体（不可见，但可选择）
+    source.imported.clear();
+    for (node, source_entity) in loaded.nodes {
+        let cad_entity = spawn_cad_node(&mut commands, editor_entity, node);
+        if let Some(ent) = source_entity {
+            source.imported.insert(ent.common.handle.0);
+            commands.entity(cad_entity).insert(DxfSource(ent));
+        }
+    }
+
+    // 历史中的实体已被替换
+    history.clear();
+
+    // 保留源图纸，导出时使用
+    source.path = Some(pending.path.clone());
+    source.bytes = Some(loaded.bytes);
+    *layer_table = loaded.layers;
+    *line_types = loaded.line_types;
+    line_weight_display.enabled = loaded.line_weight_display;
+
+    // 导入的实体尚未保存到场景文件
+    editor.is_dirty = true;
+    // 图纸常常远离原点，加载后缩放到图纸范围
+    zoom_messages.write(ZoomMessage::Extents);
+
+    let counts = &loaded.counts;
+    println!(
+        "DXF 加载完成: {:?}, 线段: {}, 圆: {}, 弧: {}, 多段线: {}, 文字: {}, 块参照: {}, 标注: {}, 曲线: {}, 填充: {}",
+        pending.path,
+        counts.line,
+        counts.circle,
+        counts.arc,
+        counts.polyline,
+        counts.text,
+        counts.insert,
+        counts.dimension,
+        counts.curve,
+        counts.hatch
+    );
+}
+
+/// 解析 DXF 并转换为 CAD 实体（在后台线程运行），失败或取消时返回 None
+fn load_dxf(path: &Path, progress: &LoadProgress) -> Option<LoadedDxf> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("读取DXF失败 {:?}: {}", path, e);
+            return None;
+        }
+    };
+    let drawing = match Drawing::load(&mut bytes.as_slice()) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("加载DXF失败: {}", e);
+            return None;
+        }
+    };
+    if progress.is_cancelled() {
+        return None;
+    }
+
+    // dxf 库不读取 HATCH、属性的通用组码、图层标志等数据，另外按组码解析
+    let codes = DxfCodes::parse(&bytes);
+    let blocks = BlockTable::new(&drawing, &codes);
+    let dim_styles = DimStyles::new(&drawing);
+    let mut layers = LayerTable::from_drawing(&drawing, &codes);
+    let mut counts = EntityCounts::default();
+    let mut nodes = Vec::new();
+    progress.total.store(
+        drawing.entities().count() + codes.hatches.entities.len(),
+        Ordering::Relaxed,
+    );
+
+    for ent in drawing.entities() {
+        if progress.is_cancelled() {
+            return None;
+        }
+        progress.converted.fetch_add(1, Ordering::Relaxed);
+        let Some(node) = entity_node(ent, &blocks, &dim_styles, &layers) else {
+            continue;
+        };
+        counts.add(&node.cad.entity_type);
+        // LAYER 表中缺少的图层也要出现在图层面板中
+        layers.ensure(&node.cad.layer);
+        nodes.push((node, Some(ent.clone())));
+    }
+
+    // 填充没有对应的 DXF 实体，导出时按原始组码写回
+    for (common, hatch) in &codes.hatches.entities {
+        progress.converted.fetch_add(1, Ordering::Relaxed);
+        let cad = CadEntity::from_dxf(common, CadEntityType::Hatch, &layers, None);
+        counts.add(&cad.entity_type);
+        layers.ensure(&cad.layer);
+        let node = CadNode {
+            cad,
+            geometry: CadGeometry::Hatch(hatch.clone()),
+            children: Vec::new(),
+        };
+        nodes.push((node, None));
+    }
+
+    Some(LoadedDxf {
+        line_types: LineTypeTable::from_drawing(&drawing),
+        // 线宽显示开关沿用图纸的 LWDISPLAY
+        line_weight_display: drawing.header.display_linewieght_in_model_and_layout_tab,
+        bytes,
+        layers,
+        nodes,
+        counts,
+    })
+}
+
+/// 顶层 DXF 实体转换为 CAD 实体，不支持的类型返回 None
+fn entity_node(
+    ent: &DxfEntity,
+    blocks: &BlockTable,
+    dim_styles: &DimStyles,
+    layers: &LayerTable,
+) -> Option<CadNode> {
+    match &ent.specific {
+        // 块参照展开为父实体 + 子实体
+        EntityType::Insert(insert) => {
+            let cad = CadEntity::from_dxf(&ent.common, CadEntityType::Insert, layers, None);
+            let handle = ent.common.handle;
+            insert_node(blocks, layers, cad, handle, insert, Affine3A::IDENTITY, 0)
+        }
+        // 标注展开为父实体 + 图形子实体
+        specific => {
+            if let Some(dimension) = dimension_from_dxf(specific) {
+                let cad = CadEntity::from_dxf(&ent.common, CadEntityType::Dimension, layers, None);
+                Some(dimension_node(blocks, layers, dim_styles, cad, dimension))
+            } else {
+                let geometry = geometry_from_dxf(specific, ent.common.elevation)?;
+                Some(CadNode {
+                    cad: CadEntity::from_dxf(&ent.common, geometry.entity_type(), layers, None),
+                    geometry,
+                    children: Vec::new(),
+                })
+            }
+        }
+    }
+}
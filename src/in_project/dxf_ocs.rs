@@ -0,0 +1,53 @@
+use bevy::math::{Affine3A, DVec3, Vec3A};
+use dxf::Vector;
+use dxf::entities::EntityType;
+
+/// DXF 坐标与世界坐标之间的轴交换（DXF 的 Y 对应世界的 Z）
+pub(super) const SWAP_YZ: Affine3A = Affine3A::from_cols(Vec3A::X, Vec3A::Z, Vec3A::Y, Vec3A::ZERO);
+
+/// 任意轴算法中判断法向是否接近 Z 轴的阈值
+const ARBITRARY_AXIS_LIMIT: f64 = 1.0 / 64.0;
+
+/// 任意轴算法：由拉伸方向计算 OCS 到 WCS 的变换（DXF 坐标系）
+pub fn ocs_matrix(extrusion: &Vector) -> Affine3A {
+    let normal = DVec3::new(extrusion.x, extrusion.y, extrusion.z)
+        .try_normalize()
+        .unwrap_or(DVec3::Z);
+    let axis_x =
+        if normal.x.abs() < ARBITRARY_AXIS_LIMIT && normal.y.abs() < ARBITRARY_AXIS_LIMIT {
+            DVec3::Y.cross(normal)
+        } else {
+            DVec3::Z.cross(normal)
+        }
+        .normalize();
+    let axis_y = normal.cross(axis_x).normalize();
+    Affine3A::from_cols(
+        axis_x.as_vec3().into(),
+        axis_y.as_vec3().into(),
+        normal.as_vec3().into(),
+        Vec3A::ZERO,
+    )
+}
+
+/// OCS 到世界坐标的变换；拉伸方向为默认的 (0, 0, 1) 时 OCS 与 WCS 重合，返回 None
+pub fn ocs_to_world(extrusion: &Vector) -> Option<Affine3A> {
+    let is_default = extrusion.x.abs() < 1e-9 && extrusion.y.abs() < 1e-9 && extrusion.z > 0.0;
+    (!is_default).then(|| SWAP_YZ * ocs_matrix(extrusion) * SWAP_YZ)
+}
+
+/// 在 OCS 中定义的实体从 OCS 到世界坐标的变换
+///
+/// 直线、三维多段线、样条曲线、椭圆、多行文字和标注的坐标本身就是 WCS，返回 None。
+pub fn entity_ocs(specific: &EntityType) -> Option<Affine3A> {
+    let extrusion = match specific {
+        EntityType::Circle(circle) => &circle.normal,
+        EntityType::Arc(arc) => &arc.normal,
+        EntityType::LwPolyline(pl) => &pl.extrusion_direction,
+        EntityType::Polyline(pl) if !pl.is_3d_polyline() => &pl.normal,
+        EntityType::Solid(solid) => &solid.extrusion_direction,
+        EntityType::Text(text) => &text.normal,
+        EntityType::Insert(insert) => &insert.extrusion_direction,
+        _ => return None,
+    };
+    ocs_to_world(extrusion)
+}
@@ -0,0 +1,268 @@
+use super::dxf_line_mesh::{DxfLayerMesh, layer_visibility};
+use super::dxf_renderer::{DxfDrawData, PolylineEntity};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+use crate::editor::{Editor, EditorPart};
+use bevy::asset::RenderAssetUsages;
+use bevy::mesh::PrimitiveTopology;
+use bevy::prelude::*;
+
+/// 多段线的一段
+#[derive(Debug, Clone, Copy)]
+pub enum PolylineSegment {
+    Line {
+        start: Vec3,
+        end: Vec3,
+    },
+    /// 圆弧段，sweep 为带符号的圆心角，正值为逆时针
+    Arc {
+        center: Vec3,
+        radius: f32,
+        start_angle: f32,
+        sweep: f32,
+    },
+}
+
+impl PolylineSegment {
+    /// 线段上参数 t (0..=1) 处的点
+    pub fn point_at(&self, t: f32) -> Vec3 {
+        match *self {
+            PolylineSegment::Line { start, end } => start.lerp(end, t),
+            PolylineSegment::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                let a = start_angle + sweep * t;
+                center + Vec3::new(a.cos(), 0.0, a.sin()) * radius
+            }
+        }
+    }
+
+    /// 离散的段数，圆弧段按弦高误差分段
+    fn divisions(&self, tolerance: &CurveTolerance) -> usize {
+        match self {
+            PolylineSegment::Line { .. } => 1,
+            PolylineSegment::Arc { radius, sweep, .. } => tolerance.arc_segments(*radius, *sweep),
+        }
+    }
+}
+
+impl PolylineEntity {
+    /// 没有凸度和宽度的多段线
+    pub fn new(vertices: Vec<Vec3>, closed: bool) -> Self {
+        Self {
+            vertices,
+            closed,
+            bulges: Vec::new(),
+            widths: Vec::new(),
+        }
+    }
+
+    pub fn bulge(&self, index: usize) -> f32 {
+        self.bulges.get(index).copied().unwrap_or(0.0)
+    }
+
+    pub fn width(&self, index: usize) -> [f32; 2] {
+        self.widths.get(index).copied().unwrap_or([0.0; 2])
+    }
+
+    /// 是否有宽度，有宽度时填充绘制
+    pub fn is_wide(&self) -> bool {
+        self.widths.iter().any(|[w0, w1]| *w0 > 0.0 || *w1 > 0.0)
+    }
+
+    /// 各段几何数据，闭合时包括最后一个顶点回到第一个顶点的一段
+    pub fn segments(&self) -> Vec<PolylineSegment> {
+        let n = self.vertices.len();
+        let count = if self.closed && n > 2 {
+            n
+        } else {
+            n.saturating_sub(1)
+        };
+        (0..count)
+            .map(|i| {
+                let (start, end) = (self.vertices[i], self.vertices[(i + 1) % n]);
+                bulge_segment(start, end, self.bulge(i))
+            })
+            .collect()
+    }
+
+    /// 离散为折线，圆弧段按弦高误差分段
+    pub fn tessellate(&self, tolerance: &CurveTolerance) -> Vec<Vec3> {
+        let mut points: Vec<Vec3> = self.vertices.first().copied().into_iter().collect();
+        for segment in self.segments() {
+            let divisions = segment.divisions(tolerance);
+            points.extend((1..=divisions).map(|i| segment.point_at(i as f32 / divisions as f32)));
+        }
+        points
+    }
+
+    /// 宽多段线的填充三角形（每 3 个点一个三角形）
+    pub fn fill_triangles(&self, tolerance: &CurveTolerance) -> Vec<Vec3> {
+        let mut triangles = Vec::new();
+        for (index, segment) in self.segments().into_iter().enumerate() {
+            let [w0, w1] = self.width(index);
+            if w0 <= 0.0 && w1 <= 0.0 {
+                continue;
+            }
+            let divisions = segment.divisions(tolerance);
+            let samples: Vec<(Vec3, f32)> = (0..=divisions)
+                .map(|i| {
+                    let t = i as f32 / divisions as f32;
+                    (segment.point_at(t), w0 + (w1 - w0) * t)
+                })
+                .collect();
+            // 每个采样点沿 XZ 平面内的法向向两侧偏移半个宽度
+            let offsets: Vec<(Vec3, Vec3)> = (0..samples.len())
+                .map(|i| {
+                    let prev = samples[i.saturating_sub(1)].0;
+                    let next = samples[(i + 1).min(samples.len() - 1)].0;
+                    let dir = (next - prev).normalize_or_zero();
+                    let normal = Vec3::new(-dir.z, 0.0, dir.x) * samples[i].1 * 0.5;
+                    (samples[i].0 + normal, samples[i].0 - normal)
+                })
+                .collect();
+            for pair in offsets.windows(2) {
+                let ((a_left, a_right), (b_left, b_right)) = (pair[0], pair[1]);
+                triangles.extend([a_left, a_right, b_right, a_left, b_right, b_left]);
+            }
+        }
+        triangles
+    }
+}
+
+/// 由凸度计算两点之间的线段或圆弧
+fn bulge_segment(start: Vec3, end: Vec3, bulge: f32) -> PolylineSegment {
+    let chord = Vec2::new(end.x - start.x, end.z - start.z);
+    let length = chord.length();
+    if bulge.abs() < 1e-6 || length < f32::EPSILON {
+        return PolylineSegment::Line { start, end };
+    }
+    let sweep = 4.0 * bulge.atan();
+    // 圆心在弦的中垂线上，逆时针圆弧时位于弦的左侧
+    let half = (sweep * 0.5).tan();
+    let mid = Vec2::new(start.x + end.x, start.z + end.z) * 0.5;
+    let center = mid + chord.perp() * (0.5 / half);
+    let radius = (length * 0.5 / (sweep * 0.5).sin()).abs();
+    let start_angle = (Vec2::new(start.x, start.z) - center).to_angle();
+    PolylineSegment::Arc {
+        center: Vec3::new(center.x, (start.y + end.y) * 0.5, center.y),
+        radius,
+        start_angle,
+        sweep,
+    }
+}
+
+/// 填充网格标记 - 每个分块中宽多段线、实体填充等需要填充的图形合并为一个网格
+#[derive(Component)]
+pub struct DxfFillMesh;
+
+/// 填充网格系统 - 绘制数据变化时重建有变化的分块的填充网格
+pub fn dxf_fill_mesh_system(
+    mut commands: Commands,
+    draw_data: Res<DxfDrawData>,
+    layers: Res<LayerTable>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    fill_query: Query<(Entity, &DxfLayerMesh), With<DxfFillMesh>>,
+    editor_query: Query<Entity, With<Editor>>,
+) {
+    if !draw_data.is_changed() {
+        return;
+    }
+    for (entity, mesh) in &fill_query {
+        if draw_data.dirty.contains(&mesh.chunk) {
+            commands.entity(entity).despawn();
+        }
+    }
+    let Some(editor) = editor_query.iter().next() else {
+        return;
+    };
+
+    for chunk in &draw_data.dirty {
+        let Some(data) = draw_data.chunks.get(chunk) else {
+            continue;
+        };
+        let positions: Vec<[f32; 3]> = data
+            .entities
+            .values()
+            .flat_map(|entity| entity.fills.iter().flatten())
+            .map(|p| p.to_array())
+            .collect();
+        if positions.is_empty() {
+            continue;
+        }
+        let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        let material = materials.add(StandardMaterial {
+            base_color: data.color,
+            unlit: true,
+            // 从下方也能看到
+            cull_mode: None,
+            double_sided: true,
+            ..default()
+        });
+
+        let fill = commands
+            .spawn((
+                EditorPart,
+                DxfFillMesh,
+                DxfLayerMesh {
+                    chunk: chunk.clone(),
+                },
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d(material),
+                Transform::default(),
+                layer_visibility(&layers, &chunk.layer),
+            ))
+            .id();
+        commands.entity(editor).add_child(fill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn zero_bulge_is_a_line() {
+        let segment = bulge_segment(Vec3::ZERO, Vec3::X, 0.0);
+        assert!(matches!(segment, PolylineSegment::Line { .. }));
+    }
+
+    #[test]
+    fn unit_bulge_is_a_half_circle() {
+        let PolylineSegment::Arc {
+            center,
+            radius,
+            sweep,
+            ..
+        } = bulge_segment(Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0), 1.0)
+        else {
+            panic!("bulge 1 should give an arc");
+        };
+        assert!(center.distance(Vec3::X) < 1e-5);
+        assert!((radius - 1.0).abs() < 1e-5);
+        assert!((sweep - PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn negative_bulge_sweeps_clockwise() {
+        let PolylineSegment::Arc { center, sweep, .. } =
+            bulge_segment(Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0), -(PI / 8.0).tan())
+        else {
+            panic!("bulge should give an arc");
+        };
+        assert!((sweep + PI / 2.0).abs() < 1e-5);
+        // 顺时针的四分之一圆弧，圆心在弦的右侧
+        assert!(center.distance(Vec3::new(1.0, 0.0, -1.0)) < 1e-5);
+    }
+}
@@ -1,10 +1,26 @@
+use bevy::ecs::query::QueryData;
 use bevy::prelude::*;
-use bevy_panorbit_camera::PanOrbitCamera;
 use dxf::Drawing;
-use dxf::entities::EntityType;
+use dxf::entities::{EntityCommon, EntityType};
+use dxf::enums::{AttachmentPoint, HorizontalTextJustification, VerticalTextJustification};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::f32::consts::TAU;
 use std::path::PathBuf;
 
-use crate::editor::Editor;
+use super::dxf_block::{InsertEntity, transform_geometry};
+use super::dxf_color::CadColor;
+use super::dxf_curve::{EllipseEntity, SplineEntity, ellipse_from_dxf, spline_from_dxf};
+use super::dxf_dimension::DimensionEntity;
+use super::dxf_hatch::HatchEntity;
+use super::dxf_linetype::{LineTypeTable, dash_polyline};
+use super::dxf_lineweight::{
+    DEFAULT_LINE_WEIGHT, LineWeightDisplay, LineWeightGizmos, line_weight_from_dxf,
+    line_weight_level,
+};
+use super::dxf_ocs::entity_ocs;
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::{CONTINUOUS, LayerTable};
 
 /// DXF 加载消息
 #[derive(Message, Debug)]
@@ -19,33 +35,106 @@ pub enum CadEntityType {
     Circle,
     Arc,
     Polyline,
+    Text,
+    Insert,
+    Dimension,
+    Spline,
+    Ellipse,
+    Hatch,
 }
 
 /// CAD 实体组件 - 标记和存储CAD实体信息
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct CadEntity {
     pub entity_type: CadEntityType,
     pub layer: String,
+    /// 按随层/随块规则解析后的显示颜色
     pub color: Color,
+    /// 颜色设置（随层、随块、ACI 或真彩色）
+    pub color_spec: CadColor,
+    /// 按随层/随块规则解析后的线型名
+    pub line_type: String,
+    /// 实体的线型比例，显示时再乘以全局线型比例
+    pub line_type_scale: f32,
+    /// 按随层/随块规则解析后的线宽（毫米）
+    pub line_weight: f32,
     pub selectable: bool,
 }
 
+impl CadEntity {
+    /// 由 DXF 实体的通用属性生成，block 是引用块的实体（顶层实体为 None）
+    ///
+    /// 块中 0 层上的实体随引用块的实体所在的图层，只有顶层实体可以选择。
+    pub(super) fn from_dxf(
+        common: &EntityCommon,
+        entity_type: CadEntityType,
+        layers: &LayerTable,
+        block: Option<&CadEntity>,
+    ) -> Self {
+        let layer = match block {
+            Some(block) if common.layer == "0" => block.layer.clone(),
+            _ => common.layer.clone(),
+        };
+        let color_spec = CadColor::from_dxf(common);
+        let block_color = block.map_or(Color::WHITE, |block| block.color);
+        let line_type = match common.line_type_name.to_uppercase().as_str() {
+            "" | "BYLAYER" => layers.line_type(&layer).to_string(),
+            "BYBLOCK" => block.map_or(CONTINUOUS.to_string(), |block| block.line_type.clone()),
+            _ => common.line_type_name.clone(),
+        };
+        let line_weight = match common.lineweight_enum_value {
+            -1 => layers.line_weight(&layer),
+            -2 => block.map_or(DEFAULT_LINE_WEIGHT, |block| block.line_weight),
+            value => line_weight_from_dxf(value).unwrap_or(DEFAULT_LINE_WEIGHT),
+        };
+        Self {
+            entity_type,
+            color: color_spec.resolve(layers.color(&layer), block_color),
+            layer,
+            color_spec,
+            line_type,
+            line_type_scale: common.line_type_scale as f32,
+            line_weight,
+            selectable: block.is_none(),
+        }
+    }
+
+    /// 新绘制的实体：颜色、线型和线宽随层，线型比例为图纸的 CELTSCALE
+    pub fn new(
+        entity_type: CadEntityType,
+        layer: &str,
+        layers: &LayerTable,
+        line_types: &LineTypeTable,
+    ) -> Self {
+        Self {
+            entity_type,
+            layer: layer.to_string(),
+            color: layers.color(layer),
+            color_spec: CadColor::ByLayer,
+            line_type: layers.line_type(layer).to_string(),
+            line_type_scale: line_types.entity_scale,
+            line_weight: layers.line_weight(layer),
+            selectable: !layers.is_locked(layer),
+        }
+    }
+}
+
 /// 线段实体数据
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct LineEntity {
     pub start: Vec3,
     pub end: Vec3,
 }
 
 /// 圆形实体数据
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct CircleEntity {
     pub center: Vec3,
     pub radius: f32,
 }
 
 /// 弧形实体数据
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct ArcEntity {
     pub center: Vec3,
     pub radius: f32,
@@ -54,309 +143,809 @@ pub struct ArcEntity {
 }
 
 /// 多段线实体数据
-#[derive(Component, Debug)]
+///
+/// 顶点的 y 坐标即多段线的标高。凸度和宽度按顶点存放，描述从该顶点出发的线段，
+/// 为空表示全为 0。
+#[derive(Component, Debug, Clone)]
 pub struct PolylineEntity {
     pub vertices: Vec<Vec3>,
     pub closed: bool,
+    /// 凸度：线段圆心角四分之一的正切，正值为逆时针圆弧
+    pub bulges: Vec<f32>,
+    /// 线段的起点宽度和终点宽度
+    pub widths: Vec<[f32; 2]>,
+}
+
+/// 文字水平对齐
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextHAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 文字垂直对齐
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextVAlign {
+    #[default]
+    Baseline,
+    Bottom,
+    Middle,
+    Top,
 }
 
-/// 存储已加载的 DXF 数据用于 Gizmos 绘制
+/// 文字实体数据（TEXT / MTEXT）
+#[derive(Component, Debug, Clone)]
+pub struct TextEntity {
+    /// 对齐点
+    pub position: Vec3,
+    /// 字高
+    pub height: f32,
+    /// 在 DXF XY 平面内的旋转角（弧度）
+    pub rotation: f32,
+    pub h_align: TextHAlign,
+    pub v_align: TextVAlign,
+    /// 原始内容，MTEXT 含格式代码
+    pub content: String,
+    pub is_mtext: bool,
+    /// MTEXT 参考矩形宽度，超出时换行
+    pub wrap_width: Option<f32>,
+    /// TEXT 对齐/布满方式时两个对齐点的距离
+    pub fit_width: Option<f32>,
+}
+
+/// 导入时的原始 DXF 实体，导出时保留其公共属性（句柄、线型、扩展数据等）
+#[derive(Component, Debug, Clone)]
+pub struct DxfSource(pub dxf::entities::Entity);
+
+/// 当前图纸对应的源 DXF，导出时保留表头、图层和未识别的实体
 #[derive(Resource, Default)]
-pub struct DxfDrawData {
-    pub lines: Vec<(Vec3, Vec3, Color)>,
-    pub circles: Vec<(Vec3, f32, Color)>,
-    pub arcs: Vec<(Vec3, f32, f32, f32, Color)>,
-    pub polylines: Vec<(Vec<Vec3>, bool, Color)>,
+pub struct SourceDrawing {
+    /// 源 DXF 文件路径
+    pub path: Option<PathBuf>,
+    /// 加载时读取的文件内容；为 None 时导出会按 path 重新读取
+    pub bytes: Option<Vec<u8>>,
+    /// 导入为编辑器实体的源实体句柄
+    ///
+    /// 导出时这些源实体由编辑器中的实体重新生成（在编辑器中删除的不再写出），其余原样保留。
+    pub imported: BTreeSet<u64>,
 }
 
-impl DxfDrawData {
+impl SourceDrawing {
     pub fn clear(&mut self) {
-        self.lines.clear();
-        self.circles.clear();
-        self.arcs.clear();
-        self.polylines.clear();
+        self.path = None;
+        self.bytes = None;
+        self.imported.clear();
     }
-}
 
-/// DXF 加载系统 - 解析 DXF 并创建 CAD 实体
-pub fn dxf_load_system(
-    mut messages: MessageReader<LoadDxfMessage>,
-    mut commands: Commands,
-    mut draw_data: ResMut<DxfDrawData>,
-    children_query: Query<&Children, With<Editor>>,
-    editor_query: Query<Entity, With<Editor>>,
-    // 用 Has<T> 或 Query 过滤相机和灯光
-    to_keep_query: Query<(Has<PanOrbitCamera>, Has<DirectionalLight>)>,
-) {
-    for message in messages.read() {
-        draw_data.clear();
-        
-        // 清理当前的 CAD 实体
-        for children in &children_query {
-            for &child in children {
-                // 检查这个子实体是否需要保留
-                if let Ok((is_camera, is_light)) = to_keep_query.get(child) {
-                    if !is_camera && !is_light {
-                        // 如果既不是相机也不是灯光，则删除
-                        commands.entity(child).despawn();
-                    }
+    /// 重新解析源图纸（Drawing 不能克隆，每次导出都解析一份）
+    ///
+    /// 没有源 DXF 或读取失败时返回 None。
+    pub fn drawing(&mut self) -> Option<Drawing> {
+        if self.bytes.is_none() {
+            let path = self.path.as_ref()?;
+            match std::fs::read(path) {
+                Ok(bytes) => self.bytes = Some(bytes),
+                Err(e) => {
+                    eprintln!("读取源DXF失败 {:?}: {}", path, e);
+                    return None;
                 }
             }
         }
-
-        let drawing = match Drawing::load_file(&message.path) {
-            Ok(d) => d,
+        let bytes = self.bytes.as_ref()?;
+        match Drawing::load(&mut bytes.as_slice()) {
+            Ok(drawing) => Some(drawing),
             Err(e) => {
-                eprintln!("加载DXF失败: {}", e);
-                continue;
+                eprintln!("解析源DXF失败: {}", e);
+                None
             }
+        }
+    }
+}
+
+/// DXF 坐标 (x, y, z) 转换为世界坐标：DXF 的 XY 平面对应世界的 XZ 平面
+pub fn dxf_to_world(x: f64, y: f64, z: f64) -> Vec3 {
+    Vec3::new(x as f32, z as f32, y as f32)
+}
+
+/// 世界坐标转换回 DXF 坐标
+pub fn world_to_dxf(v: Vec3) -> dxf::Point {
+    dxf::Point::new(v.x as f64, v.z as f64, v.y as f64)
+}
+
+/// 一个实体的绘制数据，颜色由所在的分块决定
+#[derive(Default)]
+pub struct EntityDrawData {
+    pub lines: Vec<(Vec3, Vec3)>,
+    pub circles: Vec<(Vec3, f32)>,
+    pub arcs: Vec<(Vec3, f32, f32, f32)>,
+    pub polylines: Vec<(Vec<Vec3>, bool)>,
+    /// 已离散的折线：样条曲线、椭圆和按线型生成的线段
+    pub curves: Vec<Vec<Vec3>>,
+    /// 显示线宽时较粗的折线，第二项是线宽档位（从 1 开始）
+    pub weighted: Vec<(Vec<Vec3>, usize)>,
+    /// 填充三角形（每 3 个点一个三角形），由填充网格绘制
+    pub fills: Vec<Vec<Vec3>>,
+}
+
+impl EntityDrawData {
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+            && self.circles.is_empty()
+            && self.arcs.is_empty()
+            && self.polylines.is_empty()
+            && self.curves.is_empty()
+            && self.weighted.is_empty()
+            && self.fills.is_empty()
+    }
+}
+
+/// 绘制分块：同一图层中同一颜色的实体合并生成网格，实体改变时只重建所在的分块
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DrawChunk {
+    pub layer: String,
+    /// sRGBA 颜色
+    pub color: [u8; 4],
+}
+
+/// 一个分块中各实体的绘制数据
+pub struct ChunkDrawData {
+    pub color: Color,
+    pub entities: HashMap<Entity, EntityDrawData>,
+}
+
+/// 按图层和颜色分块存储已加载的 DXF 数据，由线网格、填充网格和 Gizmos 绘制
+#[derive(Resource, Default)]
+pub struct DxfDrawData {
+    pub chunks: BTreeMap<DrawChunk, ChunkDrawData>,
+    /// 实体所在的分块
+    entity_chunks: HashMap<Entity, DrawChunk>,
+    /// 本帧有变化、网格需要重建的分块（包括已经删除的分块）
+    pub dirty: HashSet<DrawChunk>,
+}
+
+impl DxfDrawData {
+    /// 删除所有实体，原有分块的网格都要删除
+    pub fn clear(&mut self) {
+        let chunks = std::mem::take(&mut self.chunks);
+        self.dirty.extend(chunks.into_keys());
+        self.entity_chunks.clear();
+    }
+
+    /// 设置实体的绘制数据，实体原来所在的分块和新的分块都标记为需要重建
+    pub fn insert(&mut self, entity: Entity, layer: &str, color: Color, data: EntityDrawData) {
+        self.remove(entity);
+        if data.is_empty() {
+            return;
+        }
+        let chunk = DrawChunk {
+            layer: layer.to_string(),
+            color: color.to_srgba().to_u8_array(),
         };
+        self.chunks
+            .entry(chunk.clone())
+            .or_insert_with(|| ChunkDrawData {
+                color,
+                entities: HashMap::new(),
+            })
+            .entities
+            .insert(entity, data);
+        self.entity_chunks.insert(entity, chunk.clone());
+        self.dirty.insert(chunk);
+    }
 
-        // 获取 Editor 实体
-        let editor_entity = if let Some(entity) = editor_query.iter().next() {
-            entity
-        } else {
-            eprintln!("未找到 Editor 实体");
-            continue;
+    /// 删除实体的绘制数据，所在的分块标记为需要重建
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(chunk) = self.entity_chunks.remove(&entity) else {
+            return;
         };
+        if let Some(data) = self.chunks.get_mut(&chunk) {
+            data.entities.remove(&entity);
+            if data.entities.is_empty() {
+                self.chunks.remove(&chunk);
+            }
+        }
+        self.dirty.insert(chunk);
+    }
+}
 
-        let line_color = Color::WHITE;
-        let circle_color = Color::srgb(0.0, 1.0, 1.0);
-        let arc_color = Color::srgb(1.0, 1.0, 0.0);
-        let poly_color = Color::srgb(0.0, 1.0, 0.0);
-
-        let mut line_count = 0;
-        let mut circle_count = 0;
-        let mut arc_count = 0;
-        let mut polyline_count = 0;
-
-        for ent in drawing.entities() {
-            match &ent.specific {
-                EntityType::Line(line) => {
-                    let start = Vec3::new(line.p1.x as f32, line.p1.z as f32, line.p1.y as f32);
-                    let end = Vec3::new(line.p2.x as f32, line.p2.z as f32, line.p2.y as f32);
-                    
-                    // 添加到绘制数据
-                    draw_data.lines.push((start, end, line_color));
-                    
-                    // 创建CAD实体（不可见，但可选择）
-                    let line_entity = commands.spawn((
-                        Transform::from_translation((start + end) * 0.5),
-                        Visibility::Hidden, // 隐藏，因为我们用Gizmos绘制
-                        CadEntity {
-                            entity_type: CadEntityType::Line,
-                            layer: ent.common.layer.clone(),
-                            color: line_color,
-                            selectable: true,
-                        },
-                        LineEntity { start, end },
-                    )).id();
-                    
-                    commands.entity(editor_entity).add_child(line_entity);
-                    line_count += 1;
-                }
-                EntityType::Circle(c) => {
-                    let center = Vec3::new(c.center.x as f32, c.center.z as f32, c.center.y as f32);
-                    let radius = c.radius as f32;
-                    
-                    // 添加到绘制数据
-                    draw_data.circles.push((center, radius, circle_color));
-                    
-                    // 创建CAD实体
-                    let circle_entity = commands.spawn((
-                        Transform::from_translation(center),
-                        Visibility::Hidden,
-                        CadEntity {
-                            entity_type: CadEntityType::Circle,
-                            layer: ent.common.layer.clone(),
-                            color: circle_color,
-                            selectable: true,
-                        },
-                        CircleEntity { center, radius },
-                    )).id();
-                    
-                    commands.entity(editor_entity).add_child(circle_entity);
-                    circle_count += 1;
-                }
-                EntityType::Arc(arc) => {
-                    let center = Vec3::new(
-                        arc.center.x as f32,
-                        arc.center.z as f32,
-                        arc.center.y as f32,
-                    );
-                    let radius = arc.radius as f32;
-                    let start_angle = arc.start_angle.to_radians() as f32;
-                    let end_angle = arc.end_angle.to_radians() as f32;
-                    
-                    // 添加到绘制数据
-                    draw_data.arcs.push((center, radius, start_angle, end_angle, arc_color));
-                    
-                    // 创建CAD实体
-                    let arc_entity = commands.spawn((
-                        Transform::from_translation(center),
-                        Visibility::Hidden,
-                        CadEntity {
-                            entity_type: CadEntityType::Arc,
-                            layer: ent.common.layer.clone(),
-                            color: arc_color,
-                            selectable: true,
-                        },
-                        ArcEntity {
-                            center,
-                            radius,
-                            start_angle,
-                            end_angle,
-                        },
-                    )).id();
-                    
-                    commands.entity(editor_entity).add_child(arc_entity);
-                    arc_count += 1;
-                }
-                EntityType::LwPolyline(pl) => {
-                    if pl.vertices.len() >= 2 {
-                        let vertices: Vec<Vec3> = pl
-                            .vertices
-                            .iter()
-                            .map(|v| Vec3::new(v.x as f32, 0.0, v.y as f32))
-                            .collect();
-                        let closed = pl.is_closed();
-                        
-                        // 添加到绘制数据
-                        draw_data.polylines.push((vertices.clone(), closed, poly_color));
-                        
-                        // 计算中心点
-                        let center = vertices.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / vertices.len() as f32;
-                        
-                        // 创建CAD实体
-                        let polyline_entity = commands.spawn((
-                            Transform::from_translation(center),
-                            Visibility::Hidden,
-                            CadEntity {
-                                entity_type: CadEntityType::Polyline,
-                                layer: ent.common.layer.clone(),
-                                color: poly_color,
-                                selectable: true,
-                            },
-                            PolylineEntity { vertices, closed },
-                        )).id();
-                        
-                        commands.entity(editor_entity).add_child(polyline_entity);
-                        polyline_count += 1;
-                    }
-                }
-                EntityType::Polyline(pl) => {
-                    let verts: Vec<_> = pl.vertices().collect();
-                    if verts.len() >= 2 {
-                        let vertices: Vec<Vec3> = verts
-                            .iter()
-                            .map(|v| {
-                                Vec3::new(
-                                    v.location.x as f32,
-                                    v.location.z as f32,
-                                    v.location.y as f32,
-                                )
-                            })
-                            .collect();
-                        let closed = pl.is_closed();
-                        
-                        // 添加到绘制数据
-                        draw_data.polylines.push((vertices.clone(), closed, poly_color));
-                        
-                        // 计算中心点
-                        let center = vertices.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / vertices.len() as f32;
-                        
-                        // 创建CAD实体
-                        let polyline_entity = commands.spawn((
-                            Transform::from_translation(center),
-                            Visibility::Hidden,
-                            CadEntity {
-                                entity_type: CadEntityType::Polyline,
-                                layer: ent.common.layer.clone(),
-                                color: poly_color,
-                                selectable: true,
-                            },
-                            PolylineEntity { vertices, closed },
-                        )).id();
-                        
-                        commands.entity(editor_entity).add_child(polyline_entity);
-                        polyline_count += 1;
+/// CAD 几何数据 - 生成 CAD 实体时使用
+#[derive(Debug, Clone)]
+pub enum CadGeometry {
+    Line(LineEntity),
+    Circle(CircleEntity),
+    Arc(ArcEntity),
+    Polyline(PolylineEntity),
+    Text(TextEntity),
+    Insert(InsertEntity),
+    Dimension(DimensionEntity),
+    Spline(SplineEntity),
+    Ellipse(EllipseEntity),
+    Hatch(HatchEntity),
+}
+
+/// 实体上的几何组件，用于从组件还原 CadGeometry
+#[derive(QueryData)]
+pub struct CadGeometryQuery {
+    pub line: Option<&'static LineEntity>,
+    pub circle: Option<&'static CircleEntity>,
+    pub arc: Option<&'static ArcEntity>,
+    pub polyline: Option<&'static PolylineEntity>,
+    pub text: Option<&'static TextEntity>,
+    pub insert: Option<&'static InsertEntity>,
+    pub dimension: Option<&'static DimensionEntity>,
+    pub spline: Option<&'static SplineEntity>,
+    pub ellipse: Option<&'static EllipseEntity>,
+    pub hatch: Option<&'static HatchEntity>,
+}
+
+impl CadGeometryQueryItem<'_, '_> {
+    /// 从实体上的几何组件还原
+    pub fn geometry(&self) -> Option<CadGeometry> {
+        if let Some(line) = self.line {
+            Some(CadGeometry::Line(line.clone()))
+        } else if let Some(circle) = self.circle {
+            Some(CadGeometry::Circle(circle.clone()))
+        } else if let Some(arc) = self.arc {
+            Some(CadGeometry::Arc(arc.clone()))
+        } else if let Some(pl) = self.polyline {
+            Some(CadGeometry::Polyline(pl.clone()))
+        } else if let Some(text) = self.text {
+            Some(CadGeometry::Text(text.clone()))
+        } else if let Some(insert) = self.insert {
+            Some(CadGeometry::Insert(insert.clone()))
+        } else if let Some(dimension) = self.dimension {
+            Some(CadGeometry::Dimension(dimension.clone()))
+        } else if let Some(spline) = self.spline {
+            Some(CadGeometry::Spline(spline.clone()))
+        } else if let Some(ellipse) = self.ellipse {
+            Some(CadGeometry::Ellipse(ellipse.clone()))
+        } else {
+            self.hatch.map(|hatch| CadGeometry::Hatch(hatch.clone()))
+        }
+    }
+
+    /// 绘制数据是否按弦高误差离散（圆、圆弧、有凸度的多段线、样条曲线和椭圆）
+    pub fn is_curve(&self) -> bool {
+        self.circle.is_some()
+            || self.arc.is_some()
+            || self.spline.is_some()
+            || self.ellipse.is_some()
+            || self
+                .polyline
+                .is_some_and(|pl| pl.bulges.iter().any(|b| *b != 0.0))
+    }
+
+    /// 实体自身的轮廓折线（不含子实体），用于拾取、高亮和计算范围
+    ///
+    /// 文字和标注只有插入点（单点折线），块参照的图形都在子实体中。
+    pub fn outline(&self, tolerance: &CurveTolerance) -> Vec<Vec<Vec3>> {
+        if let Some(line) = self.line {
+            vec![vec![line.start, line.end]]
+        } else if let Some(c) = self.circle {
+            vec![arc_points(c.center, c.radius, 0.0, TAU, tolerance)]
+        } else if let Some(a) = self.arc {
+            vec![arc_points(
+                a.center,
+                a.radius,
+                a.start_angle,
+                a.end_angle,
+                tolerance,
+            )]
+        } else if let Some(pl) = self.polyline {
+            vec![pl.tessellate(tolerance)]
+        } else if let Some(spline) = self.spline {
+            vec![spline.tessellate(tolerance)]
+        } else if let Some(ellipse) = self.ellipse {
+            vec![ellipse.tessellate(tolerance)]
+        } else if let Some(hatch) = self.hatch {
+            hatch
+                .loops
+                .iter()
+                .map(|points| points.iter().chain(points.first()).copied().collect())
+                .collect()
+        } else if let Some(text) = self.text {
+            vec![vec![text.position]]
+        } else if let Some(dimension) = self.dimension {
+            vec![vec![dimension.text_position]]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl CadGeometry {
+    pub fn entity_type(&self) -> CadEntityType {
+        match self {
+            CadGeometry::Line(_) => CadEntityType::Line,
+            CadGeometry::Circle(_) => CadEntityType::Circle,
+            CadGeometry::Arc(_) => CadEntityType::Arc,
+            CadGeometry::Polyline(_) => CadEntityType::Polyline,
+            CadGeometry::Text(_) => CadEntityType::Text,
+            CadGeometry::Insert(_) => CadEntityType::Insert,
+            CadGeometry::Dimension(_) => CadEntityType::Dimension,
+            CadGeometry::Spline(_) => CadEntityType::Spline,
+            CadGeometry::Ellipse(_) => CadEntityType::Ellipse,
+            CadGeometry::Hatch(_) => CadEntityType::Hatch,
+        }
+    }
+
+    /// 实体的参考位置（用作 Transform）
+    pub fn anchor(&self) -> Vec3 {
+        match self {
+            CadGeometry::Line(line) => (line.start + line.end) * 0.5,
+            CadGeometry::Circle(circle) => circle.center,
+            CadGeometry::Arc(arc) => arc.center,
+            CadGeometry::Polyline(pl) => {
+                pl.vertices.iter().fold(Vec3::ZERO, |acc, v| acc + *v)
+                    / pl.vertices.len().max(1) as f32
+            }
+            CadGeometry::Text(text) => text.position,
+            CadGeometry::Insert(insert) => insert.position,
+            CadGeometry::Dimension(dimension) => dimension.text_position,
+            CadGeometry::Spline(spline) => {
+                let points = if spline.control_points.is_empty() {
+                    &spline.fit_points
+                } else {
+                    &spline.control_points
+                };
+                points.iter().fold(Vec3::ZERO, |acc, v| acc + *v) / points.len().max(1) as f32
+            }
+            CadGeometry::Ellipse(ellipse) => ellipse.center,
+            CadGeometry::Hatch(hatch) => hatch.center(),
+        }
+    }
+}
+
+/// 在 parent 下生成一个 CAD 实体（不可见，由 Gizmos 绘制）
+pub fn spawn_cad_entity(
+    commands: &mut Commands,
+    parent: Entity,
+    cad: CadEntity,
+    geometry: CadGeometry,
+) -> Entity {
+    let mut entity = commands.spawn((
+        Transform::from_translation(geometry.anchor()),
+        Visibility::Hidden, // 隐藏，因为我们用Gizmos绘制
+        cad,
+    ));
+    insert_geometry(&mut entity, geometry);
+    let id = entity.id();
+    commands.entity(parent).add_child(id);
+    id
+}
+
+/// 把几何数据作为组件加到实体上，已有同类组件时替换
+pub fn insert_geometry(entity: &mut EntityCommands, geometry: CadGeometry) {
+    match geometry {
+        CadGeometry::Line(line) => entity.insert(line),
+        CadGeometry::Circle(circle) => entity.insert(circle),
+        CadGeometry::Arc(arc) => entity.insert(arc),
+        CadGeometry::Polyline(pl) => entity.insert(pl),
+        CadGeometry::Text(text) => entity.insert(text),
+        CadGeometry::Insert(insert) => entity.insert(insert),
+        CadGeometry::Dimension(dimension) => entity.insert(dimension),
+        CadGeometry::Spline(spline) => entity.insert(spline),
+        CadGeometry::Ellipse(ellipse) => entity.insert(ellipse),
+        CadGeometry::Hatch(hatch) => entity.insert(hatch),
+    };
+}
+
+/// DXF 实体转换为几何数据和显示颜色（块参照除外）
+///
+/// elevation 是实体公共数据中的标高，dxf 库把 LWPOLYLINE 的标高（38 组码）读到这里。
+pub(super) fn geometry_from_dxf(specific: &EntityType, elevation: f64) -> Option<CadGeometry> {
+    let geometry = match specific {
+        EntityType::Line(line) => {
+            let start = dxf_to_world(line.p1.x, line.p1.y, line.p1.z);
+            let end = dxf_to_world(line.p2.x, line.p2.y, line.p2.z);
+            CadGeometry::Line(LineEntity { start, end })
+        }
+        EntityType::Circle(c) => {
+            let center = dxf_to_world(c.center.x, c.center.y, c.center.z);
+            let radius = c.radius as f32;
+            CadGeometry::Circle(CircleEntity { center, radius })
+        }
+        EntityType::Arc(arc) => {
+            let center = dxf_to_world(arc.center.x, arc.center.y, arc.center.z);
+            let radius = arc.radius as f32;
+            let start_angle = arc.start_angle.to_radians() as f32;
+            let end_angle = arc.end_angle.to_radians() as f32;
+            CadGeometry::Arc(ArcEntity {
+                center,
+                radius,
+                start_angle,
+                end_angle,
+            })
+        }
+        EntityType::LwPolyline(pl) => {
+            if pl.vertices.len() < 2 {
+                return None;
+            }
+            let vertices = pl
+                .vertices
+                .iter()
+                .map(|v| dxf_to_world(v.x, v.y, elevation))
+                .collect();
+            // 全局宽度对没有单独设置宽度的顶点生效
+            let widths = pl
+                .vertices
+                .iter()
+                .map(|v| {
+                    if v.starting_width == 0.0 && v.ending_width == 0.0 {
+                        [pl.constant_width as f32; 2]
+                    } else {
+                        [v.starting_width as f32, v.ending_width as f32]
                     }
-                }
-                _ => {}
+                })
+                .collect();
+            CadGeometry::Polyline(PolylineEntity {
+                vertices,
+                closed: pl.is_closed(),
+                bulges: pl.vertices.iter().map(|v| v.bulge as f32).collect(),
+                widths,
+            })
+        }
+        EntityType::Polyline(pl) => {
+            let verts: Vec<_> = pl.vertices().collect();
+            if verts.len() < 2 {
+                return None;
             }
+            // 二维多段线的标高在多段线的定位点上，三维多段线的定位点为原点
+            let elevation = pl.location.z;
+            let vertices = verts
+                .iter()
+                .map(|v| dxf_to_world(v.location.x, v.location.y, v.location.z + elevation))
+                .collect();
+            CadGeometry::Polyline(PolylineEntity {
+                vertices,
+                closed: pl.is_closed(),
+                bulges: verts.iter().map(|v| v.bulge as f32).collect(),
+                widths: verts
+                    .iter()
+                    .map(|v| [v.starting_width as f32, v.ending_width as f32])
+                    .collect(),
+            })
         }
-        
-        println!(
-            "DXF 加载完成: {:?}, 线段: {}, 圆: {}, 弧: {}, 多段线: {}",
-            message.path, line_count, circle_count, arc_count, polyline_count
-        );
+        EntityType::Spline(spline) => CadGeometry::Spline(spline_from_dxf(spline)?),
+        EntityType::Ellipse(ellipse) => CadGeometry::Ellipse(ellipse_from_dxf(ellipse)),
+        EntityType::Solid(solid) => {
+            // SOLID 的顶点顺序是 1-2-4-3，三角形时第 3、4 点重合
+            let mut corners = vec![
+                &solid.first_corner,
+                &solid.second_corner,
+                &solid.fourth_corner,
+                &solid.third_corner,
+            ];
+            if solid.third_corner == solid.fourth_corner {
+                corners.remove(2);
+            }
+            // 按实体填充显示（例如标注的箭头）
+            CadGeometry::Hatch(HatchEntity {
+                pattern: "SOLID".to_string(),
+                solid: true,
+                loops: vec![
+                    corners
+                        .into_iter()
+                        .map(|p| dxf_to_world(p.x, p.y, p.z))
+                        .collect(),
+                ],
+                lines: Vec::new(),
+                handle: None,
+            })
+        }
+        EntityType::Text(t) => CadGeometry::Text(text_from_dxf(t)),
+        EntityType::MText(t) => CadGeometry::Text(mtext_from_dxf(t)),
+        _ => return None,
+    };
+    // 圆、弧、二维多段线等的坐标在 OCS 中，按拉伸方向变换到世界坐标
+    match entity_ocs(specific) {
+        Some(ocs) => Some(transform_geometry(geometry, &ocs)),
+        None => Some(geometry),
     }
 }
 
-/// DXF Gizmos 绘制系统 - 每帧绘制
-pub fn dxf_gizmos_system(mut gizmos: Gizmos, draw_data: Res<DxfDrawData>) {
-    // 绘制线段
-    for (start, end, color) in &draw_data.lines {
-        gizmos.line(*start, *end, *color);
+/// TEXT 转换为文字实体
+pub(super) fn text_from_dxf(t: &dxf::entities::Text) -> TextEntity {
+    let location = dxf_to_world(t.location.x, t.location.y, t.location.z);
+    let second = dxf_to_world(
+        t.second_alignment_point.x,
+        t.second_alignment_point.y,
+        t.second_alignment_point.z,
+    );
+    let mut text = TextEntity {
+        position: location,
+        height: t.text_height as f32,
+        rotation: t.rotation.to_radians() as f32,
+        h_align: TextHAlign::Left,
+        v_align: match t.vertical_text_justification {
+            VerticalTextJustification::Baseline => TextVAlign::Baseline,
+            VerticalTextJustification::Bottom => TextVAlign::Bottom,
+            VerticalTextJustification::Middle => TextVAlign::Middle,
+            VerticalTextJustification::Top => TextVAlign::Top,
+        },
+        content: t.value.clone(),
+        is_mtext: false,
+        wrap_width: None,
+        fit_width: None,
+    };
+    match t.horizontal_text_justification {
+        HorizontalTextJustification::Left => {}
+        HorizontalTextJustification::Center => text.h_align = TextHAlign::Center,
+        HorizontalTextJustification::Right => text.h_align = TextHAlign::Right,
+        HorizontalTextJustification::Middle => {
+            text.h_align = TextHAlign::Center;
+            text.v_align = TextVAlign::Middle;
+        }
+        // 对齐/布满：文字排在两个对齐点之间
+        HorizontalTextJustification::Aligned | HorizontalTextJustification::Fit => {
+            let delta = second - location;
+            text.fit_width = Some(delta.length());
+            text.rotation = delta.z.atan2(delta.x);
+            text.v_align = TextVAlign::Baseline;
+            return text;
+        }
     }
+    // 非默认对齐时，对齐点是第二对齐点
+    if text.h_align != TextHAlign::Left || text.v_align != TextVAlign::Baseline {
+        text.position = second;
+    }
+    text
+}
 
-    // 绘制圆（在 XZ 平面）
-    for (center, radius, color) in &draw_data.circles {
-        // Gizmos 的 circle 需要指定法向量，DXF 通常在 XZ 平面
-        gizmos.circle(
-            Isometry3d::new(*center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
-            *radius,
-            *color,
-        );
+/// MTEXT 转换为文字实体
+fn mtext_from_dxf(t: &dxf::entities::MText) -> TextEntity {
+    let (h_align, v_align) = match t.attachment_point {
+        AttachmentPoint::TopLeft => (TextHAlign::Left, TextVAlign::Top),
+        AttachmentPoint::TopCenter => (TextHAlign::Center, TextVAlign::Top),
+        AttachmentPoint::TopRight => (TextHAlign::Right, TextVAlign::Top),
+        AttachmentPoint::MiddleLeft => (TextHAlign::Left, TextVAlign::Middle),
+        AttachmentPoint::MiddleCenter => (TextHAlign::Center, TextVAlign::Middle),
+        AttachmentPoint::MiddleRight => (TextHAlign::Right, TextVAlign::Middle),
+        AttachmentPoint::BottomLeft => (TextHAlign::Left, TextVAlign::Bottom),
+        AttachmentPoint::BottomCenter => (TextHAlign::Center, TextVAlign::Bottom),
+        AttachmentPoint::BottomRight => (TextHAlign::Right, TextVAlign::Bottom),
+    };
+    // X 轴方向优先于旋转角
+    let x_axis = &t.x_axis_direction;
+    let rotation = if x_axis.x != 0.0 || x_axis.y != 0.0 {
+        x_axis.y.atan2(x_axis.x) as f32
+    } else {
+        t.rotation_angle as f32
+    };
+    // 超长内容分段存放在扩展文本中，位于主文本之前
+    let mut content = t.extended_text.concat();
+    content.push_str(&t.text);
+    TextEntity {
+        position: dxf_to_world(
+            t.insertion_point.x,
+            t.insertion_point.y,
+            t.insertion_point.z,
+        ),
+        height: t.initial_text_height as f32,
+        rotation,
+        h_align,
+        v_align,
+        content,
+        is_mtext: true,
+        wrap_width: (t.reference_rectangle_width > 0.0)
+            .then_some(t.reference_rectangle_width as f32),
+        fit_width: None,
     }
+}
 
-    // 绘制弧
-    for (center, radius, start_angle, end_angle, color) in &draw_data.arcs {
-        draw_arc(
-            &mut gizmos,
-            *center,
-            *radius,
-            *start_angle,
-            *end_angle,
-            *color,
+/// 绘制数据同步系统 - CAD 实体有增删改时，更新这些实体的 DxfDrawData
+///
+/// 所有图层的实体都加入绘制数据，图层的开关和冻结由网格的可见性处理，不需要重建。
+/// 修改实体（例如在属性面板中拖动数值）时只更新该实体，网格系统只重建它所在的分块。
+/// 曲线按当前的弦高误差离散，缩放跨过一档精度时只重新离散曲线，
+/// 直线和填充所在分块的网格保持不变。
+#[allow(clippy::type_complexity)]
+pub fn dxf_draw_data_sync_system(
+    mut draw_data: ResMut<DxfDrawData>,
+    tolerance: Res<CurveTolerance>,
+    line_types: Res<LineTypeTable>,
+    line_weight_display: Res<LineWeightDisplay>,
+    changed_query: Query<
+        Entity,
+        Or<(
+            Changed<CadEntity>,
+            Changed<LineEntity>,
+            Changed<CircleEntity>,
+            Changed<ArcEntity>,
+            Changed<PolylineEntity>,
+            Changed<SplineEntity>,
+            Changed<EllipseEntity>,
+            Changed<HatchEntity>,
+        )>,
+    >,
+    mut removed: RemovedComponents<CadEntity>,
+    cad_query: Query<(Entity, &CadEntity, CadGeometryQuery)>,
+) {
+    // 上一帧标记的分块已由网格系统重建；本帧在此之前的修改（例如打开场景）要保留
+    if !draw_data.is_changed() {
+        draw_data.bypass_change_detection().dirty.clear();
+    }
+    for entity in removed.read() {
+        draw_data.remove(entity);
+    }
+
+    // 线型和线宽显示影响所有实体
+    let all = line_types.is_changed() || line_weight_display.is_changed();
+    if all {
+        draw_data.clear();
+    }
+    let entities: Vec<Entity> = if all || tolerance.is_changed() {
+        // 只有弦高误差变化时，不需要离散的实体保持原有的绘制数据
+        cad_query
+            .iter()
+            .filter(|(entity, _, geometry)| {
+                all || geometry.is_curve() || changed_query.contains(*entity)
+            })
+            .map(|(entity, ..)| entity)
+            .collect()
+    } else {
+        changed_query.iter().collect()
+    };
+    for (entity, cad, geometry) in cad_query.iter_many(&entities) {
+        let data = entity_draw_data(
+            cad,
+            &geometry,
+            &line_types,
+            &line_weight_display,
+            &tolerance,
         );
+        draw_data.insert(entity, &cad.layer, cad.color, data);
     }
+}
 
-    // 绘制多段线
-    for (points, closed, color) in &draw_data.polylines {
-        if points.len() < 2 {
-            continue;
+/// 一个实体的绘制数据
+fn entity_draw_data(
+    cad: &CadEntity,
+    geometry: &CadGeometryQueryItem,
+    line_types: &LineTypeTable,
+    line_weight_display: &LineWeightDisplay,
+    tolerance: &CurveTolerance,
+) -> EntityDrawData {
+    let mut data = EntityDrawData::default();
+    let (line, circle, arc, polyline) = (
+        geometry.line,
+        geometry.circle,
+        geometry.arc,
+        geometry.polyline,
+    );
+    let (spline, ellipse, hatch) = (geometry.spline, geometry.ellipse, geometry.hatch);
+    // 非实线线型或较粗的线宽：离散为折线后绘制，线型图案沿全长排布
+    let pattern = line_types.pattern(&cad.line_type);
+    let level = if line_weight_display.enabled {
+        line_weight_level(cad.line_weight)
+    } else {
+        0
+    };
+    // 实体填充加入填充网格，图案填充绘制裁剪到边界内的图案线
+    if let Some(hatch) = hatch {
+        if hatch.solid {
+            data.fills.push(hatch.fill_triangles());
         }
-        for i in 0..points.len() - 1 {
-            gizmos.line(points[i], points[i + 1], *color);
+        for (start, end) in hatch.pattern_segments() {
+            if level > 0 {
+                data.weighted.push((vec![start, end], level));
+            } else {
+                data.lines.push((start, end));
+            }
         }
-        if *closed && points.len() > 2 {
-            gizmos.line(points[points.len() - 1], points[0], *color);
+        return data;
+    }
+    if pattern.is_some() || level > 0 {
+        let outline = line
+            .map(|line| vec![line.start, line.end])
+            .or_else(|| circle.map(|c| arc_points(c.center, c.radius, 0.0, TAU, tolerance)))
+            .or_else(|| {
+                arc.map(|a| arc_points(a.center, a.radius, a.start_angle, a.end_angle, tolerance))
+            })
+            .or_else(|| polyline.map(|pl| pl.tessellate(tolerance)))
+            .or_else(|| spline.map(|s| s.tessellate(tolerance)))
+            .or_else(|| ellipse.map(|e| e.tessellate(tolerance)));
+        if let Some(outline) = outline {
+            let strips = match pattern {
+                Some(pattern) => dash_polyline(
+                    &outline,
+                    pattern,
+                    line_types.global_scale * cad.line_type_scale,
+                ),
+                None => vec![outline],
+            };
+            for strip in strips {
+                if level > 0 {
+                    data.weighted.push((strip, level));
+                } else {
+                    data.curves.push(strip);
+                }
+            }
+            if let Some(pl) = polyline.filter(|pl| pl.is_wide()) {
+                data.fills.push(pl.fill_triangles(tolerance));
+            }
+            return data;
         }
     }
+    if let Some(line) = line {
+        data.lines.push((line.start, line.end));
+    }
+    if let Some(circle) = circle {
+        data.circles.push((circle.center, circle.radius));
+    }
+    if let Some(arc) = arc {
+        data.arcs
+            .push((arc.center, arc.radius, arc.start_angle, arc.end_angle));
+    }
+    if let Some(pl) = polyline {
+        // 有凸度时离散后的折线已包括闭合段
+        if pl.bulges.iter().any(|b| *b != 0.0) {
+            data.polylines.push((pl.tessellate(tolerance), false));
+        } else {
+            data.polylines.push((pl.vertices.clone(), pl.closed));
+        }
+        if pl.is_wide() {
+            data.fills.push(pl.fill_triangles(tolerance));
+        }
+    }
+    if let Some(spline) = spline {
+        data.curves.push(spline.tessellate(tolerance));
+    }
+    if let Some(ellipse) = ellipse {
+        data.curves.push(ellipse.tessellate(tolerance));
+    }
+    data
 }
 
-/// 绘制弧线（分段近似）
-fn draw_arc(
-    gizmos: &mut Gizmos,
+/// 粗线 Gizmos 绘制系统 - 每帧按线宽档位绘制显示线宽时较粗的线
+///
+/// 其余线条由线网格绘制；粗线只在打开线宽显示时出现，数量较少。
+pub fn dxf_gizmos_system(
+    mut weighted_gizmos: (
+        Gizmos<LineWeightGizmos<1>>,
+        Gizmos<LineWeightGizmos<2>>,
+        Gizmos<LineWeightGizmos<3>>,
+    ),
+    draw_data: Res<DxfDrawData>,
+    layers: Res<LayerTable>,
+) {
+    let (level_1, level_2, level_3) = &mut weighted_gizmos;
+    for (chunk, data) in &draw_data.chunks {
+        if !layers.is_visible(&chunk.layer) {
+            continue;
+        }
+        let color = data.color;
+        for (points, level) in data.entities.values().flat_map(|e| &e.weighted) {
+            let points = points.iter().copied();
+            match level {
+                1 => level_1.linestrip(points, color),
+                2 => level_2.linestrip(points, color),
+                _ => level_3.linestrip(points, color),
+            }
+        }
+    }
+}
+
+/// XZ 平面内逆时针圆弧上的点，终止角不大于起始角时加一整圈，段数按弦高误差确定
+pub(super) fn arc_points(
     center: Vec3,
     radius: f32,
     start_angle: f32,
     end_angle: f32,
-    color: Color,
-) {
-    let segments = 32;
+    tolerance: &CurveTolerance,
+) -> Vec<Vec3> {
     let mut diff = end_angle - start_angle;
     if diff <= 0.0 {
-        diff += std::f32::consts::TAU;
+        diff += TAU;
     }
+    let segments = tolerance.arc_segments(radius, diff);
     let step = diff / segments as f32;
-
-    for i in 0..segments {
-        let a1 = start_angle + i as f32 * step;
-        let a2 = start_angle + (i + 1) as f32 * step;
-        let p1 = center + Vec3::new(radius * a1.cos(), 0.0, radius * a1.sin());
-        let p2 = center + Vec3::new(radius * a2.cos(), 0.0, radius * a2.sin());
-        gizmos.line(p1, p2, color);
-    }
-}
\ No newline at end of file
+    (0..=segments)
+        .map(|i| {
+            let a = start_angle + i as f32 * step;
+            center + Vec3::new(radius * a.cos(), 0.0, radius * a.sin())
+        })
+        .collect()
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use std::f32::consts::{PI, TAU};
+
+/// 显示时允许的弦高误差（屏幕像素）
+const PIXEL_TOLERANCE: f32 = 0.5;
+/// 导入时弦高误差相对图形大小的比例
+const RELATIVE_TOLERANCE: f32 = 1e-4;
+/// 整圆离散的最少段数
+const MIN_CIRCLE_SEGMENTS: f32 = 8.0;
+/// 整圆离散的最多段数
+const MAX_CIRCLE_SEGMENTS: f32 = 4096.0;
+
+/// 曲线离散的精度：允许的弦高误差（世界长度）
+///
+/// 显示时由相机决定，缩放每跨过 2 倍更新一次；导入时按图形大小确定。
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CurveTolerance {
+    pub chord: f32,
+}
+
+impl Default for CurveTolerance {
+    fn default() -> Self {
+        Self { chord: 0.01 }
+    }
+}
+
+impl CurveTolerance {
+    /// 按点集包围盒的大小确定弦高误差
+    pub fn relative(points: &[Vec3]) -> Self {
+        let (min, max) = points.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        );
+        Self {
+            chord: (max - min).length().max(f32::EPSILON) * RELATIVE_TOLERANCE,
+        }
+    }
+
+    /// 半径为 radius、圆心角为 sweep 的圆弧离散的段数
+    ///
+    /// 每段圆心角 θ 的弦高为 r(1 - cos(θ/2))，不超过弦高误差即可。
+    pub fn arc_segments(&self, radius: f32, sweep: f32) -> usize {
+        let per_circle = if self.chord >= radius {
+            MIN_CIRCLE_SEGMENTS
+        } else {
+            PI / (1.0 - self.chord / radius).acos()
+        };
+        let per_circle = per_circle.clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS);
+        ((sweep.abs() / TAU * per_circle).ceil() as usize).max(2)
+    }
+}
+
+/// 曲线精度系统 - 按相机计算一个像素对应的世界长度，更新显示用的弦高误差
+///
+/// 弦高误差取 2 的整数次幂，缩放跨过 2 倍时才变化，避免缩放过程中每帧重建绘制数据。
+pub fn curve_tolerance_system(
+    camera_query: Query<(&Camera, &Projection, &GlobalTransform, &PanOrbitCamera)>,
+    mut tolerance: ResMut<CurveTolerance>,
+) {
+    let Some((camera, projection, transform, orbit)) = camera_query.iter().next() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    // 视口高度对应的世界长度，透视相机取焦点处
+    let world_height = match projection {
+        Projection::Perspective(perspective) => {
+            2.0 * transform.translation().distance(orbit.focus) * (perspective.fov * 0.5).tan()
+        }
+        Projection::Orthographic(orthographic) => orthographic.area.height(),
+        _ => return,
+    };
+    let chord = world_height / viewport.y * PIXEL_TOLERANCE;
+    if !chord.is_finite() || chord <= 0.0 {
+        return;
+    }
+    tolerance.set_if_neq(CurveTolerance {
+        chord: chord.log2().floor().exp2(),
+    });
+}
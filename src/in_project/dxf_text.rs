@@ -0,0 +1,492 @@
+use bevy::prelude::*;
+use bevy_egui::*;
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui::epaint::TextShape;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Align, Color32, FontId, LayerId, Pos2, Stroke, Vec2 as EguiVec2};
+
+use super::dxf_color::aci_color;
+use super::dxf_renderer::{CadEntity, TextEntity, TextHAlign, TextVAlign};
+use super::layer_panel::LayerTable;
+
+/// DXF 字高是大写字母高度，约为字号的 0.7
+const CAP_HEIGHT_RATIO: f32 = 0.7;
+/// 屏幕上小于这个字号的文字不绘制
+const MIN_FONT_SIZE: f32 = 2.0;
+/// 堆叠文字（分数）的字号比例
+const STACK_SCALE: f32 = 0.6;
+
+/// 一段格式相同的文字
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    /// 相对实体字高的比例（\H）
+    pub height_scale: f32,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub italic: bool,
+    /// ACI 颜色（\C），None 表示沿用实体颜色
+    pub color_index: Option<u8>,
+    /// 堆叠文字（\S），分子和分母
+    pub stacked: Option<(String, String)>,
+}
+
+/// 一行文字
+pub type TextLine = Vec<TextRun>;
+
+#[derive(Debug, Clone)]
+struct TextFormatState {
+    height_scale: f32,
+    underline: bool,
+    strikethrough: bool,
+    italic: bool,
+    color_index: Option<u8>,
+}
+
+impl Default for TextFormatState {
+    fn default() -> Self {
+        Self {
+            height_scale: 1.0,
+            underline: false,
+            strikethrough: false,
+            italic: false,
+            color_index: None,
+        }
+    }
+}
+
+/// 解析文字内容
+///
+/// TEXT 只处理 %% 控制码；MTEXT 还处理格式代码：换行 \P、堆叠 \S、字体 \f、
+/// 字高 \H、颜色 \C、下划线 \L、删除线 \K 和 {} 分组。
+/// 只内置了一种中文字体，\f 中的字体名被忽略，只保留斜体标记。
+pub fn parse_text(raw: &str, base_height: f32, is_mtext: bool) -> Vec<TextLine> {
+    let mut lines: Vec<TextLine> = vec![Vec::new()];
+    let mut format = TextFormatState::default();
+    let mut stack: Vec<TextFormatState> = Vec::new();
+    let mut buf = String::new();
+    let mut chars = raw.chars().peekable();
+
+    fn flush(lines: &mut [TextLine], buf: &mut String, format: &TextFormatState) {
+        if buf.is_empty() {
+            return;
+        }
+        if let Some(line) = lines.last_mut() {
+            line.push(TextRun {
+                text: std::mem::take(buf),
+                height_scale: format.height_scale,
+                underline: format.underline,
+                strikethrough: format.strikethrough,
+                italic: format.italic,
+                color_index: format.color_index,
+                stacked: None,
+            });
+        }
+    }
+
+    // 读取到分号为止的参数
+    fn read_arg(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+        let mut arg = String::new();
+        for c in chars.by_ref() {
+            if c == ';' {
+                break;
+            }
+            arg.push(c);
+        }
+        arg
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.peek() == Some(&'%') => {
+                chars.next();
+                match chars.next() {
+                    Some('d' | 'D') => buf.push('°'),
+                    Some('c' | 'C') => buf.push('⌀'),
+                    Some('p' | 'P') => buf.push('±'),
+                    Some('%') => buf.push('%'),
+                    Some('u' | 'U') => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.underline = !format.underline;
+                    }
+                    Some('k' | 'K') => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.strikethrough = !format.strikethrough;
+                    }
+                    // 上划线不支持，忽略
+                    Some('o' | 'O') => {}
+                    Some(d) if d.is_ascii_digit() => {
+                        // %%nnn：字符编码
+                        let mut code = d.to_string();
+                        while code.len() < 3 {
+                            match chars.peek() {
+                                Some(n) if n.is_ascii_digit() => {
+                                    code.push(*n);
+                                    chars.next();
+                                }
+                                _ => break,
+                            }
+                        }
+                        if let Some(ch) = code.parse::<u32>().ok().and_then(char::from_u32) {
+                            buf.push(ch);
+                        }
+                    }
+                    Some(other) => {
+                        buf.push_str("%%");
+                        buf.push(other);
+                    }
+                    None => buf.push_str("%%"),
+                }
+            }
+            '\\' => match chars.next() {
+                Some('U' | 'u') if chars.peek() == Some(&'+') => {
+                    chars.next();
+                    let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                    if let Some(ch) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        buf.push(ch);
+                    }
+                }
+                Some(code) if is_mtext => match code {
+                    'P' | 'N' | 'X' => {
+                        flush(&mut lines, &mut buf, &format);
+                        lines.push(Vec::new());
+                    }
+                    '~' => buf.push('\u{a0}'),
+                    'L' | 'l' => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.underline = code == 'L';
+                    }
+                    'K' | 'k' => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.strikethrough = code == 'K';
+                    }
+                    // 上划线不支持，忽略
+                    'O' | 'o' => {}
+                    'H' => {
+                        flush(&mut lines, &mut buf, &format);
+                        let arg = read_arg(&mut chars);
+                        if let Some(factor) = arg.strip_suffix(['x', 'X']) {
+                            if let Ok(factor) = factor.parse::<f32>() {
+                                format.height_scale *= factor;
+                            }
+                        } else if let Ok(height) = arg.parse::<f32>()
+                            && base_height > 0.0
+                        {
+                            format.height_scale = height / base_height;
+                        }
+                    }
+                    'C' => {
+                        flush(&mut lines, &mut buf, &format);
+                        // 0 随块、256 随层，都沿用实体颜色
+                        format.color_index = read_arg(&mut chars)
+                            .parse::<u16>()
+                            .ok()
+                            .filter(|i| (1..=255).contains(i))
+                            .map(|i| i as u8);
+                    }
+                    'f' | 'F' => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.italic = read_arg(&mut chars).contains("|i1");
+                    }
+                    'Q' => {
+                        flush(&mut lines, &mut buf, &format);
+                        format.italic = read_arg(&mut chars)
+                            .parse::<f32>()
+                            .is_ok_and(|angle| angle != 0.0);
+                    }
+                    'S' => {
+                        flush(&mut lines, &mut buf, &format);
+                        let arg = read_arg(&mut chars);
+                        let (num, den) = arg
+                            .split_once(['^', '/', '#'])
+                            .unwrap_or((arg.as_str(), ""));
+                        if let Some(line) = lines.last_mut() {
+                            line.push(TextRun {
+                                text: String::new(),
+                                height_scale: format.height_scale,
+                                underline: format.underline,
+                                strikethrough: format.strikethrough,
+                                italic: format.italic,
+                                color_index: format.color_index,
+                                stacked: Some((num.trim().to_string(), den.trim().to_string())),
+                            });
+                        }
+                    }
+                    // 宽度、字距、对齐、段落、真彩色等参数不影响显示内容，跳过
+                    'W' | 'T' | 'A' | 'p' | 'c' => {
+                        read_arg(&mut chars);
+                    }
+                    other => buf.push(other),
+                },
+                Some(other) => {
+                    buf.push('\\');
+                    buf.push(other);
+                }
+                None => buf.push('\\'),
+            },
+            '{' if is_mtext => {
+                flush(&mut lines, &mut buf, &format);
+                stack.push(format.clone());
+            }
+            '}' if is_mtext => {
+                flush(&mut lines, &mut buf, &format);
+                if let Some(previous) = stack.pop() {
+                    format = previous;
+                }
+            }
+            _ => buf.push(c),
+        }
+    }
+    flush(&mut lines, &mut buf, &format);
+    lines
+}
+
+/// 文字绘制系统 - 用 egui 在视口中绘制 TEXT / MTEXT（使用 font_system 中的黑体）
+pub fn dxf_text_system(
+    mut contexts: EguiContexts,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    text_query: Query<(&CadEntity, &TextEntity)>,
+    layers: Res<LayerTable>,
+) -> Result {
+    let Some((camera, camera_transform)) = camera_query.iter().next() else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let painter = ctx.layer_painter(LayerId::background());
+    let screen = ctx.content_rect();
+
+    for (cad, text) in &text_query {
+        if !layers.is_visible(&cad.layer) {
+            continue;
+        }
+        // 文字的 X 方向和向上方向（DXF XY 平面对应世界 XZ 平面）
+        let (sin, cos) = text.rotation.sin_cos();
+        let x_dir = Vec3::new(cos, 0.0, sin);
+        let up_dir = Vec3::new(-sin, 0.0, cos);
+
+        let Ok(anchor) = camera.world_to_viewport(camera_transform, text.position) else {
+            continue;
+        };
+        let Ok(up) =
+            camera.world_to_viewport(camera_transform, text.position + up_dir * text.height)
+        else {
+            continue;
+        };
+        let Ok(right) =
+            camera.world_to_viewport(camera_transform, text.position + x_dir * text.height)
+        else {
+            continue;
+        };
+
+        let pixel_height = anchor.distance(up);
+        let mut font_size = pixel_height / CAP_HEIGHT_RATIO;
+        if font_size < MIN_FONT_SIZE {
+            continue;
+        }
+        let anchor = Pos2::new(anchor.x, anchor.y);
+        // 粗略剔除屏幕外的文字
+        if !screen.expand(font_size * 50.0).contains(anchor) {
+            continue;
+        }
+        let angle = (right.y - anchor.y).atan2(right.x - anchor.x);
+        let pixels_per_unit = pixel_height / text.height.max(f32::EPSILON);
+
+        let lines = parse_text(&text.content, text.height, text.is_mtext);
+        let color = to_color32(cad.color);
+        let mut galley = painter.layout_job(layout_text(
+            &lines,
+            font_size,
+            color,
+            text.wrap_width.map(|w| w * pixels_per_unit),
+        ));
+        // 对齐/布满：缩放到两个对齐点之间
+        if let Some(fit_width) = text.fit_width {
+            let target = fit_width * pixels_per_unit;
+            if galley.size().x > 0.0 && target > 0.0 {
+                font_size *= target / galley.size().x;
+                galley = painter.layout_job(layout_text(&lines, font_size, color, None));
+            }
+        }
+
+        let size = galley.size();
+        let offset_x = match text.h_align {
+            TextHAlign::Left => 0.0,
+            TextHAlign::Center => size.x * 0.5,
+            TextHAlign::Right => size.x,
+        };
+        let first_row_height = galley
+            .rows
+            .first()
+            .map(|row| row.height())
+            .unwrap_or(size.y);
+        let offset_y = match text.v_align {
+            TextVAlign::Top => 0.0,
+            TextVAlign::Middle => size.y * 0.5,
+            TextVAlign::Bottom => size.y,
+            // 单行文字的基线约在行高的 0.8 处
+            TextVAlign::Baseline => first_row_height * 0.8,
+        };
+        // 左上角位置：对齐偏移随文字一起旋转
+        let (sin_a, cos_a) = angle.sin_cos();
+        let rotated = EguiVec2::new(
+            offset_x * cos_a - offset_y * sin_a,
+            offset_x * sin_a + offset_y * cos_a,
+        );
+        painter.add(TextShape::new(anchor - rotated, galley, color).with_angle(angle));
+    }
+    Ok(())
+}
+
+/// 把解析后的文字排版为 egui LayoutJob
+fn layout_text(
+    lines: &[TextLine],
+    font_size: f32,
+    color: Color32,
+    wrap_width: Option<f32>,
+) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    if let Some(wrap_width) = wrap_width {
+        job.wrap.max_width = wrap_width;
+    }
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 {
+            job.append("\n", 0.0, text_format(font_size, color));
+        }
+        for run in line {
+            // \C 指定的颜色优先，0（随块）沿用实体颜色
+            let color = match run.color_index {
+                Some(index) if index > 0 => to_color32(aci_color(index)),
+                _ => color,
+            };
+            let mut format = text_format(font_size * run.height_scale, color);
+            format.italics = run.italic;
+            if run.underline {
+                format.underline = Stroke::new(1.0, color);
+            }
+            if run.strikethrough {
+                format.strikethrough = Stroke::new(1.0, color);
+            }
+            match &run.stacked {
+                Some((num, den)) => {
+                    let small = font_size * run.height_scale * STACK_SCALE;
+                    let mut top = format.clone();
+                    top.font_id = FontId::proportional(small);
+                    top.valign = Align::TOP;
+                    let mut bottom = top.clone();
+                    bottom.valign = Align::BOTTOM;
+                    job.append(num, 0.0, top.clone());
+                    if !den.is_empty() {
+                        job.append("⁄", 0.0, top);
+                        job.append(den, 0.0, bottom);
+                    }
+                }
+                None => job.append(&run.text, 0.0, format),
+            }
+        }
+    }
+    job
+}
+
+fn text_format(font_size: f32, color: Color32) -> TextFormat {
+    TextFormat {
+        font_id: FontId::proportional(font_size),
+        color,
+        ..Default::default()
+    }
+}
+
+fn to_color32(color: Color) -> Color32 {
+    let [r, g, b, a] = color.to_srgba().to_u8_array();
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每行文字的内容（堆叠文字写成 分子/分母）
+    fn texts(lines: &[TextLine]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|run| match &run.stacked {
+                        Some((num, den)) => format!("{}/{}", num, den),
+                        None => run.text.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn control_codes() {
+        let lines = parse_text("%%d%%c%%p%%%", 1.0, false);
+        assert_eq!(texts(&lines), ["°⌀±%"]);
+
+        // %%nnn 最多读 3 位十进制字符编码
+        let lines = parse_text("%%0651", 1.0, false);
+        assert_eq!(texts(&lines), ["A1"]);
+
+        // TEXT 不处理 MTEXT 的格式代码
+        let lines = parse_text(r"a\Pb{c}", 1.0, false);
+        assert_eq!(texts(&lines), [r"a\Pb{c}"]);
+    }
+
+    #[test]
+    fn unicode_escape() {
+        let lines = parse_text(r"\U+4E2D\U+6587", 1.0, true);
+        assert_eq!(texts(&lines), ["中文"]);
+        let lines = parse_text(r"\U+4E2D", 1.0, false);
+        assert_eq!(texts(&lines), ["中"]);
+    }
+
+    #[test]
+    fn mtext_line_breaks() {
+        let lines = parse_text(r"第一行\P第二行\P", 1.0, true);
+        assert_eq!(texts(&lines), ["第一行", "第二行", ""]);
+    }
+
+    #[test]
+    fn mtext_stacking() {
+        let lines = parse_text(r"1\S1/2;mm", 1.0, true);
+        assert_eq!(texts(&lines), ["11/2mm"]);
+        let stacked = &lines[0][1];
+        assert_eq!(stacked.stacked, Some(("1".to_string(), "2".to_string())));
+        assert!(stacked.text.is_empty());
+
+        // 公差堆叠 ^ 和没有分母的情况
+        let lines = parse_text(r"\S+0.1^-0.2;\S3;", 1.0, true);
+        assert_eq!(
+            lines[0]
+                .iter()
+                .map(|run| run.stacked.clone())
+                .collect::<Vec<_>>(),
+            [
+                Some(("+0.1".to_string(), "-0.2".to_string())),
+                Some(("3".to_string(), String::new())),
+            ]
+        );
+    }
+
+    #[test]
+    fn mtext_group_scopes_format() {
+        let lines = parse_text(r"a{\H2x;\C1;\Lb}c", 2.5, true);
+        assert_eq!(texts(&lines), ["abc"]);
+        let [a, b, c] = lines[0].as_slice() else {
+            panic!("expected three runs");
+        };
+        assert_eq!(b.height_scale, 2.0);
+        assert_eq!(b.color_index, Some(1));
+        assert!(b.underline);
+        for run in [a, c] {
+            assert_eq!(run.height_scale, 1.0);
+            assert_eq!(run.color_index, None);
+            assert!(!run.underline);
+        }
+
+        // 绝对字高按实体字高换算为比例
+        let lines = parse_text(r"{\H5;x}", 2.5, true);
+        assert_eq!(lines[0][0].height_scale, 2.0);
+    }
+}
@@ -7,7 +7,7 @@ use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
 
-use super::LoadDxfMessage;
+use super::{GuardedAction, UnsavedGuard};
 
 /// 文件树节点
 #[derive(Debug, Clone)]
@@ -59,6 +59,7 @@ pub struct DxfJsonViewer {
 #[derive(Default)]
 pub struct PendingActions {
     pub load_dxf: Option<PathBuf>,
+    pub open_scene: Option<PathBuf>,
 }
 
 /// 文件树组件
@@ -97,10 +98,13 @@ impl FileTree {
         tree
     }
 
-    /// 处理待发送的事件
-    pub fn process_pending_events(&mut self, dxf_messages: &mut MessageWriter<LoadDxfMessage>) {
+    /// 处理待发送的事件（会替换当前图纸，先经过未保存检查）
+    pub fn process_pending_events(&mut self, guard: &mut UnsavedGuard) {
         if let Some(path) = self.pending_actions.load_dxf.take() {
-            dxf_messages.write(LoadDxfMessage { path });
+            guard.request(GuardedAction::LoadDxf(path));
+        }
+        if let Some(path) = self.pending_actions.open_scene.take() {
+            guard.request(GuardedAction::OpenScene(path));
         }
     }
 
@@ -206,6 +210,14 @@ impl FileTree {
             .unwrap_or(false)
     }
 
+    /// 判断是否为场景文件
+    fn is_scene_file(path: &PathBuf) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase() == "ron")
+            .unwrap_or(false)
+    }
+
     /// 显示文件树
     pub fn show(&mut self, ui: &mut egui::Ui) {
         // 处理待刷新的目录
@@ -371,6 +383,9 @@ impl FileTree {
             if Self::is_dxf_file(path) {
                 // DXF文件：发送渲染事件
                 self.pending_actions.load_dxf = Some(path.clone());
+            } else if Self::is_scene_file(path) {
+                // 场景文件：打开场景
+                self.pending_actions.open_scene = Some(path.clone());
             }
             // 其他文件暂不处理
         }
@@ -405,6 +420,7 @@ impl FileTree {
             "rs" => "🦀",
             "toml" => "⚙️",
             "json" => "📋",
+            "ron" => "🎬",
             "txt" => "📝",
             "md" => "📖",
             _ => "📄",
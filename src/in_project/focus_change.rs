@@ -3,19 +3,25 @@ use bevy::prelude::*;
 use bevy_egui::EguiContexts;
 use bevy_panorbit_camera::PanOrbitCamera;
 
+use super::view_mode::ViewMode;
+
 pub fn focus_change_system(
     mut contexts: EguiContexts,
+    view_mode: Res<ViewMode>,
     mut query: Query<&mut PanOrbitCamera>,
     mut was_ui_focused: Local<bool>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
     let ui_focused = ctx.is_pointer_over_area() || ctx.wants_pointer_input();
 
-    if *was_ui_focused != ui_focused {
-        for mut camera in query.iter_mut() {
-            camera.enabled = !ui_focused;
+    // 2D 视图由 plan_view_system 处理输入
+    let changed = *was_ui_focused != ui_focused || view_mode.is_changed();
+    for mut camera in query.iter_mut() {
+        // 新建编辑器或打开场景时生成的相机也要按当前状态设置
+        if changed || camera.is_added() {
+            camera.enabled = !ui_focused && *view_mode == ViewMode::Orbit;
         }
-        *was_ui_focused = ui_focused;
     }
+    *was_ui_focused = ui_focused;
     Ok(())
 }
\ No newline at end of file
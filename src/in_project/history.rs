@@ -0,0 +1,423 @@
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use egui::{Key, KeyboardShortcut, Modifiers, ScrollArea};
+use std::time::Duration;
+
+use super::dxf_block::{CadNode, cad_node, spawn_cad_node};
+use super::dxf_renderer::{CadEntity, CadGeometry, CadGeometryQuery, DxfSource, insert_geometry};
+use super::layer_panel::{LayerState, LayerTable};
+use super::selection::Selected;
+use crate::editor::Editor;
+
+/// 最多保留的命令个数，超出时丢弃最早的命令
+const MAX_COMMANDS: usize = 200;
+/// 连续修改同一组实体的同一项时，间隔小于这个时间的修改合并为一条命令（拖动数值时每帧都会修改）
+const MERGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 一项修改，记录修改前后的数据，可以双向应用
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// 新建实体
+    Create {
+        entity: Entity,
+        node: Box<CadNode>,
+        source: Option<Box<DxfSource>>,
+    },
+    /// 删除实体，保留实体及其子实体的全部数据
+    Delete {
+        entity: Entity,
+        node: Box<CadNode>,
+        source: Option<Box<DxfSource>>,
+    },
+    /// 修改图层、颜色等特性
+    Properties {
+        entity: Entity,
+        before: CadEntity,
+        after: CadEntity,
+    },
+    /// 修改几何数据，移动、旋转等变换也记录为几何数据的修改
+    Geometry {
+        entity: Entity,
+        before: Box<CadGeometry>,
+        after: Box<CadGeometry>,
+    },
+    /// 修改图层表
+    Layers {
+        before: Vec<LayerState>,
+        after: Vec<LayerState>,
+    },
+}
+
+impl Change {
+    /// 修改的实体，图层表的修改没有实体
+    fn entity(&self) -> Option<Entity> {
+        match self {
+            Change::Create { entity, .. }
+            | Change::Delete { entity, .. }
+            | Change::Properties { entity, .. }
+            | Change::Geometry { entity, .. } => Some(*entity),
+            Change::Layers { .. } => None,
+        }
+    }
+
+    /// 把同一项的后一次修改合并进来：保留修改前的数据，取后一次修改后的数据
+    fn merge(&mut self, next: Change) -> bool {
+        if self.entity() != next.entity() {
+            return false;
+        }
+        match (self, next) {
+            (Change::Properties { after, .. }, Change::Properties { after: next, .. }) => {
+                *after = next;
+            }
+            (Change::Geometry { after, .. }, Change::Geometry { after: next, .. }) => {
+                *after = next;
+            }
+            (Change::Layers { after, .. }, Change::Layers { after: next, .. }) => {
+                *after = next;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// 实体被删除后重新生成时，把对旧实体的引用换成新实体
+    fn remap(&mut self, old: Entity, new: Entity) {
+        match self {
+            Change::Create { entity, .. }
+            | Change::Delete { entity, .. }
+            | Change::Properties { entity, .. }
+            | Change::Geometry { entity, .. } => {
+                if *entity == old {
+                    *entity = new;
+                }
+            }
+            Change::Layers { .. } => {}
+        }
+    }
+}
+
+/// 一条命令：用户的一次操作，可能修改多个实体
+#[derive(Debug, Clone)]
+pub struct EditCommand {
+    /// 在历史面板中显示的名称
+    pub label: String,
+    pub changes: Vec<Change>,
+}
+
+/// 编辑历史 - 记录已经完成的修改，撤销、重做时反向或正向应用
+///
+/// 修改由各个编辑功能直接作用到实体上，完成后把修改前后的数据记录为一条命令。
+/// 撤销、重做和跳转只修改目标位置，由编辑历史系统应用。
+#[derive(Resource, Debug, Default)]
+pub struct EditHistory {
+    commands: Vec<EditCommand>,
+    /// 已经应用的命令个数
+    position: usize,
+    /// 要跳到的位置
+    target: usize,
+    /// 上一次记录命令的时间，用于合并连续的修改
+    last_push: Option<Instant>,
+    /// 是否显示历史面板
+    pub panel_open: bool,
+}
+
+impl EditHistory {
+    /// 记录一条已经完成的命令，丢弃可以重做的命令
+    pub fn push(&mut self, label: impl Into<String>, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.commands.truncate(self.position);
+        self.commands.push(EditCommand {
+            label: label.into(),
+            changes,
+        });
+        if self.commands.len() > MAX_COMMANDS {
+            self.commands.remove(0);
+        }
+        self.position = self.commands.len();
+        self.target = self.position;
+        self.last_push = Some(Instant::now());
+    }
+
+    /// 记录一条命令，与刚记录的同名、修改同样几项的命令合并
+    pub fn push_merged(&mut self, label: impl Into<String>, changes: Vec<Change>) {
+        let label = label.into();
+        let recent = self
+            .last_push
+            .is_some_and(|time| time.elapsed() < MERGE_INTERVAL);
+        if recent
+            && self.position == self.commands.len()
+            && self.target == self.position
+            && let Some(last) = self.commands.last_mut()
+        {
+            let same = last.label == label
+                && last.changes.len() == changes.len()
+                && last
+                    .changes
+                    .iter()
+                    .zip(&changes)
+                    .all(|(a, b)| a.entity() == b.entity());
+            if same {
+                let mut merged = last.changes.clone();
+                if merged
+                    .iter_mut()
+                    .zip(changes.clone())
+                    .all(|(change, next)| change.merge(next))
+                {
+                    last.changes = merged;
+                    self.last_push = Some(Instant::now());
+                    return;
+                }
+            }
+        }
+        self.push(label, changes);
+    }
+
+    /// 清空历史，加载图纸或离开项目时调用
+    pub fn clear(&mut self) {
+        let panel_open = self.panel_open;
+        *self = Self {
+            panel_open,
+            ..default()
+        };
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.target > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.target < self.commands.len()
+    }
+
+    pub fn undo(&mut self) {
+        self.target = self.target.saturating_sub(1);
+        self.last_push = None;
+    }
+
+    pub fn redo(&mut self) {
+        self.target = (self.target + 1).min(self.commands.len());
+        self.last_push = None;
+    }
+
+    /// 跳到应用了前 position 条命令的状态
+    pub fn jump_to(&mut self, position: usize) {
+        self.target = position.min(self.commands.len());
+        self.last_push = None;
+    }
+
+    fn remap(&mut self, old: Entity, new: Entity) {
+        for command in &mut self.commands {
+            for change in &mut command.changes {
+                change.remap(old, new);
+            }
+        }
+    }
+}
+
+/// 编辑历史系统 - 撤销、重做到目标位置
+///
+/// 撤销时倒序应用命令中的修改。删除后重新生成的实体是新的实体，历史中的引用随之更新。
+pub fn history_system(
+    mut commands: Commands,
+    mut history: ResMut<EditHistory>,
+    mut layers: ResMut<LayerTable>,
+    mut editor_query: Query<(Entity, &mut Editor)>,
+) {
+    if history.position == history.target {
+        return;
+    }
+    let Some((editor_entity, mut editor)) = editor_query.iter_mut().next() else {
+        return;
+    };
+    while history.position != history.target {
+        let forward = history.position < history.target;
+        let index = if forward {
+            history.position
+        } else {
+            history.position - 1
+        };
+        let mut changes = std::mem::take(&mut history.commands[index].changes);
+        let mut respawned = Vec::new();
+        let mut apply = |change: &Change| {
+            if let Some(remap) =
+                apply_change(&mut commands, editor_entity, &mut layers, change, forward)
+            {
+                respawned.push(remap);
+            }
+        };
+        if forward {
+            changes.iter().for_each(&mut apply);
+        } else {
+            changes.iter().rev().for_each(&mut apply);
+        }
+        for &(old, new) in &respawned {
+            for change in &mut changes {
+                change.remap(old, new);
+            }
+        }
+        history.commands[index].changes = changes;
+        for (old, new) in respawned {
+            history.remap(old, new);
+        }
+        history.position = if forward { index + 1 } else { index };
+    }
+    // 由图层状态系统重新计算恢复的实体的显示颜色和可选择状态
+    layers.set_changed();
+    editor.is_dirty = true;
+}
+
+/// 正向或反向应用一项修改，重新生成实体时返回旧实体和新实体
+fn apply_change(
+    commands: &mut Commands,
+    editor: Entity,
+    layers: &mut LayerTable,
+    change: &Change,
+    forward: bool,
+) -> Option<(Entity, Entity)> {
+    match change {
+        Change::Create {
+            entity,
+            node,
+            source,
+        }
+        | Change::Delete {
+            entity,
+            node,
+            source,
+        } => {
+            // 重做新建、撤销删除时生成实体，反之删除实体
+            let spawn = forward == matches!(change, Change::Create { .. });
+            if !spawn {
+                if let Ok(mut entity) = commands.get_entity(*entity) {
+                    entity.despawn();
+                }
+                return None;
+            }
+            let new = spawn_cad_node(commands, editor, node.as_ref().clone());
+            if let Some(source) = source {
+                commands.entity(new).insert(source.as_ref().clone());
+            }
+            Some((*entity, new))
+        }
+        Change::Properties {
+            entity,
+            before,
+            after,
+        } => {
+            let cad = if forward { after } else { before };
+            if let Ok(mut entity) = commands.get_entity(*entity) {
+                entity.insert(cad.clone());
+            }
+            None
+        }
+        Change::Geometry {
+            entity,
+            before,
+            after,
+        } => {
+            let geometry = if forward { after } else { before };
+            if let Ok(mut entity) = commands.get_entity(*entity) {
+                insert_geometry(&mut entity, geometry.as_ref().clone());
+            }
+            None
+        }
+        Change::Layers { before, after } => {
+            layers.layers = if forward { after } else { before }.clone();
+            None
+        }
+    }
+}
+
+/// 删除选中系统 - Delete 键删除选中的实体
+pub fn delete_selection_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut history: ResMut<EditHistory>,
+    keys: Res<ButtonInput<KeyCode>>,
+    selected_query: Query<(Entity, Option<&DxfSource>), With<Selected>>,
+    cad_query: Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+    mut editor_query: Query<&mut Editor>,
+) -> Result {
+    if !keys.just_pressed(KeyCode::Delete) || selected_query.is_empty() {
+        return Ok(());
+    }
+    // 正在输入文字时 Delete 键由输入框处理
+    if contexts.ctx_mut()?.wants_keyboard_input() {
+        return Ok(());
+    }
+    let mut changes = Vec::new();
+    for (entity, source) in &selected_query {
+        let Some(node) = cad_node(entity, &cad_query) else {
+            continue;
+        };
+        commands.entity(entity).despawn();
+        changes.push(Change::Delete {
+            entity,
+            node: Box::new(node),
+            source: source.cloned().map(Box::new),
+        });
+    }
+    history.push(format!("删除 {} 个实体", changes.len()), changes);
+    for mut editor in &mut editor_query {
+        editor.is_dirty = true;
+    }
+    Ok(())
+}
+
+/// 历史面板系统 - Ctrl+Z 撤销，Ctrl+Y 或 Ctrl+Shift+Z 重做；历史面板中点击一条命令跳到该命令完成后的状态
+pub fn history_panel_system(
+    mut contexts: EguiContexts,
+    mut history: ResMut<EditHistory>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    // Ctrl+Z 也会匹配 Ctrl+Shift+Z，先检查带 Shift 的组合
+    let redo = ctx.input_mut(|i| {
+        i.consume_shortcut(&KeyboardShortcut::new(
+            Modifiers::COMMAND | Modifiers::SHIFT,
+            Key::Z,
+        )) || i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Y))
+    });
+    let undo =
+        ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)));
+    if redo {
+        history.redo();
+    } else if undo {
+        history.undo();
+    }
+
+    let mut open = history.panel_open;
+    let mut jump = None;
+    egui::Window::new("历史")
+        .open(&mut open)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                // 第 0 项是没有任何修改的状态，第 i 项是完成第 i 条命令后的状态
+                let labels = std::iter::once("（初始）").chain(
+                    history
+                        .commands
+                        .iter()
+                        .map(|command| command.label.as_str()),
+                );
+                for (position, label) in labels.enumerate() {
+                    let current = position == history.target;
+                    let text = if position > history.target {
+                        egui::RichText::new(label).weak()
+                    } else {
+                        egui::RichText::new(label)
+                    };
+                    if ui.selectable_label(current, text).clicked() {
+                        jump = Some(position);
+                    }
+                }
+            });
+        });
+    history.panel_open = open;
+    if let Some(position) = jump {
+        history.jump_to(position);
+    }
+    Ok(())
+}
@@ -1,6 +1,10 @@
-use crate::state::AppState;
+use crate::editor::{Editor, SaveDialog, SaveSceneMessage};
 
-use super::{FileTree, LoadDxfMessage, Project};
+use super::history::{Change, EditHistory};
+use super::{
+    DxfLoadTask, ExportDxfDialog, FileTree, GuardedAction, LayerTable, LineWeightDisplay, Project,
+    SnapSettings, UnsavedGuard, ViewMode, ZoomMessage,
+};
 use bevy::{
     prelude::*,
     window::{PrimaryWindow, Window},
@@ -8,21 +12,156 @@ use bevy::{
 use bevy_egui::*;
 use egui::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn in_project_ui_system(
     mut contexts: EguiContexts,
-    _window: Single<&mut Window, With<PrimaryWindow>>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
     project: Res<Project>,
-    mut next_state: ResMut<NextState<AppState>>,
+    mut editor_query: Query<&mut Editor>,
+    mut guard: ResMut<UnsavedGuard>,
+    mut save_dialog: ResMut<SaveDialog>,
+    mut export_dialog: ResMut<ExportDxfDialog>,
+    mut layers: ResMut<LayerTable>,
+    mut history: ResMut<EditHistory>,
+    mut line_weight_display: ResMut<LineWeightDisplay>,
+    mut view_mode: ResMut<ViewMode>,
+    mut snap_settings: ResMut<SnapSettings>,
+    mut load_task: ResMut<DxfLoadTask>,
     mut file_tree: Local<Option<FileTree>>,
-    mut dxf_messages: MessageWriter<LoadDxfMessage>,
+    mut save_messages: MessageWriter<SaveSceneMessage>,
+    mut zoom_messages: MessageWriter<ZoomMessage>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
+    let editor = editor_query.iter().next();
+    let editor_path = editor.and_then(|editor| editor.path.clone());
+
+    // 窗口标题，有未保存的更改时加 * 标记
+    let scene_name = editor_path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or("未命名");
+    let dirty_mark = if editor.is_some_and(|editor| editor.is_dirty) {
+        "*"
+    } else {
+        ""
+    };
+    let title = format!("开源Cad - {} - {}{}", project.name, scene_name, dirty_mark);
+    if window.title != title {
+        window.title = title;
+    }
+
+    let mut save =
+        ctx.input_mut(|i| i.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::S)));
+    let mut save_as = false;
 
     // 顶部菜单栏
     TopBottomPanel::top("title_bar").show(ctx, |ui| {
         MenuBar::new().ui(ui, |ui: &mut Ui| {
+            ui.menu_button("文件", |ui| {
+                if ui.button("💾 保存").clicked() {
+                    save = true;
+                    ui.close();
+                }
+                if ui.button("💾 另存为...").clicked() {
+                    save_as = true;
+                    ui.close();
+                }
+                ui.separator();
+                if ui.button("📤 导出 DXF...").clicked() {
+                    export_dialog.open(project.path.clone());
+                    ui.close();
+                }
+            });
+            ui.menu_button("编辑", |ui| {
+                if ui
+                    .add_enabled(history.can_undo(), egui::Button::new("↶ 撤销"))
+                    .clicked()
+                {
+                    history.undo();
+                    ui.close();
+                }
+                if ui
+                    .add_enabled(history.can_redo(), egui::Button::new("↷ 重做"))
+                    .clicked()
+                {
+                    history.redo();
+                    ui.close();
+                }
+                ui.separator();
+                if ui.button("历史记录").clicked() {
+                    history.panel_open = true;
+                    ui.close();
+                }
+            });
+            ui.menu_button("视图", |ui| {
+                for (label, message) in [
+                    ("范围缩放", ZoomMessage::Extents),
+                    ("窗口缩放", ZoomMessage::Window),
+                    ("上一个视图", ZoomMessage::Previous),
+                    ("缩放到选中", ZoomMessage::Selected),
+                ] {
+                    if ui.button(label).clicked() {
+                        zoom_messages.write(message);
+                        ui.close();
+                    }
+                }
+            });
             if ui.button("退出").clicked() {
-                next_state.set(AppState::MainMenu);
+                guard.request(GuardedAction::ExitProject);
+            }
+        });
+    });
+
+    // 保存：没有路径时走另存为
+    if save {
+        match editor_path {
+            Some(path) => {
+                save_messages.write(SaveSceneMessage { path });
+            }
+            None => save_as = true,
+        }
+    }
+    if save_as {
+        save_dialog.open(project.path.clone());
+    }
+
+    // 底部状态栏：显示开关
+    TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let mut enabled = line_weight_display.enabled;
+            if ui
+                .toggle_value(&mut enabled, "LWT")
+                .on_hover_text("显示/隐藏线宽")
+                .changed()
+            {
+                line_weight_display.enabled = enabled;
+                // 线宽显示开关随场景保存
+                for mut editor in &mut editor_query {
+                    editor.is_dirty = true;
+                }
+            }
+            let mut plan = *view_mode == ViewMode::Plan;
+            if ui
+                .toggle_value(&mut plan, "2D")
+                .on_hover_text("俯视的 2D 视图 / 可旋转的 3D 视图")
+                .changed()
+            {
+                *view_mode = if plan {
+                    ViewMode::Plan
+                } else {
+                    ViewMode::Orbit
+                };
+            }
+            let mut snap = snap_settings.enabled;
+            let response = ui
+                .toggle_value(&mut snap, "OSNAP")
+                .on_hover_text("开/关对象捕捉 (F3)，右键打开捕捉设置");
+            if response.changed() {
+                snap_settings.enabled = snap;
+            }
+            if response.secondary_clicked() {
+                snap_settings.panel_open = true;
             }
         });
     });
@@ -49,11 +188,81 @@ pub fn in_project_ui_system(
             }
         });
 
+    // 右侧图层面板
+    SidePanel::right("layer_panel")
+        .resizable(true)
+        .min_width(100.0)
+        .default_width(240.0)
+        .show(ctx, |ui| {
+            ui.heading("图层");
+            ui.separator();
+            // 只在有修改时标记变化，避免每帧都触发图层表变化
+            let before = layers.layers.clone();
+            if layers.bypass_change_detection().show(ui) {
+                layers.set_changed();
+                let after = layers.layers.clone();
+                // 拖动取色器时每帧都会修改，合并为一条命令
+                history.push_merged("修改图层状态", vec![Change::Layers { before, after }]);
+                // 图层状态随场景保存
+                for mut editor in &mut editor_query {
+                    editor.is_dirty = true;
+                }
+            }
+        });
+
+    // 后台加载 DXF 时显示进度，可以取消
+    if let Some((path, fraction)) = load_task.status() {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("DXF")
+            .to_string();
+        let mut cancel = false;
+        egui::Window::new("加载 DXF")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("正在加载 {}", name));
+                match fraction {
+                    Some(fraction) => ui.add(ProgressBar::new(fraction).show_percentage()),
+                    None => ui.add(ProgressBar::new(0.0).animate(true).text("正在解析文件...")),
+                };
+                if ui.button("取消").clicked() {
+                    cancel = true;
+                }
+            });
+        if cancel {
+            load_task.cancel();
+        }
+    }
+
+    // 导出时有填充无法写出（几何数据被修改过或新绘制的填充）
+    if export_dialog.skipped_hatches > 0 {
+        let mut close = false;
+        egui::Window::new("导出 DXF")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{} 个填充无法写出：只有导入后没有修改几何数据的填充能写回 DXF。",
+                    export_dialog.skipped_hatches
+                ));
+                if ui.button("确定").clicked() {
+                    close = true;
+                }
+            });
+        if close {
+            export_dialog.skipped_hatches = 0;
+        }
+    }
+
     // 显示对话框和处理事件
     if let Some(file_tree) = file_tree.as_mut() {
         file_tree.show_new_item_dialog(ctx);
         file_tree.show_dxf_json_viewer(ctx);
-        file_tree.process_pending_events(&mut dxf_messages);
+        file_tree.process_pending_events(&mut guard);
     }
 
     Ok(())
@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use bevy_egui::egui::{Grid, ScrollArea, Ui};
+use dxf::Drawing;
+use serde::{Deserialize, Serialize};
+
+use super::dxf_block::BlockPart;
+use super::dxf_codes::DxfCodes;
+use super::dxf_color::CadColor;
+use super::dxf_lineweight::{DEFAULT_LINE_WEIGHT, line_weight_from_dxf};
+use super::dxf_renderer::CadEntity;
+
+/// 实线线型名
+pub const CONTINUOUS: &str = "Continuous";
+
+/// 图层冻结标记（DXF LAYER 的 70 组码）
+const LAYER_FROZEN: i32 = 1;
+/// 图层锁定标记
+const LAYER_LOCKED: i32 = 4;
+
+/// 一个图层的显示状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerState {
+    pub name: String,
+    /// 图层颜色，只会是 ACI 或真彩色
+    pub color: CadColor,
+    /// 图层线型名
+    #[serde(default = "continuous")]
+    pub line_type: String,
+    /// 图层线宽（毫米）
+    #[serde(default = "default_line_weight")]
+    pub line_weight: f32,
+    /// 开/关：关闭的图层不显示
+    pub visible: bool,
+    /// 冻结的图层不显示
+    pub frozen: bool,
+    /// 锁定的图层上的实体不能选择
+    pub locked: bool,
+}
+
+impl LayerState {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            color: CadColor::Index(7),
+            line_type: continuous(),
+            line_weight: DEFAULT_LINE_WEIGHT,
+            visible: true,
+            frozen: false,
+            locked: false,
+        }
+    }
+
+    /// 图层的显示颜色
+    pub fn display_color(&self) -> Color {
+        self.color.resolve(Color::WHITE, Color::WHITE)
+    }
+}
+
+/// 图层表 - 导入 DXF 时从 LAYER 表生成，随场景保存
+#[derive(Resource, Debug, Clone, Default)]
+pub struct LayerTable {
+    pub layers: Vec<LayerState>,
+}
+
+impl LayerTable {
+    pub fn new(layers: Vec<LayerState>) -> Self {
+        Self { layers }
+    }
+
+    /// 从 DXF 的 LAYER 表生成
+    ///
+    /// dxf 库不读取图层的冻结和锁定标志，从 codes 中按图层名查找。
+    pub fn from_drawing(drawing: &Drawing, codes: &DxfCodes) -> Self {
+        let layers = drawing
+            .layers()
+            .map(|layer| LayerState {
+                name: layer.name.clone(),
+                // 关闭的图层颜色号为负，dxf 库读取时已取绝对值
+                color: CadColor::Index(layer.color.index().unwrap_or(7)),
+                line_type: layer.line_type_name.clone(),
+                line_weight: line_weight_from_dxf(layer.line_weight.raw_value())
+                    .unwrap_or(DEFAULT_LINE_WEIGHT),
+                visible: layer.is_layer_on,
+                frozen: codes.layer_flags(&layer.name) & LAYER_FROZEN != 0,
+                locked: codes.layer_flags(&layer.name) & LAYER_LOCKED != 0,
+            })
+            .collect();
+        Self { layers }
+    }
+
+    /// 按名称查找图层（图层名不区分大小写）
+    pub fn get(&self, name: &str) -> Option<&LayerState> {
+        self.layers
+            .iter()
+            .find(|layer| layer.name.eq_ignore_ascii_case(name))
+    }
+
+    /// 图层不存在时添加
+    pub fn ensure(&mut self, name: &str) {
+        if self.get(name).is_none() {
+            self.layers.push(LayerState::new(name.to_string()));
+        }
+    }
+
+    /// 图层颜色，图层不存在时为白色
+    pub fn color(&self, name: &str) -> Color {
+        self.get(name)
+            .map_or(Color::WHITE, |layer| layer.display_color())
+    }
+
+    /// 图层是否显示（打开且未冻结），图层不存在时显示
+    pub fn is_visible(&self, name: &str) -> bool {
+        self.get(name)
+            .is_none_or(|layer| layer.visible && !layer.frozen)
+    }
+
+    /// 图层线型名，图层不存在时为实线
+    pub fn line_type(&self, name: &str) -> &str {
+        self.get(name)
+            .map_or(CONTINUOUS, |layer| layer.line_type.as_str())
+    }
+
+    /// 图层线宽（毫米），图层不存在时为默认线宽
+    pub fn line_weight(&self, name: &str) -> f32 {
+        self.get(name)
+            .map_or(DEFAULT_LINE_WEIGHT, |layer| layer.line_weight)
+    }
+
+    pub fn is_locked(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|layer| layer.locked)
+    }
+
+    /// 显示图层面板，有修改时返回 true
+    pub fn show(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        if self.layers.is_empty() {
+            ui.label("没有图层");
+            return false;
+        }
+        ScrollArea::vertical().show(ui, |ui| {
+            Grid::new("layer_grid").striped(true).show(ui, |ui| {
+                ui.label("图层");
+                ui.label("开");
+                ui.label("冻结");
+                ui.label("锁定");
+                ui.label("颜色");
+                ui.end_row();
+                for layer in &mut self.layers {
+                    ui.label(&layer.name);
+                    changed |= ui.checkbox(&mut layer.visible, "").changed();
+                    changed |= ui.checkbox(&mut layer.frozen, "").changed();
+                    changed |= ui.checkbox(&mut layer.locked, "").changed();
+                    let mut rgb = layer.display_color().to_srgba().to_u8_array_no_alpha();
+                    let response = ui.color_edit_button_srgb(&mut rgb);
+                    if let CadColor::Index(index) = layer.color {
+                        response.on_hover_text(format!("ACI {}", index));
+                    }
+                    if rgb != layer.display_color().to_srgba().to_u8_array_no_alpha() {
+                        layer.color = CadColor::TrueColor(rgb);
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        changed
+    }
+}
+
+fn continuous() -> String {
+    CONTINUOUS.to_string()
+}
+
+fn default_line_weight() -> f32 {
+    DEFAULT_LINE_WEIGHT
+}
+
+/// 图层状态系统 - 图层表变化时更新实体的颜色和可选择状态
+///
+/// 随层的实体使用图层颜色，随块的子实体使用父实体的颜色；锁定图层上的实体不能选择。
+/// 不在图层表中的图层上的实体保持原来的颜色。
+pub fn layer_state_system(
+    layers: Res<LayerTable>,
+    root_query: Query<Entity, (With<CadEntity>, Without<BlockPart>)>,
+    mut cad_query: Query<(&mut CadEntity, Option<&Children>, Has<BlockPart>)>,
+) {
+    if !layers.is_changed() {
+        return;
+    }
+    for entity in &root_query {
+        apply_layer_state(entity, None, &layers, &mut cad_query);
+    }
+}
+
+/// 更新实体及其子实体；block_color 是父实体的颜色，顶层实体为 None
+fn apply_layer_state(
+    entity: Entity,
+    block_color: Option<Color>,
+    layers: &LayerTable,
+    cad_query: &mut Query<(&mut CadEntity, Option<&Children>, Has<BlockPart>)>,
+) {
+    let Ok((mut cad, children, is_part)) = cad_query.get_mut(entity) else {
+        return;
+    };
+    let layer_color = layers.get(&cad.layer).map(|layer| layer.display_color());
+    let color = match (cad.color_spec, layer_color, block_color) {
+        (CadColor::ByLayer, Some(color), _) | (CadColor::ByBlock, _, Some(color)) => color,
+        (CadColor::ByLayer | CadColor::ByBlock, _, _) => cad.color,
+        (spec, _, _) => spec.resolve(Color::WHITE, Color::WHITE),
+    };
+    let selectable = !is_part && !layers.is_locked(&cad.layer);
+    // 只在有变化时修改，避免触发脏标记
+    if cad.color != color {
+        cad.color = color;
+    }
+    if cad.selectable != selectable {
+        cad.selectable = selectable;
+    }
+    let children: Vec<Entity> = children.map(|c| c.to_vec()).unwrap_or_default();
+    for child in children {
+        apply_layer_state(child, Some(color), layers, cad_query);
+    }
+}
@@ -6,8 +6,66 @@ mod project;
 pub use project::Project;
 mod focus_change;
 pub use focus_change::focus_change_system;
+mod view_mode;
+pub use view_mode::{ViewMode, plan_view_system, view_mode_system};
+mod zoom;
+pub use zoom::{ZoomMessage, ZoomWindow, zoom_system, zoom_window_ui_system};
+mod selection;
+pub use selection::{
+    SelectionBox, selection_box_ui_system, selection_highlight_system, selection_system,
+};
+use selection::{SelectionGizmos, selection_gizmo_config};
+mod history;
+pub use history::{EditHistory, delete_selection_system, history_panel_system, history_system};
+mod draw;
+pub use draw::{DrawState, draw_cursor_system, draw_preview_system, draw_system, draw_ui_system};
+mod snap;
+pub use snap::{
+    SnapCache, SnapSettings, SnapTarget, snap_cache_system, snap_system, snap_ui_system,
+};
+mod properties_panel;
+pub use properties_panel::properties_panel_system;
+mod unsaved_guard;
+pub use unsaved_guard::{
+    GuardedAction, UnsavedGuard, unsaved_guard_system, window_close_request_system,
+};
 mod dxf_renderer;
-pub use dxf_renderer::{dxf_load_system, dxf_gizmos_system, LoadDxfMessage, DxfDrawData};
+pub use dxf_renderer::{
+    ArcEntity, CadEntity, CadGeometry, CadGeometryQuery, CircleEntity, DxfDrawData, DxfSource,
+    LineEntity, LoadDxfMessage, PolylineEntity, SourceDrawing, TextEntity, TextHAlign, TextVAlign,
+    dxf_draw_data_sync_system, dxf_gizmos_system,
+};
+mod dxf_load;
+pub use dxf_load::{DxfLoadTask, dxf_load_apply_system, dxf_load_system};
+mod dxf_block;
+pub use dxf_block::{CadNode, InsertEntity, spawn_cad_node};
+mod dxf_tessellation;
+pub use dxf_tessellation::{CurveTolerance, curve_tolerance_system};
+mod dxf_curve;
+pub use dxf_curve::{EllipseEntity, SplineEntity};
+mod dxf_hatch;
+pub use dxf_hatch::{HatchEntity, HatchLine};
+mod dxf_codes;
+mod dxf_color;
+pub use dxf_color::CadColor;
+mod dxf_ocs;
+mod dxf_polyline;
+pub use dxf_polyline::dxf_fill_mesh_system;
+mod dxf_line_mesh;
+pub use dxf_line_mesh::{dxf_layer_mesh_visibility_system, dxf_line_mesh_system};
+mod dxf_dimension;
+pub use dxf_dimension::{DimensionEntity, DimensionKind};
+mod dxf_text;
+pub use dxf_text::dxf_text_system;
+mod dxf_lineweight;
+pub use dxf_lineweight::LineWeightDisplay;
+use dxf_lineweight::{LineWeightGizmos, line_weight_config};
+mod dxf_linetype;
+pub use dxf_linetype::LineTypeTable;
+mod layer_panel;
+pub use layer_panel::{LayerState, LayerTable, layer_state_system};
+mod dxf_export;
+pub use dxf_export::{ExportDxfDialog, ExportDxfMessage, dxf_export_system, export_dialog_system};
 
 use bevy::prelude::*;
 use bevy_egui::EguiPrimaryContextPass;
@@ -22,15 +80,95 @@ impl Plugin for InProjectPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(PanOrbitCameraPlugin)
             .add_message::<LoadDxfMessage>()
+            .add_message::<editor::OpenSceneMessage>()
+            .add_message::<editor::SaveSceneMessage>()
+            .add_message::<ExportDxfMessage>()
+            .add_message::<ZoomMessage>()
             .init_resource::<DxfDrawData>()
+            .init_resource::<CurveTolerance>()
+            .init_resource::<ViewMode>()
+            .init_resource::<ZoomWindow>()
+            .init_resource::<SelectionBox>()
+            .init_resource::<EditHistory>()
+            .init_resource::<DrawState>()
+            .init_resource::<SnapSettings>()
+            .init_resource::<SnapTarget>()
+            .init_resource::<SnapCache>()
+            .init_resource::<UnsavedGuard>()
+            .init_resource::<editor::SaveDialog>()
+            .init_resource::<SourceDrawing>()
+            .init_resource::<DxfLoadTask>()
+            .init_resource::<ExportDxfDialog>()
+            .init_resource::<LayerTable>()
+            .init_resource::<LineTypeTable>()
+            .init_resource::<LineWeightDisplay>()
+            .insert_gizmo_config(LineWeightGizmos::<1>, line_weight_config(1))
+            .insert_gizmo_config(LineWeightGizmos::<2>, line_weight_config(2))
+            .insert_gizmo_config(LineWeightGizmos::<3>, line_weight_config(3))
+            .insert_gizmo_config(SelectionGizmos, selection_gizmo_config())
             .add_systems(OnEnter(AppState::InPreject), editor::create_blank_editor)
             .add_systems(
                 EguiPrimaryContextPass,
-                in_project_ui_system.run_if(in_state(AppState::InPreject)),
+                (
+                    dxf_text_system.run_if(in_state(AppState::InPreject)),
+                    in_project_ui_system.run_if(in_state(AppState::InPreject)),
+                    draw_ui_system.run_if(in_state(AppState::InPreject)),
+                    snap_ui_system.run_if(in_state(AppState::InPreject)),
+                    properties_panel_system.run_if(in_state(AppState::InPreject)),
+                    history_panel_system.run_if(in_state(AppState::InPreject)),
+                    zoom_window_ui_system.run_if(in_state(AppState::InPreject)),
+                    selection_box_ui_system.run_if(in_state(AppState::InPreject)),
+                    // 关闭窗口在任何状态下都要经过检查
+                    unsaved_guard_system,
+                )
+                    .chain(),
             )
+            .add_systems(Update, window_close_request_system)
             .add_systems(
                 Update,
-                (focus_change_system, dxf_load_system, dxf_gizmos_system)
+                (
+                    // 相机和输入
+                    (
+                        focus_change_system,
+                        view_mode_system,
+                        plan_view_system,
+                        draw_cursor_system,
+                        snap_cache_system,
+                        snap_system,
+                    )
+                        .chain(),
+                    // 文件和命令
+                    (
+                        editor::open_scene_system,
+                        dxf_load_system,
+                        dxf_load_apply_system,
+                        zoom_system,
+                        draw_system,
+                        selection_system,
+                        delete_selection_system,
+                        history_system,
+                        editor::mark_dirty_system,
+                        editor::save_dialog_system,
+                        editor::save_scene_system,
+                        export_dialog_system,
+                        dxf_export_system,
+                    )
+                        .chain(),
+                    // 绘制
+                    (
+                        layer_state_system,
+                        curve_tolerance_system,
+                        dxf_draw_data_sync_system,
+                        dxf_line_mesh_system,
+                        dxf_fill_mesh_system,
+                        dxf_layer_mesh_visibility_system,
+                        dxf_gizmos_system,
+                        selection_highlight_system,
+                        draw_preview_system,
+                    )
+                        .chain(),
+                )
+                    .chain()
                     .run_if(in_state(AppState::InPreject)),
             )
             .add_systems(OnExit(AppState::InPreject), editor::dispose_system);
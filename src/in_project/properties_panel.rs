@@ -0,0 +1,374 @@
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use egui::{ComboBox, DragValue, Grid, ScrollArea, SidePanel, Ui};
+
+use super::dxf_color::CadColor;
+use super::dxf_renderer::{
+    ArcEntity, CadEntity, CadEntityType, CadGeometry, CircleEntity, LineEntity, PolylineEntity,
+};
+use super::history::{Change, EditHistory};
+use super::layer_panel::LayerTable;
+use super::selection::Selected;
+
+/// 选中的实体取值不同时显示的文字
+const VARIES: &str = "多种";
+
+/// 几何数值字段的类型
+#[derive(Clone, Copy)]
+enum FieldKind {
+    /// 坐标分量
+    Coordinate,
+    /// 半径等不能为负的长度
+    Length,
+    /// 角度，内部为弧度，界面上按度显示
+    Angle,
+}
+
+/// 几何数值字段：名称、取字段的函数、字段类型
+type NumberField<T> = (&'static str, fn(&mut T) -> &mut f32, FieldKind);
+
+/// 坐标按 DXF 坐标系显示：DXF 的 Y 是世界坐标的 Z，DXF 的 Z 是世界坐标的 Y
+const LINE_FIELDS: &[NumberField<LineEntity>] = &[
+    ("起点 X", |l| &mut l.start.x, FieldKind::Coordinate),
+    ("起点 Y", |l| &mut l.start.z, FieldKind::Coordinate),
+    ("起点 Z", |l| &mut l.start.y, FieldKind::Coordinate),
+    ("终点 X", |l| &mut l.end.x, FieldKind::Coordinate),
+    ("终点 Y", |l| &mut l.end.z, FieldKind::Coordinate),
+    ("终点 Z", |l| &mut l.end.y, FieldKind::Coordinate),
+];
+
+const CIRCLE_FIELDS: &[NumberField<CircleEntity>] = &[
+    ("圆心 X", |c| &mut c.center.x, FieldKind::Coordinate),
+    ("圆心 Y", |c| &mut c.center.z, FieldKind::Coordinate),
+    ("圆心 Z", |c| &mut c.center.y, FieldKind::Coordinate),
+    ("半径", |c| &mut c.radius, FieldKind::Length),
+];
+
+const ARC_FIELDS: &[NumberField<ArcEntity>] = &[
+    ("圆心 X", |a| &mut a.center.x, FieldKind::Coordinate),
+    ("圆心 Y", |a| &mut a.center.z, FieldKind::Coordinate),
+    ("圆心 Z", |a| &mut a.center.y, FieldKind::Coordinate),
+    ("半径", |a| &mut a.radius, FieldKind::Length),
+    ("起始角度", |a| &mut a.start_angle, FieldKind::Angle),
+    ("终止角度", |a| &mut a.end_angle, FieldKind::Angle),
+];
+
+/// 特性面板系统 - 显示并编辑选中实体的类型、图层、颜色和几何数据
+///
+/// 修改直接写回组件，绘制数据和脏标记由组件的变化检测更新，修改前后的数据记录到编辑历史。
+/// 选中多个实体时只显示共同的字段，取值不同的字段标出"多种"，编辑后写入所有选中的实体。
+#[allow(clippy::too_many_arguments)]
+pub fn properties_panel_system(
+    mut contexts: EguiContexts,
+    mut layers: ResMut<LayerTable>,
+    mut history: ResMut<EditHistory>,
+    mut cad_query: Query<(Entity, &mut CadEntity), With<Selected>>,
+    mut line_query: Query<(Entity, &mut LineEntity), With<Selected>>,
+    mut circle_query: Query<(Entity, &mut CircleEntity), With<Selected>>,
+    mut arc_query: Query<(Entity, &mut ArcEntity), With<Selected>>,
+    mut polyline_query: Query<(Entity, &mut PolylineEntity), With<Selected>>,
+) -> Result {
+    let count = cad_query.iter().len();
+    if count == 0 {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+
+    SidePanel::right("properties_panel")
+        .resizable(true)
+        .min_width(100.0)
+        .default_width(240.0)
+        .show(ctx, |ui| {
+            ui.heading("特性");
+            ui.label(format!("选中 {} 个实体", count));
+            ui.separator();
+
+            let entity_type = shared(cad_query.iter().map(|(_, cad)| cad.entity_type.clone()));
+            let mut cad_changed = false;
+            ScrollArea::vertical().show(ui, |ui| {
+                Grid::new("properties_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("类型");
+                        ui.label(entity_type.as_ref().map_or(VARIES, type_name));
+                        ui.end_row();
+                        cad_changed |= layer_field(ui, &layers, &mut history, &mut cad_query);
+                        cad_changed |= color_field(ui, &mut history, &mut cad_query);
+
+                        match entity_type {
+                            Some(CadEntityType::Line) => {
+                                number_fields(
+                                    ui,
+                                    &mut history,
+                                    &mut line_query,
+                                    LINE_FIELDS,
+                                    CadGeometry::Line,
+                                );
+                            }
+                            Some(CadEntityType::Circle) => {
+                                number_fields(
+                                    ui,
+                                    &mut history,
+                                    &mut circle_query,
+                                    CIRCLE_FIELDS,
+                                    CadGeometry::Circle,
+                                );
+                            }
+                            Some(CadEntityType::Arc) => {
+                                number_fields(
+                                    ui,
+                                    &mut history,
+                                    &mut arc_query,
+                                    ARC_FIELDS,
+                                    CadGeometry::Arc,
+                                );
+                            }
+                            Some(CadEntityType::Polyline) => {
+                                polyline_fields(ui, &mut history, &mut polyline_query);
+                            }
+                            _ => {}
+                        }
+                    });
+            });
+            // 图层和颜色的修改由图层状态系统重新计算显示颜色和可选择状态
+            if cad_changed {
+                layers.set_changed();
+            }
+        });
+    Ok(())
+}
+
+/// 所有取值相同时返回这个值
+fn shared<T: PartialEq>(mut values: impl Iterator<Item = T>) -> Option<T> {
+    let first = values.next()?;
+    values.all(|value| value == first).then_some(first)
+}
+
+fn type_name(entity_type: &CadEntityType) -> &'static str {
+    match entity_type {
+        CadEntityType::Line => "直线",
+        CadEntityType::Circle => "圆",
+        CadEntityType::Arc => "圆弧",
+        CadEntityType::Polyline => "多段线",
+        CadEntityType::Text => "文字",
+        CadEntityType::Insert => "块参照",
+        CadEntityType::Dimension => "标注",
+        CadEntityType::Spline => "样条曲线",
+        CadEntityType::Ellipse => "椭圆",
+        CadEntityType::Hatch => "填充",
+    }
+}
+
+fn color_name(color: CadColor) -> String {
+    match color {
+        CadColor::ByLayer => "随层".to_string(),
+        CadColor::ByBlock => "随块".to_string(),
+        CadColor::Index(index) => format!("ACI {}", index),
+        CadColor::TrueColor([r, g, b]) => format!("{}, {}, {}", r, g, b),
+    }
+}
+
+/// 图层下拉框，返回是否修改
+fn layer_field(
+    ui: &mut Ui,
+    layers: &LayerTable,
+    history: &mut EditHistory,
+    cad_query: &mut Query<(Entity, &mut CadEntity), With<Selected>>,
+) -> bool {
+    let current = shared(cad_query.iter().map(|(_, cad)| cad.layer.clone()));
+    let mut selected = current.clone();
+    ui.label("图层");
+    ComboBox::from_id_salt("properties_layer")
+        .selected_text(current.as_deref().unwrap_or(VARIES))
+        .show_ui(ui, |ui| {
+            for layer in &layers.layers {
+                ui.selectable_value(&mut selected, Some(layer.name.clone()), &layer.name);
+            }
+        });
+    ui.end_row();
+    let Some(layer) = selected.filter(|layer| Some(layer) != current.as_ref()) else {
+        return false;
+    };
+    let changes = edit_all(
+        cad_query,
+        |cad| cad.layer = layer.clone(),
+        |entity, before, after| Change::Properties {
+            entity,
+            before,
+            after,
+        },
+    );
+    history.push("修改图层", changes);
+    true
+}
+
+/// 颜色下拉框和取色按钮，返回是否修改
+fn color_field(
+    ui: &mut Ui,
+    history: &mut EditHistory,
+    cad_query: &mut Query<(Entity, &mut CadEntity), With<Selected>>,
+) -> bool {
+    let current = shared(cad_query.iter().map(|(_, cad)| cad.color_spec));
+    let mut selected = current;
+    ui.label("颜色");
+    ui.horizontal(|ui| {
+        ComboBox::from_id_salt("properties_color")
+            .selected_text(current.map_or(VARIES.to_string(), color_name))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, Some(CadColor::ByLayer), "随层");
+                ui.selectable_value(&mut selected, Some(CadColor::ByBlock), "随块");
+            });
+        // 取色后改为真彩色
+        let display = shared(
+            cad_query
+                .iter()
+                .map(|(_, cad)| cad.color.to_srgba().to_u8_array()),
+        );
+        let mut rgb = display.map_or([255; 3], |[r, g, b, _]| [r, g, b]);
+        if ui.color_edit_button_srgb(&mut rgb).changed() {
+            selected = Some(CadColor::TrueColor(rgb));
+        }
+    });
+    ui.end_row();
+    let Some(color) = selected.filter(|color| Some(*color) != current) else {
+        return false;
+    };
+    let changes = edit_all(
+        cad_query,
+        |cad| cad.color_spec = color,
+        |entity, before, after| Change::Properties {
+            entity,
+            before,
+            after,
+        },
+    );
+    // 拖动取色器时每帧都会修改，合并为一条命令
+    history.push_merged("修改颜色", changes);
+    true
+}
+
+/// 一组数值字段：每个字段一行，取值不同时标出"多种"；编辑后写入所有选中的实体
+///
+/// geometry 把组件包装为几何数据，用于记录编辑历史。
+fn number_fields<T: Component<Mutability = Mutable> + Clone>(
+    ui: &mut Ui,
+    history: &mut EditHistory,
+    query: &mut Query<(Entity, &mut T), With<Selected>>,
+    fields: &[NumberField<T>],
+    geometry: fn(T) -> CadGeometry,
+) {
+    for (label, field, kind) in fields {
+        // 读取时不触发变化检测
+        let values: Vec<f32> = query
+            .iter_mut()
+            .map(|(_, mut item)| *field(item.bypass_change_detection()))
+            .collect();
+        let Some(&value) = values.first() else {
+            continue;
+        };
+        let varies = values.iter().any(|v| *v != value);
+        ui.label(*label);
+        if let Some(value) = number_edit(ui, value, *kind) {
+            let changes = edit_all(
+                query,
+                |item| *field(item) = value,
+                |entity, before, after| Change::Geometry {
+                    entity,
+                    before: Box::new(geometry(before)),
+                    after: Box::new(geometry(after)),
+                },
+            );
+            // 拖动数值时每帧都会修改，合并为一条命令
+            history.push_merged(format!("修改{}", label), changes);
+        }
+        if varies {
+            ui.weak(VARIES);
+        }
+        ui.end_row();
+    }
+}
+
+/// 数值编辑框，修改后返回新值（角度换算回弧度）
+fn number_edit(ui: &mut Ui, value: f32, kind: FieldKind) -> Option<f32> {
+    let mut shown = match kind {
+        FieldKind::Angle => value.to_degrees(),
+        _ => value,
+    };
+    let mut drag = DragValue::new(&mut shown).speed(0.1);
+    match kind {
+        FieldKind::Length => drag = drag.range(0.0..=f32::MAX),
+        FieldKind::Angle => drag = drag.suffix("°"),
+        FieldKind::Coordinate => {}
+    }
+    if !ui.add(drag).changed() {
+        return None;
+    }
+    Some(match kind {
+        FieldKind::Angle => shown.to_radians(),
+        _ => shown,
+    })
+}
+
+/// 多段线：闭合标记；只选中一条多段线时可以编辑各顶点
+fn polyline_fields(
+    ui: &mut Ui,
+    history: &mut EditHistory,
+    query: &mut Query<(Entity, &mut PolylineEntity), With<Selected>>,
+) {
+    let geometry = |entity, before, after| Change::Geometry {
+        entity,
+        before: Box::new(CadGeometry::Polyline(before)),
+        after: Box::new(CadGeometry::Polyline(after)),
+    };
+    let current = shared(query.iter().map(|(_, pl)| pl.closed));
+    let mut closed = current.unwrap_or(false);
+    ui.label("闭合");
+    if ui.checkbox(&mut closed, "").changed() {
+        let changes = edit_all(query, |pl| pl.closed = closed, geometry);
+        history.push("修改闭合", changes);
+    }
+    if current.is_none() {
+        ui.weak(VARIES);
+    }
+    ui.end_row();
+
+    let Ok((entity, mut pl)) = query.single_mut() else {
+        return;
+    };
+    let count = pl.vertices.len();
+    for index in 0..count {
+        let vertex = pl.vertices[index];
+        for (axis, value) in [("X", vertex.x), ("Y", vertex.z)] {
+            ui.label(format!("顶点 {} {}", index + 1, axis));
+            if let Some(value) = number_edit(ui, value, FieldKind::Coordinate) {
+                let before = pl.clone();
+                let vertex = &mut pl.vertices[index];
+                match axis {
+                    "X" => vertex.x = value,
+                    _ => vertex.z = value,
+                }
+                let change = geometry(entity, before, pl.clone());
+                history.push_merged(format!("修改顶点 {}", index + 1), vec![change]);
+            }
+            ui.end_row();
+        }
+    }
+}
+
+/// 修改所有选中实体的组件，返回每个实体修改前后的记录
+fn edit_all<T: Component<Mutability = Mutable> + Clone>(
+    query: &mut Query<(Entity, &mut T), With<Selected>>,
+    mut edit: impl FnMut(&mut T),
+    change: impl Fn(Entity, T, T) -> Change,
+) -> Vec<Change> {
+    query
+        .iter_mut()
+        .map(|(entity, mut item)| {
+            let before = item.clone();
+            edit(&mut item);
+            change(entity, before, item.clone())
+        })
+        .collect()
+}
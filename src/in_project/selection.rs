@@ -0,0 +1,314 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui::{Color32, CornerRadius, LayerId, Pos2, Stroke, StrokeKind};
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_2;
+
+use super::draw::DrawState;
+use super::dxf_renderer::{CadEntity, CadGeometryQuery};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+use super::zoom::ZoomWindow;
+
+/// 点选的容差（像素）
+const PICK_TOLERANCE: f32 = 5.0;
+/// 拖动距离小于这个值（像素）时按点选处理
+const MIN_DRAG: f32 = 4.0;
+/// 选中实体的高亮颜色
+const HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 0.75, 0.2);
+/// 文字、标注等只有插入点的实体，高亮时在插入点画的圆的半径（弦高误差的倍数，约 4 像素）
+const MARKER_RADIUS: f32 = 8.0;
+
+/// 选中标记 - 加在被选中的 CAD 实体上
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Selected;
+
+/// 选中高亮 Gizmos 配置组
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct SelectionGizmos;
+
+/// 选中高亮的 Gizmos 配置：较粗，并且画在其他图形前面
+pub fn selection_gizmo_config() -> GizmoConfig {
+    GizmoConfig {
+        line: GizmoLineConfig {
+            width: 3.0,
+            ..default()
+        },
+        depth_bias: -1.0,
+        ..default()
+    }
+}
+
+/// 正在拖动的选择框（视口坐标）
+#[derive(Resource, Debug, Default)]
+pub struct SelectionBox {
+    pub start: Option<Vec2>,
+    pub end: Option<Vec2>,
+}
+
+impl SelectionBox {
+    /// 拖动距离足够大时的选择框，从左向右拖时为窗口选择，从右向左拖时为交叉选择
+    pub fn rect(&self) -> Option<(Rect, bool)> {
+        let (start, end) = (self.start?, self.end?);
+        ((end - start).abs().max_element() >= MIN_DRAG)
+            .then(|| (Rect::from_corners(start, end), start.x <= end.x))
+    }
+}
+
+/// 选择系统 - 左键点选、拖动框选，Shift 加选，Ctrl 减选，Esc 清除选择
+///
+/// 块参照和标注中的图形不能单独选中，拾取到时向上找到可选择的父实体。
+#[allow(clippy::too_many_arguments)]
+pub fn selection_system(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    mut selection_box: ResMut<SelectionBox>,
+    zoom_window: Res<ZoomWindow>,
+    draw: Res<DrawState>,
+    tolerance: Res<CurveTolerance>,
+    layers: Res<LayerTable>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    cad_query: Query<(Entity, &CadEntity, CadGeometryQuery)>,
+    parent_query: Query<&ChildOf>,
+    selected_query: Query<Entity, With<Selected>>,
+) -> Result {
+    // 窗口缩放时左键用于拖出缩放窗口，绘图时左键用于输入点
+    if zoom_window.active || draw.is_active() {
+        *selection_box = SelectionBox::default();
+        return Ok(());
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        *selection_box = SelectionBox::default();
+        for entity in &selected_query {
+            commands.entity(entity).remove::<Selected>();
+        }
+        return Ok(());
+    }
+    let cursor = window.and_then(|window| window.cursor_position());
+    if buttons.just_pressed(MouseButton::Left) {
+        let ctx = contexts.ctx_mut()?;
+        if !ctx.is_pointer_over_area() {
+            selection_box.start = cursor;
+            selection_box.end = cursor;
+        }
+        return Ok(());
+    }
+    let Some(start) = selection_box.start else {
+        return Ok(());
+    };
+    if cursor.is_some() {
+        selection_box.end = cursor;
+    }
+    if !buttons.just_released(MouseButton::Left) {
+        return Ok(());
+    }
+    let rect = selection_box.rect();
+    let point = selection_box.end.unwrap_or(start);
+    *selection_box = SelectionBox::default();
+    let Some((camera, camera_transform)) = camera_query.iter().next() else {
+        return Ok(());
+    };
+
+    // 可见图层上的实体轮廓投影到视口，同时记录每段轮廓属于哪个可选择的实体
+    let selectable_ancestor = |mut entity: Entity| loop {
+        let (_, cad, _) = cad_query.get(entity).ok()?;
+        if cad.selectable {
+            return Some(entity);
+        }
+        entity = parent_query.get(entity).ok()?.parent();
+    };
+    let outlines = cad_query.iter().filter_map(|(entity, cad, geometry)| {
+        if !layers.is_visible(&cad.layer) {
+            return None;
+        }
+        let target = selectable_ancestor(entity)?;
+        let (strips, complete) = project_strips(geometry.outline(&tolerance), |p| {
+            camera.world_to_viewport(camera_transform, p).ok()
+        });
+        Some((target, strips, complete))
+    });
+
+    let hits: Vec<Entity> = match rect {
+        // 点选：光标附近最近的实体
+        None => outlines
+            .filter_map(|(target, strips, _)| {
+                let distance = strips
+                    .iter()
+                    .map(|strip| distance_to_strip(point, strip))
+                    .fold(f32::INFINITY, f32::min);
+                (distance <= PICK_TOLERANCE).then_some((distance, target))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, target)| target)
+            .into_iter()
+            .collect(),
+        // 框选：按可选择的实体汇总，块参照的所有图形都在窗口内才算在窗口内
+        Some((rect, window_mode)) => {
+            let mut targets: HashMap<Entity, (bool, bool)> = HashMap::new();
+            for (target, strips, complete) in outlines {
+                let inside = complete && strips.iter().flatten().all(|p| rect.contains(*p));
+                let touches = strips.iter().any(|strip| strip_touches_rect(strip, rect));
+                let state = targets.entry(target).or_insert((true, false));
+                state.0 &= inside;
+                state.1 |= touches;
+            }
+            targets
+                .into_iter()
+                .filter(|(_, (inside, touches))| if window_mode { *inside } else { *touches })
+                .map(|(target, _)| target)
+                .collect()
+        }
+    };
+
+    let add = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let remove = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !add && !remove {
+        for entity in &selected_query {
+            commands.entity(entity).remove::<Selected>();
+        }
+    }
+    for entity in hits {
+        if remove {
+            commands.entity(entity).remove::<Selected>();
+        } else {
+            commands.entity(entity).insert(Selected);
+        }
+    }
+    Ok(())
+}
+
+/// 选中高亮系统 - 每帧用 Gizmos 画出选中实体及其子实体的轮廓
+pub fn selection_highlight_system(
+    mut gizmos: Gizmos<SelectionGizmos>,
+    tolerance: Res<CurveTolerance>,
+    selected_query: Query<Entity, With<Selected>>,
+    cad_query: Query<(CadGeometryQuery, Option<&Children>)>,
+) {
+    fn draw(
+        gizmos: &mut Gizmos<SelectionGizmos>,
+        tolerance: &CurveTolerance,
+        cad_query: &Query<(CadGeometryQuery, Option<&Children>)>,
+        entity: Entity,
+    ) {
+        let Ok((geometry, children)) = cad_query.get(entity) else {
+            return;
+        };
+        for strip in geometry.outline(tolerance) {
+            if let [point] = strip[..] {
+                // 只有插入点时画一个屏幕上大小固定的圆
+                let isometry = Isometry3d::new(point, Quat::from_rotation_x(FRAC_PI_2));
+                gizmos.circle(isometry, tolerance.chord * MARKER_RADIUS, HIGHLIGHT_COLOR);
+            } else {
+                gizmos.linestrip(strip, HIGHLIGHT_COLOR);
+            }
+        }
+        for &child in children.into_iter().flatten() {
+            draw(gizmos, tolerance, cad_query, child);
+        }
+    }
+
+    for entity in &selected_query {
+        draw(&mut gizmos, &tolerance, &cad_query, entity);
+    }
+}
+
+/// 选择框界面系统 - 画出正在拖动的选择框，窗口选择为蓝色，交叉选择为绿色
+pub fn selection_box_ui_system(
+    mut contexts: EguiContexts,
+    selection_box: Res<SelectionBox>,
+) -> Result {
+    let Some((rect, window_mode)) = selection_box.rect() else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let painter = ctx.layer_painter(LayerId::background());
+    let (r, g, b) = if window_mode {
+        (80, 140, 255)
+    } else {
+        (80, 200, 120)
+    };
+    painter.rect(
+        egui::Rect::from_min_max(
+            Pos2::new(rect.min.x, rect.min.y),
+            Pos2::new(rect.max.x, rect.max.y),
+        ),
+        CornerRadius::ZERO,
+        Color32::from_rgba_unmultiplied(r, g, b, 30),
+        Stroke::new(1.0, Color32::from_rgb(r, g, b)),
+        StrokeKind::Inside,
+    );
+    Ok(())
+}
+
+/// 折线投影到视口，在相机后面的点处断开；返回投影后的折线和是否所有点都能投影
+fn project_strips(
+    strips: Vec<Vec<Vec3>>,
+    project: impl Fn(Vec3) -> Option<Vec2>,
+) -> (Vec<Vec<Vec2>>, bool) {
+    let mut projected = Vec::new();
+    let mut complete = true;
+    for strip in strips {
+        let mut current = Vec::new();
+        for point in strip {
+            match project(point) {
+                Some(p) => current.push(p),
+                None => {
+                    complete = false;
+                    if !current.is_empty() {
+                        projected.push(std::mem::take(&mut current));
+                    }
+                }
+            }
+        }
+        if !current.is_empty() {
+            projected.push(current);
+        }
+    }
+    (projected, complete)
+}
+
+/// 点到折线的距离，单点折线按点计算
+fn distance_to_strip(point: Vec2, strip: &[Vec2]) -> f32 {
+    if let [single] = strip {
+        return point.distance(*single);
+    }
+    strip
+        .windows(2)
+        .map(|pair| {
+            let (a, ab) = (pair[0], pair[1] - pair[0]);
+            let t = if ab.length_squared() > 0.0 {
+                ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            point.distance(a + ab * t)
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// 折线是否有点在矩形内或与矩形的边相交
+fn strip_touches_rect(strip: &[Vec2], rect: Rect) -> bool {
+    if strip.iter().any(|p| rect.contains(*p)) {
+        return true;
+    }
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+    strip.windows(2).any(|pair| {
+        (0..4).any(|i| segments_intersect(pair[0], pair[1], corners[i], corners[(i + 1) % 4]))
+    })
+}
+
+fn segments_intersect(p1: Vec2, p2: Vec2, q1: Vec2, q2: Vec2) -> bool {
+    let (p, q) = (p2 - p1, q2 - q1);
+    p.perp_dot(q1 - p1) * p.perp_dot(q2 - p1) <= 0.0
+        && q.perp_dot(p1 - q1) * q.perp_dot(p2 - q1) <= 0.0
+}
@@ -0,0 +1,758 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui::{Color32, CornerRadius, FontId, Key, LayerId, Modifiers, Pos2, Shape, Stroke};
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use super::draw::DrawState;
+use super::dxf_block::InsertEntity;
+use super::dxf_curve::{EllipseEntity, SplineEntity};
+use super::dxf_dimension::DimensionEntity;
+use super::dxf_hatch::HatchEntity;
+use super::dxf_polyline::PolylineSegment;
+use super::dxf_renderer::{
+    ArcEntity, CadEntity, CadGeometryQuery, CadGeometryQueryItem, CircleEntity, LineEntity,
+    PolylineEntity, TextEntity,
+};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+
+/// 捕捉范围（像素），光标附近这个范围内的点才会被捕捉
+const APERTURE: f32 = 10.0;
+/// 捕捉标记的半边长（像素）
+const MARKER_SIZE: f32 = 6.0;
+/// 捕捉标记的颜色
+const MARKER_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+
+/// 对象捕捉模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapMode {
+    /// 直线、圆弧、多段线各段的端点
+    Endpoint,
+    /// 直线、圆弧、多段线各段的中点
+    Midpoint,
+    /// 圆、圆弧、椭圆的圆心
+    Center,
+    /// 两个图形的交点
+    Intersection,
+    /// 从上一个点到直线或圆的垂足
+    Perpendicular,
+    /// 从上一个点到圆的切点
+    Tangent,
+    /// 图形上离光标最近的点
+    Nearest,
+}
+
+impl SnapMode {
+    const ALL: [SnapMode; 7] = [
+        SnapMode::Endpoint,
+        SnapMode::Midpoint,
+        SnapMode::Center,
+        SnapMode::Intersection,
+        SnapMode::Perpendicular,
+        SnapMode::Tangent,
+        SnapMode::Nearest,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SnapMode::Endpoint => "端点",
+            SnapMode::Midpoint => "中点",
+            SnapMode::Center => "圆心",
+            SnapMode::Intersection => "交点",
+            SnapMode::Perpendicular => "垂足",
+            SnapMode::Tangent => "切点",
+            SnapMode::Nearest => "最近点",
+        }
+    }
+}
+
+/// 对象捕捉设置
+#[derive(Resource, Debug)]
+pub struct SnapSettings {
+    /// 对象捕捉总开关（F3）
+    pub enabled: bool,
+    /// 启用的捕捉模式
+    pub modes: Vec<SnapMode>,
+    /// 是否显示设置面板
+    pub panel_open: bool,
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            modes: vec![
+                SnapMode::Endpoint,
+                SnapMode::Midpoint,
+                SnapMode::Center,
+                SnapMode::Intersection,
+                SnapMode::Perpendicular,
+            ],
+            panel_open: false,
+        }
+    }
+}
+
+impl SnapSettings {
+    pub fn is_active(&self, mode: SnapMode) -> bool {
+        self.enabled && self.modes.contains(&mode)
+    }
+
+    fn set(&mut self, mode: SnapMode, active: bool) {
+        self.modes.retain(|m| *m != mode);
+        if active {
+            self.modes.push(mode);
+        }
+    }
+}
+
+/// 捕捉到的点
+#[derive(Debug, Clone, Copy)]
+pub struct Snap {
+    pub point: Vec3,
+    pub mode: SnapMode,
+    /// 视口坐标，用于画捕捉标记
+    pub screen: Vec2,
+}
+
+/// 当前捕捉到的点，没有捕捉到时为 None
+#[derive(Resource, Debug, Default)]
+pub struct SnapTarget {
+    pub snap: Option<Snap>,
+}
+
+/// 捕捉用的图形数据缓存：特征点、离散后的轮廓和包围盒，实体改变时才重新计算
+#[derive(Resource, Default)]
+pub struct SnapCache {
+    entities: HashMap<Entity, SnapShape>,
+}
+
+/// 一个实体的捕捉数据
+struct SnapShape {
+    features: Features,
+    /// 离散后的轮廓，用于求交点和最近点
+    outline: Vec<Vec<Vec3>>,
+    /// 包含所有候选点的包围盒（圆弧的圆心可能在轮廓之外）
+    bounds: Option<(Vec3, Vec3)>,
+}
+
+impl SnapShape {
+    fn of(geometry: &CadGeometryQueryItem, tolerance: &CurveTolerance) -> Self {
+        let features = Features::of(geometry, tolerance);
+        let outline = geometry.outline(tolerance);
+        let bounds = outline
+            .iter()
+            .flatten()
+            .chain(&features.centers)
+            .map(|&p| (p, p))
+            .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)));
+        Self {
+            features,
+            outline,
+            bounds,
+        }
+    }
+
+    /// 包围盒投影到视口后是否在光标的捕捉范围内；有角点无法投影时不剔除
+    fn near(&self, cursor: Vec2, project: &impl Fn(Vec3) -> Option<Vec2>) -> bool {
+        let Some((min, max)) = self.bounds else {
+            return false;
+        };
+        let mut rect: Option<Rect> = None;
+        for corner in 0..8 {
+            let point = Vec3::new(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            );
+            let Some(screen) = project(point) else {
+                return true;
+            };
+            rect = Some(rect.map_or(Rect::from_corners(screen, screen), |rect| {
+                rect.union_point(screen)
+            }));
+        }
+        rect.is_some_and(|rect| rect.inflate(APERTURE).contains(cursor))
+    }
+}
+
+/// 捕捉缓存系统 - CAD 实体有增删改时更新它们的捕捉数据，弦高误差变化时只更新曲线
+#[allow(clippy::type_complexity)]
+pub fn snap_cache_system(
+    mut cache: ResMut<SnapCache>,
+    tolerance: Res<CurveTolerance>,
+    changed_query: Query<
+        Entity,
+        Or<(
+            Changed<CadEntity>,
+            Changed<LineEntity>,
+            Changed<CircleEntity>,
+            Changed<ArcEntity>,
+            Changed<PolylineEntity>,
+            Changed<TextEntity>,
+            Changed<InsertEntity>,
+            Changed<DimensionEntity>,
+            Changed<SplineEntity>,
+            Changed<EllipseEntity>,
+            Changed<HatchEntity>,
+        )>,
+    >,
+    mut removed: RemovedComponents<CadEntity>,
+    cad_query: Query<(Entity, CadGeometryQuery), With<CadEntity>>,
+) {
+    for entity in removed.read() {
+        cache.entities.remove(&entity);
+    }
+    let entities: Vec<Entity> = if tolerance.is_changed() {
+        cad_query
+            .iter()
+            .filter(|(entity, geometry)| geometry.is_curve() || changed_query.contains(*entity))
+            .map(|(entity, _)| entity)
+            .collect()
+    } else {
+        changed_query.iter().collect()
+    };
+    for (entity, geometry) in cad_query.iter_many(&entities) {
+        cache
+            .entities
+            .insert(entity, SnapShape::of(&geometry, &tolerance));
+    }
+}
+
+/// 对象捕捉系统 - 在光标附近查找图形上的特征点，把绘图光标移到捕捉到的点上
+///
+/// 只在绘图时捕捉：绘图命令都从绘图光标取点，捕捉到的点对它们生效。现有的编辑命令（删除、
+/// 修改特性）不需要输入点，选择和窗口缩放按鼠标位置取点，不经过捕捉。
+/// 端点、中点等特征点优先于最近点，垂足和切点以上一个输入的点为基点。
+/// 包围盒投影到视口后离光标较远的实体直接跳过。
+#[allow(clippy::too_many_arguments)]
+pub fn snap_system(
+    settings: Res<SnapSettings>,
+    mut target: ResMut<SnapTarget>,
+    mut draw: ResMut<DrawState>,
+    cache: Res<SnapCache>,
+    layers: Res<LayerTable>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<PanOrbitCamera>>,
+    cad_query: Query<(Entity, &CadEntity)>,
+) {
+    let cursor = window.and_then(|window| window.cursor_position());
+    let camera = camera_query.iter().next();
+    let (Some(cursor), Some((camera, camera_transform))) = (cursor, camera) else {
+        target.snap = None;
+        return;
+    };
+    if !draw.is_active() || !settings.enabled {
+        target.snap = None;
+        return;
+    }
+    let project = |point: Vec3| camera.world_to_viewport(camera_transform, point).ok();
+    let base = draw.points.last().copied();
+
+    let mut finder = SnapFinder {
+        settings: &settings,
+        cursor,
+        project: &project,
+        best: None,
+    };
+    // 光标附近的线段，用于求交点：（实体序号，起点，终点）
+    let mut near_segments = Vec::new();
+    let need_outline =
+        settings.is_active(SnapMode::Intersection) || settings.is_active(SnapMode::Nearest);
+    let visible = cad_query
+        .iter()
+        .filter(|(_, cad)| layers.is_visible(&cad.layer))
+        .filter_map(|(entity, _)| cache.entities.get(&entity))
+        .filter(|shape| shape.near(cursor, &project));
+    for (index, shape) in visible.enumerate() {
+        let features = &shape.features;
+        for &point in &features.endpoints {
+            finder.consider(point, SnapMode::Endpoint);
+        }
+        for &point in &features.midpoints {
+            finder.consider(point, SnapMode::Midpoint);
+        }
+        for &point in &features.centers {
+            finder.consider(point, SnapMode::Center);
+        }
+        if let Some(base) = base {
+            for &(start, end) in &features.segments {
+                if let Some(foot) = perpendicular_foot(base, start, end) {
+                    finder.consider(foot, SnapMode::Perpendicular);
+                }
+            }
+            for circle in &features.circles {
+                // 圆上离基点最近和最远的点都是垂足
+                let direction = Vec3::new(base.x - circle.center.x, 0.0, base.z - circle.center.z);
+                if let Some(direction) = direction.try_normalize() {
+                    for point in [
+                        circle.center + direction * circle.radius,
+                        circle.center - direction * circle.radius,
+                    ] {
+                        if circle.contains(point) {
+                            finder.consider(point, SnapMode::Perpendicular);
+                        }
+                    }
+                }
+                for point in tangent_points(base, circle.center, circle.radius) {
+                    if circle.contains(point) {
+                        finder.consider(point, SnapMode::Tangent);
+                    }
+                }
+            }
+        }
+        // 交点和最近点按离散后的折线计算，误差在弦高误差以内
+        if !need_outline {
+            continue;
+        }
+        for strip in &shape.outline {
+            for pair in strip.windows(2) {
+                let (Some(a), Some(b)) = (project(pair[0]), project(pair[1])) else {
+                    continue;
+                };
+                let t = closest_param(cursor, a, b);
+                if a.lerp(b, t).distance(cursor) > APERTURE {
+                    continue;
+                }
+                finder.consider(pair[0].lerp(pair[1], t), SnapMode::Nearest);
+                near_segments.push((index, pair[0], pair[1]));
+            }
+        }
+    }
+    if settings.is_active(SnapMode::Intersection) {
+        for (i, &(entity_a, a0, a1)) in near_segments.iter().enumerate() {
+            for &(entity_b, b0, b1) in &near_segments[i + 1..] {
+                // 同一图形上相邻的线段交于公共顶点，不算交点
+                if entity_a == entity_b && (a0 == b0 || a0 == b1 || a1 == b0 || a1 == b1) {
+                    continue;
+                }
+                if let Some(point) = segment_intersection(a0, a1, b0, b1) {
+                    finder.consider(point, SnapMode::Intersection);
+                }
+            }
+        }
+    }
+
+    target.snap = finder.best.map(|(_, snap)| snap);
+    if let Some(snap) = target.snap {
+        draw.cursor = Some(snap.point);
+    }
+}
+
+/// 对象捕捉界面系统 - 画出捕捉标记和提示，F3 开关对象捕捉，显示设置面板
+pub fn snap_ui_system(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<SnapSettings>,
+    target: Res<SnapTarget>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::F3)) {
+        settings.enabled = !settings.enabled;
+    }
+
+    if let Some(snap) = target.snap {
+        let painter = ctx.layer_painter(LayerId::background());
+        let center = Pos2::new(snap.screen.x, snap.screen.y);
+        for shape in marker(snap.mode, center) {
+            painter.add(shape);
+        }
+        // 提示文字在标记的右下方
+        let galley = painter.layout_no_wrap(
+            snap.mode.name().to_string(),
+            FontId::proportional(12.0),
+            Color32::WHITE,
+        );
+        let position = center + egui::vec2(MARKER_SIZE + 6.0, MARKER_SIZE + 6.0);
+        let rect = egui::Rect::from_min_size(position, galley.size()).expand(3.0);
+        painter.rect_filled(rect, CornerRadius::same(3), Color32::from_black_alpha(200));
+        painter.galley(position, galley, Color32::WHITE);
+    }
+
+    let mut open = settings.panel_open;
+    egui::Window::new("对象捕捉")
+        .open(&mut open)
+        .resizable(false)
+        .default_pos(ctx.content_rect().right_bottom() - egui::vec2(240.0, 300.0))
+        .show(ctx, |ui| {
+            ui.checkbox(&mut settings.enabled, "启用对象捕捉 (F3)");
+            ui.separator();
+            ui.add_enabled_ui(settings.enabled, |ui| {
+                for mode in SnapMode::ALL {
+                    let mut active = settings.modes.contains(&mode);
+                    if ui.checkbox(&mut active, mode.name()).changed() {
+                        settings.set(mode, active);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("全选").clicked() {
+                        settings.modes = SnapMode::ALL.to_vec();
+                    }
+                    if ui.button("全部清除").clicked() {
+                        settings.modes.clear();
+                    }
+                });
+            });
+        });
+    settings.panel_open = open;
+    Ok(())
+}
+
+/// 在捕捉范围内的候选点中挑选：特征点优先于最近点，同类中取离光标最近的
+struct SnapFinder<'a, F: Fn(Vec3) -> Option<Vec2>> {
+    settings: &'a SnapSettings,
+    cursor: Vec2,
+    project: &'a F,
+    /// （是否为最近点，到光标的距离），捕捉到的点
+    best: Option<((bool, f32), Snap)>,
+}
+
+impl<F: Fn(Vec3) -> Option<Vec2>> SnapFinder<'_, F> {
+    fn consider(&mut self, point: Vec3, mode: SnapMode) {
+        if !self.settings.is_active(mode) {
+            return;
+        }
+        let Some(screen) = (self.project)(point) else {
+            return;
+        };
+        let distance = screen.distance(self.cursor);
+        if distance > APERTURE {
+            return;
+        }
+        let key = (mode == SnapMode::Nearest, distance);
+        if self.best.as_ref().is_none_or(|(best, _)| key < *best) {
+            self.best = Some((
+                key,
+                Snap {
+                    point,
+                    mode,
+                    screen,
+                },
+            ));
+        }
+    }
+}
+
+/// 圆或圆弧，圆弧的范围为起始角和逆时针的圆心角
+struct SnapCircle {
+    center: Vec3,
+    radius: f32,
+    range: Option<(f32, f32)>,
+}
+
+impl SnapCircle {
+    /// 圆上的点是否在圆弧的范围内
+    fn contains(&self, point: Vec3) -> bool {
+        self.range.is_none_or(|(start, sweep)| {
+            let angle = (point.z - self.center.z).atan2(point.x - self.center.x);
+            (angle - start).rem_euclid(TAU) <= sweep + 1e-4
+        })
+    }
+}
+
+/// 图形上可以捕捉的特征
+#[derive(Default)]
+struct Features {
+    endpoints: Vec<Vec3>,
+    midpoints: Vec<Vec3>,
+    centers: Vec<Vec3>,
+    /// 直线段，用于求垂足
+    segments: Vec<(Vec3, Vec3)>,
+    /// 圆和圆弧，用于求垂足和切点
+    circles: Vec<SnapCircle>,
+}
+
+impl Features {
+    fn of(geometry: &CadGeometryQueryItem, tolerance: &CurveTolerance) -> Self {
+        let mut features = Self::default();
+        if let Some(line) = geometry.line {
+            features.endpoints.extend([line.start, line.end]);
+            features.midpoints.push((line.start + line.end) * 0.5);
+            features.segments.push((line.start, line.end));
+        }
+        if let Some(circle) = geometry.circle {
+            features.centers.push(circle.center);
+            features.circles.push(SnapCircle {
+                center: circle.center,
+                radius: circle.radius,
+                range: None,
+            });
+        }
+        if let Some(arc) = geometry.arc {
+            // 与绘制时一致：终止角不大于起始角时加一周
+            let mut sweep = arc.end_angle - arc.start_angle;
+            if sweep <= 0.0 {
+                sweep += TAU;
+            }
+            let segment = PolylineSegment::Arc {
+                center: arc.center,
+                radius: arc.radius,
+                start_angle: arc.start_angle,
+                sweep,
+            };
+            features.add_segment(segment);
+            features
+                .endpoints
+                .extend([segment.point_at(0.0), segment.point_at(1.0)]);
+        }
+        if let Some(pl) = geometry.polyline {
+            features.endpoints.extend(&pl.vertices);
+            for segment in pl.segments() {
+                features.add_segment(segment);
+            }
+        }
+        if let Some(ellipse) = geometry.ellipse {
+            features.centers.push(ellipse.center);
+            if (ellipse.end_param - ellipse.start_param).abs() < TAU - 1e-4 {
+                let points = ellipse.tessellate(tolerance);
+                features
+                    .endpoints
+                    .extend(points.first().into_iter().chain(points.last()));
+            }
+        }
+        if let Some(spline) = geometry.spline.filter(|spline| !spline.closed) {
+            let points = spline.tessellate(tolerance);
+            features
+                .endpoints
+                .extend(points.first().into_iter().chain(points.last()));
+        }
+        features
+    }
+
+    /// 多段线的一段（圆弧也按圆弧段处理）的中点、圆心和用于垂足、切点的几何
+    fn add_segment(&mut self, segment: PolylineSegment) {
+        self.midpoints.push(segment.point_at(0.5));
+        match segment {
+            PolylineSegment::Line { start, end } => self.segments.push((start, end)),
+            PolylineSegment::Arc {
+                center,
+                radius,
+                start_angle,
+                sweep,
+            } => {
+                self.centers.push(center);
+                // 顺时针的圆弧段换成从终点开始的逆时针范围
+                let range = if sweep < 0.0 {
+                    (start_angle + sweep, -sweep)
+                } else {
+                    (start_angle, sweep)
+                };
+                self.circles.push(SnapCircle {
+                    center,
+                    radius,
+                    range: Some(range),
+                });
+            }
+        }
+    }
+}
+
+/// 点到线段所在直线的垂足，垂足不在线段上时返回 None
+fn perpendicular_foot(point: Vec3, start: Vec3, end: Vec3) -> Option<Vec3> {
+    let direction = end - start;
+    let length_squared = direction.length_squared();
+    if length_squared == 0.0 {
+        return None;
+    }
+    let t = (point - start).dot(direction) / length_squared;
+    (0.0..=1.0).contains(&t).then(|| start + direction * t)
+}
+
+/// 从点到圆的两个切点（DXF XY 平面内），点在圆内时没有切点
+fn tangent_points(point: Vec3, center: Vec3, radius: f32) -> Vec<Vec3> {
+    let offset = Vec2::new(point.x - center.x, point.z - center.z);
+    let distance = offset.length();
+    if distance <= radius {
+        return Vec::new();
+    }
+    let angle = (radius / distance).acos();
+    let direction = offset / distance;
+    [angle, -angle]
+        .into_iter()
+        .map(|angle| {
+            let v = Vec2::from_angle(angle).rotate(direction) * radius;
+            center + Vec3::new(v.x, 0.0, v.y)
+        })
+        .collect()
+}
+
+/// 视口中线段 ab 上离 point 最近的点的参数
+fn closest_param(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// 两条线段在 DXF XY 平面内的交点，高度取第一条线段上的值
+fn segment_intersection(a0: Vec3, a1: Vec3, b0: Vec3, b1: Vec3) -> Option<Vec3> {
+    let p = Vec2::new(a0.x, a0.z);
+    let r = Vec2::new(a1.x - a0.x, a1.z - a0.z);
+    let q = Vec2::new(b0.x, b0.z);
+    let s = Vec2::new(b1.x - b0.x, b1.z - b0.z);
+    let denominator = r.perp_dot(s);
+    // 平行或重合
+    if denominator.abs() <= f32::EPSILON * r.length() * s.length() {
+        return None;
+    }
+    let t = (q - p).perp_dot(s) / denominator;
+    let u = (q - p).perp_dot(r) / denominator;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| a0.lerp(a1, t))
+}
+
+/// 捕捉标记的形状：端点为方框，中点为三角形，圆心为圆，交点为叉，垂足为直角，切点为圆加切线，
+/// 最近点为沙漏
+fn marker(mode: SnapMode, center: Pos2) -> Vec<Shape> {
+    let s = MARKER_SIZE;
+    let stroke = Stroke::new(2.0, MARKER_COLOR);
+    let p = |x: f32, y: f32| center + egui::vec2(x * s, y * s);
+    match mode {
+        SnapMode::Endpoint => vec![Shape::closed_line(
+            vec![p(-1.0, -1.0), p(1.0, -1.0), p(1.0, 1.0), p(-1.0, 1.0)],
+            stroke,
+        )],
+        SnapMode::Midpoint => vec![Shape::closed_line(
+            vec![p(0.0, -1.0), p(1.0, 1.0), p(-1.0, 1.0)],
+            stroke,
+        )],
+        SnapMode::Center => vec![Shape::circle_stroke(center, s, stroke)],
+        SnapMode::Intersection => vec![
+            Shape::line_segment([p(-1.0, -1.0), p(1.0, 1.0)], stroke),
+            Shape::line_segment([p(-1.0, 1.0), p(1.0, -1.0)], stroke),
+        ],
+        SnapMode::Perpendicular => vec![
+            Shape::line(vec![p(-1.0, -1.0), p(-1.0, 1.0), p(1.0, 1.0)], stroke),
+            Shape::line(vec![p(-1.0, 0.0), p(0.0, 0.0), p(0.0, 1.0)], stroke),
+        ],
+        SnapMode::Tangent => vec![
+            Shape::circle_stroke(center, s * 0.7, stroke),
+            Shape::line_segment([p(-1.0, -0.7), p(1.0, -0.7)], stroke),
+        ],
+        SnapMode::Nearest => vec![Shape::closed_line(
+            vec![p(-1.0, -1.0), p(1.0, -1.0), p(-1.0, 1.0), p(1.0, 1.0)],
+            stroke,
+        )],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn close(a: Vec3, b: Vec3) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    #[test]
+    fn perpendicular_foot_on_and_off_segment() {
+        let (start, end) = (Vec3::ZERO, Vec3::new(4.0, 0.0, 0.0));
+        let foot = perpendicular_foot(Vec3::new(1.0, 0.0, 3.0), start, end).unwrap();
+        assert!(close(foot, Vec3::new(1.0, 0.0, 0.0)));
+        // 垂足落在线段的延长线上
+        assert!(perpendicular_foot(Vec3::new(5.0, 0.0, 3.0), start, end).is_none());
+        assert!(perpendicular_foot(Vec3::new(-1.0, 0.0, 3.0), start, end).is_none());
+        assert!(perpendicular_foot(Vec3::ONE, start, start).is_none());
+    }
+
+    #[test]
+    fn tangent_points_from_outside_and_inside() {
+        let center = Vec3::new(1.0, 0.0, 1.0);
+        // 点在圆内或圆上时没有切点
+        assert!(tangent_points(Vec3::new(1.5, 0.0, 1.0), center, 2.0).is_empty());
+        assert!(tangent_points(Vec3::new(3.0, 0.0, 1.0), center, 2.0).is_empty());
+
+        let point = Vec3::new(5.0, 0.0, 1.0);
+        let tangents = tangent_points(point, center, 2.0);
+        assert_eq!(tangents.len(), 2);
+        for tangent in tangents {
+            assert!((tangent.distance(center) - 2.0).abs() < 1e-4);
+            // 切线垂直于半径
+            assert!((tangent - center).dot(point - tangent).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn segment_intersection_cases() {
+        let (a0, a1) = (Vec3::ZERO, Vec3::new(2.0, 0.0, 2.0));
+        let crossing =
+            segment_intersection(a0, a1, Vec3::new(0.0, 0.0, 2.0), Vec3::new(2.0, 0.0, 0.0));
+        assert!(close(crossing.unwrap(), Vec3::new(1.0, 0.0, 1.0)));
+        // 平行、重合和不相交的线段
+        let offset = Vec3::new(1.0, 0.0, 0.0);
+        assert!(segment_intersection(a0, a1, a0 + offset, a1 + offset).is_none());
+        assert!(segment_intersection(a0, a1, a0, a1).is_none());
+        assert!(
+            segment_intersection(a0, a1, Vec3::new(3.0, 0.0, 0.0), Vec3::new(4.0, 0.0, -1.0))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn arc_range_wraps_past_zero() {
+        // 从 270° 逆时针到 90°，跨过 0°
+        let arc = SnapCircle {
+            center: Vec3::ZERO,
+            radius: 1.0,
+            range: Some((3.0 * FRAC_PI_2, PI)),
+        };
+        let at = |angle: f32| Vec3::new(angle.cos(), 0.0, angle.sin());
+        assert!(arc.contains(at(0.0)));
+        assert!(arc.contains(at(1.4)));
+        assert!(arc.contains(at(-1.4)));
+        assert!(!arc.contains(at(PI)));
+        assert!(!arc.contains(at(2.0)));
+
+        let circle = SnapCircle { range: None, ..arc };
+        assert!(circle.contains(at(PI)));
+    }
+
+    #[test]
+    fn feature_snaps_beat_nearest() {
+        let settings = SnapSettings {
+            modes: SnapMode::ALL.to_vec(),
+            ..Default::default()
+        };
+        let project = |point: Vec3| Some(Vec2::new(point.x, point.z));
+        let mut finder = SnapFinder {
+            settings: &settings,
+            cursor: Vec2::ZERO,
+            project: &project,
+            best: None,
+        };
+        finder.consider(Vec3::new(1.0, 0.0, 0.0), SnapMode::Nearest);
+        finder.consider(Vec3::new(6.0, 0.0, 0.0), SnapMode::Endpoint);
+        finder.consider(Vec3::new(4.0, 0.0, 0.0), SnapMode::Midpoint);
+        // 超出捕捉范围
+        finder.consider(Vec3::new(APERTURE + 1.0, 0.0, 0.0), SnapMode::Center);
+        let (_, snap) = finder.best.unwrap();
+        assert_eq!(snap.mode, SnapMode::Midpoint);
+        assert!(close(snap.point, Vec3::new(4.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn inactive_modes_are_ignored() {
+        let settings = SnapSettings {
+            modes: vec![SnapMode::Endpoint],
+            ..Default::default()
+        };
+        let project = |point: Vec3| Some(Vec2::new(point.x, point.z));
+        let mut finder = SnapFinder {
+            settings: &settings,
+            cursor: Vec2::ZERO,
+            project: &project,
+            best: None,
+        };
+        finder.consider(Vec3::new(1.0, 0.0, 0.0), SnapMode::Midpoint);
+        assert!(finder.best.is_none());
+        finder.consider(Vec3::new(2.0, 0.0, 0.0), SnapMode::Endpoint);
+        assert_eq!(
+            finder.best.map(|(_, snap)| snap.mode),
+            Some(SnapMode::Endpoint)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy::window::{ClosingWindow, WindowCloseRequested};
+use bevy_egui::*;
+use egui::*;
+use std::path::PathBuf;
+
+use super::{LoadDxfMessage, Project};
+use crate::editor::{Editor, OpenSceneMessage, SaveDialog, SaveSceneMessage};
+use crate::state::AppState;
+
+/// 会丢弃当前编辑内容、需要先确认的操作
+#[derive(Debug, Clone)]
+pub enum GuardedAction {
+    /// 退出项目回到主菜单
+    ExitProject,
+    /// 加载另一个 DXF
+    LoadDxf(PathBuf),
+    /// 打开另一个场景
+    OpenScene(PathBuf),
+    /// 关闭窗口
+    CloseWindow(Entity),
+}
+
+/// 未保存更改检查
+#[derive(Resource, Default)]
+pub struct UnsavedGuard {
+    pending: Option<GuardedAction>,
+    /// 用户选择了保存，等待保存完成后再执行
+    awaiting_save: bool,
+}
+
+impl UnsavedGuard {
+    /// 请求执行操作；编辑器有未保存的更改时先弹窗确认
+    pub fn request(&mut self, action: GuardedAction) {
+        // 等待确认期间的新请求替换旧请求，但关闭窗口优先
+        if matches!(self.pending, Some(GuardedAction::CloseWindow(_))) {
+            return;
+        }
+        self.pending = Some(action);
+        self.awaiting_save = false;
+    }
+
+    fn clear(&mut self) {
+        self.pending = None;
+        self.awaiting_save = false;
+    }
+}
+
+/// 窗口关闭请求系统 - 关闭窗口也要经过未保存检查
+pub fn window_close_request_system(
+    mut commands: Commands,
+    mut close_requests: MessageReader<WindowCloseRequested>,
+    closing: Query<Entity, With<ClosingWindow>>,
+    mut guard: ResMut<UnsavedGuard>,
+) {
+    // 上一帧标记为关闭的窗口，这一帧销毁（与 bevy 默认的 close_when_requested 一致）
+    for window in &closing {
+        commands.entity(window).despawn();
+    }
+    for request in close_requests.read() {
+        guard.request(GuardedAction::CloseWindow(request.window));
+    }
+}
+
+/// 未保存更改确认系统 - 显示 保存/不保存/取消 对话框并执行操作
+#[allow(clippy::too_many_arguments)]
+pub fn unsaved_guard_system(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut guard: ResMut<UnsavedGuard>,
+    mut save_dialog: ResMut<SaveDialog>,
+    editor_query: Query<&Editor>,
+    project: Option<Res<Project>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut dxf_messages: MessageWriter<LoadDxfMessage>,
+    mut scene_messages: MessageWriter<OpenSceneMessage>,
+    mut save_messages: MessageWriter<SaveSceneMessage>,
+) -> Result {
+    let Some(action) = guard.pending.clone() else {
+        return Ok(());
+    };
+    let editor = editor_query.iter().next();
+
+    // 没有未保存的更改（或已保存完成）：直接执行
+    if !editor.is_some_and(|editor| editor.is_dirty) {
+        guard.clear();
+        run_action(
+            action,
+            &mut commands,
+            &mut next_state,
+            &mut dxf_messages,
+            &mut scene_messages,
+        );
+        return Ok(());
+    }
+
+    // 另存为对话框被取消，回到确认对话框
+    if guard.awaiting_save && save_dialog.take_cancelled() {
+        guard.awaiting_save = false;
+    }
+
+    let message = match &action {
+        GuardedAction::ExitProject => "退出项目前是否保存更改？".to_string(),
+        GuardedAction::LoadDxf(path) | GuardedAction::OpenScene(path) => {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("未知文件");
+            format!("打开 {} 前是否保存更改？", name)
+        }
+        GuardedAction::CloseWindow(_) => "关闭窗口前是否保存更改？".to_string(),
+    };
+
+    let mut save = false;
+    let mut discard = false;
+    let mut cancel = false;
+
+    let ctx = contexts.ctx_mut()?;
+    Modal::new(Id::new("unsaved_guard")).show(ctx, |ui| {
+        ui.set_width(280.0);
+        ui.heading("未保存的更改");
+        ui.add_space(8.0);
+        ui.label(message);
+        ui.add_space(12.0);
+
+        if guard.awaiting_save {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("正在保存...");
+            });
+            ui.add_space(8.0);
+            if ui.button("取消").clicked() {
+                cancel = true;
+            }
+        } else {
+            ui.horizontal(|ui| {
+                if ui.button("💾 保存").clicked() {
+                    save = true;
+                }
+                if ui.button("不保存").clicked() {
+                    discard = true;
+                }
+                if ui.button("取消").clicked() {
+                    cancel = true;
+                }
+            });
+        }
+    });
+
+    if save {
+        guard.awaiting_save = true;
+        match editor.and_then(|editor| editor.path.clone()) {
+            Some(path) => {
+                save_messages.write(SaveSceneMessage { path });
+            }
+            None => {
+                let directory = project
+                    .map(|project| project.path.clone())
+                    .unwrap_or_default();
+                save_dialog.open(directory);
+            }
+        }
+    }
+    if discard {
+        guard.clear();
+        run_action(
+            action,
+            &mut commands,
+            &mut next_state,
+            &mut dxf_messages,
+            &mut scene_messages,
+        );
+    }
+    if cancel {
+        guard.clear();
+    }
+
+    Ok(())
+}
+
+/// 执行确认后的操作
+fn run_action(
+    action: GuardedAction,
+    commands: &mut Commands,
+    next_state: &mut NextState<AppState>,
+    dxf_messages: &mut MessageWriter<LoadDxfMessage>,
+    scene_messages: &mut MessageWriter<OpenSceneMessage>,
+) {
+    match action {
+        GuardedAction::ExitProject => {
+            next_state.set(AppState::MainMenu);
+        }
+        GuardedAction::LoadDxf(path) => {
+            dxf_messages.write(LoadDxfMessage { path });
+        }
+        GuardedAction::OpenScene(path) => {
+            scene_messages.write(OpenSceneMessage { path });
+        }
+        GuardedAction::CloseWindow(window) => {
+            commands.entity(window).try_insert(ClosingWindow);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::camera::ScalingMode;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::EguiContexts;
+use bevy_panorbit_camera::PanOrbitCamera;
+
+/// 2D 视图正交投影的近、远裁剪面到相机的距离，图纸在焦点上下这个范围内都能看到
+const PLAN_DEPTH: f32 = 1.0e6;
+/// 滚轮每滚一行的缩放比例
+const ZOOM_STEP: f32 = 0.1;
+/// 按像素滚动（触摸板）时，这么多像素相当于滚一行
+const PIXELS_PER_LINE: f32 = 100.0;
+/// 焦点距离的范围
+const MIN_RADIUS: f32 = 1.0e-4;
+const MAX_RADIUS: f32 = 1.0e7;
+/// 缩放到范围时四周留出的余量
+const FRAME_MARGIN: f32 = 1.1;
+
+/// 视图模式
+///
+/// 2D 模式下用正交投影从上往下看 XZ 平面，只能平移和缩放。两种模式共用 PanOrbitCamera 的
+/// 焦点和焦点距离，正交视图的高度取透视视图在焦点处看到的高度，切换时画面范围不变。
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    /// 透视投影，可绕焦点旋转
+    #[default]
+    Orbit,
+    /// 正交投影的俯视图
+    Plan,
+}
+
+/// 视图模式系统 - 按视图模式切换相机的投影
+///
+/// 每帧检查，新建的编辑器相机也会按当前模式设置投影。
+pub fn view_mode_system(
+    view_mode: Res<ViewMode>,
+    mut camera_query: Query<(&mut PanOrbitCamera, &mut Projection)>,
+) {
+    for (mut orbit, mut projection) in &mut camera_query {
+        match (*view_mode, &*projection) {
+            (ViewMode::Plan, Projection::Perspective(_)) => {
+                *projection = Projection::Orthographic(OrthographicProjection {
+                    near: -PLAN_DEPTH,
+                    far: PLAN_DEPTH,
+                    scaling_mode: ScalingMode::FixedVertical {
+                        viewport_height: plan_height(radius(&orbit)),
+                    },
+                    ..OrthographicProjection::default_3d()
+                });
+            }
+            (ViewMode::Orbit, Projection::Orthographic(_)) => {
+                *projection = Projection::Perspective(PerspectiveProjection::default());
+                // 按 2D 视图中修改过的焦点和距离重新计算相机位置，旋转角度不变
+                orbit.force_update = true;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 2D 视图系统 - 中键拖动平移，滚轮以光标为中心缩放
+///
+/// 2D 模式下 PanOrbitCamera 不处理输入，相机位置由这里根据焦点和距离计算。
+pub fn plan_view_system(
+    mut contexts: EguiContexts,
+    view_mode: Res<ViewMode>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    mut camera_query: Query<(
+        &Camera,
+        &GlobalTransform,
+        &mut PanOrbitCamera,
+        &mut Projection,
+        &mut Transform,
+    )>,
+) -> Result {
+    if *view_mode != ViewMode::Plan {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    let ui_focused = ctx.is_pointer_over_area() || ctx.wants_pointer_input();
+    let cursor = window.and_then(|window| window.cursor_position());
+
+    for (camera, global_transform, mut orbit, mut projection, mut transform) in &mut camera_query {
+        let mut focus = orbit.focus;
+        let mut radius = radius(&orbit);
+
+        if !ui_focused {
+            // 每个像素对应的世界长度
+            let pixel = camera
+                .logical_viewport_size()
+                .map_or(0.0, |size| plan_height(radius) / size.y);
+            // 屏幕向右为 +X，向下为 +Z
+            if buttons.pressed(MouseButton::Middle) {
+                focus -= Vec3::new(motion.delta.x, 0.0, motion.delta.y) * pixel;
+            }
+
+            let lines = match scroll.unit {
+                MouseScrollUnit::Line => scroll.delta.y,
+                MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_LINE,
+            };
+            if lines != 0.0 {
+                let zoomed = (radius * (1.0 - ZOOM_STEP).powf(lines)).clamp(MIN_RADIUS, MAX_RADIUS);
+                // 光标下的点在缩放前后保持不动
+                if let Some(ray) = cursor
+                    .and_then(|cursor| camera.viewport_to_world(global_transform, cursor).ok())
+                {
+                    let anchor = Vec3::new(ray.origin.x, focus.y, ray.origin.z);
+                    focus = anchor + (focus - anchor) * (zoomed / radius);
+                }
+                radius = zoomed;
+            }
+        }
+
+        // 焦点和目标一起修改，PanOrbitCamera 不会再把相机移回去
+        if orbit.focus != focus {
+            orbit.focus = focus;
+            orbit.target_focus = focus;
+        }
+        if orbit.radius != Some(radius) {
+            orbit.radius = Some(radius);
+            orbit.target_radius = radius;
+        }
+        // 只在高度变化时修改投影，避免每帧触发投影的变化检测
+        let height = plan_height(radius);
+        match projection.bypass_change_detection() {
+            Projection::Orthographic(orthographic)
+                if !matches!(
+                    orthographic.scaling_mode,
+                    ScalingMode::FixedVertical { viewport_height } if viewport_height == height
+                ) =>
+            {
+                orthographic.scaling_mode = ScalingMode::FixedVertical {
+                    viewport_height: height,
+                };
+                projection.set_changed();
+            }
+            _ => {}
+        }
+        // 与 PanOrbitCamera 俯仰角为 90° 时的朝向一致：屏幕上方为 -Z
+        let plan = Transform::from_translation(focus + Vec3::Y * radius)
+            .looking_to(Vec3::NEG_Y, Vec3::NEG_Z);
+        transform.set_if_neq(plan);
+    }
+    Ok(())
+}
+
+fn radius(orbit: &PanOrbitCamera) -> f32 {
+    orbit.radius.unwrap_or(orbit.target_radius)
+}
+
+/// 相机要去的焦点和焦点距离（3D 视图平滑移动时取目标值）
+pub(super) fn camera_target(orbit: &PanOrbitCamera) -> (Vec3, f32) {
+    (orbit.target_focus, orbit.target_radius)
+}
+
+/// 移动相机到焦点和焦点距离
+///
+/// 3D 视图只修改目标值，由 PanOrbitCamera 平滑移动过去；2D 视图立即跳转。
+pub(super) fn move_camera(
+    orbit: &mut PanOrbitCamera,
+    view_mode: ViewMode,
+    focus: Vec3,
+    radius: f32,
+) {
+    let radius = radius.clamp(MIN_RADIUS, MAX_RADIUS);
+    orbit.target_focus = focus;
+    orbit.target_radius = radius;
+    if view_mode == ViewMode::Plan {
+        orbit.focus = focus;
+        orbit.radius = Some(radius);
+    }
+}
+
+/// 移动相机让包围盒 [min, max] 充满视口，aspect 为视口的宽高比
+///
+/// 2D 视图只看包围盒在 XZ 平面内的范围，3D 视图让包围球落在视野内。包围盒只是一个点时不改变距离。
+pub(super) fn frame_box(
+    orbit: &mut PanOrbitCamera,
+    view_mode: ViewMode,
+    aspect: f32,
+    min: Vec3,
+    max: Vec3,
+) {
+    let size = max - min;
+    let half_fov = PerspectiveProjection::default().fov * 0.5;
+    let radius = match view_mode {
+        ViewMode::Plan => size.z.max(size.x / aspect) * FRAME_MARGIN / (2.0 * half_fov.tan()),
+        ViewMode::Orbit => {
+            // 横向视野较窄时按横向计算
+            let half_fov = half_fov.min((half_fov.tan() * aspect).atan());
+            size.length() * 0.5 * FRAME_MARGIN / half_fov.sin()
+        }
+    };
+    let radius = if radius > MIN_RADIUS {
+        radius
+    } else {
+        camera_target(orbit).1
+    };
+    move_camera(orbit, view_mode, (min + max) * 0.5, radius);
+}
+
+/// 焦点距离为 radius 时透视视图在焦点处看到的高度，也就是 2D 视图的高度
+fn plan_height(radius: f32) -> f32 {
+    2.0 * radius * (PerspectiveProjection::default().fov * 0.5).tan()
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_egui::{EguiContexts, egui};
+use bevy_panorbit_camera::PanOrbitCamera;
+use egui::{Color32, CornerRadius, LayerId, Pos2, Rect, Stroke, StrokeKind};
+
+use super::dxf_renderer::{CadEntity, CadGeometryQuery, CadGeometryQueryItem};
+use super::dxf_tessellation::CurveTolerance;
+use super::layer_panel::LayerTable;
+use super::selection::Selected;
+use super::view_mode::{ViewMode, camera_target, frame_box, move_camera};
+
+/// 上一个视图最多记录的个数
+const MAX_PREVIOUS_VIEWS: usize = 20;
+/// 拖动距离小于这个值（像素）时不算窗口
+const MIN_WINDOW_SIZE: f32 = 4.0;
+
+/// 缩放命令消息
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomMessage {
+    /// 缩放到所有可见实体的范围
+    Extents,
+    /// 拖出一个窗口，缩放到窗口的范围
+    Window,
+    /// 回到上一个视图
+    Previous,
+    /// 缩放到选中实体的范围
+    Selected,
+}
+
+/// 窗口缩放的状态
+#[derive(Resource, Debug, Default)]
+pub struct ZoomWindow {
+    /// 正在等待拖出窗口
+    pub active: bool,
+    /// 按下左键的位置（视口坐标）
+    pub start: Option<Vec2>,
+    /// 当前光标位置（视口坐标）
+    pub end: Option<Vec2>,
+}
+
+/// 缩放系统 - 处理缩放命令，按 CAD 实体的包围盒移动相机
+#[allow(clippy::too_many_arguments)]
+pub fn zoom_system(
+    mut messages: MessageReader<ZoomMessage>,
+    mut contexts: EguiContexts,
+    mut zoom_window: ResMut<ZoomWindow>,
+    mut previous: Local<Vec<(Vec3, f32)>>,
+    view_mode: Res<ViewMode>,
+    tolerance: Res<CurveTolerance>,
+    layers: Res<LayerTable>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    window: Option<Single<&Window, With<PrimaryWindow>>>,
+    mut camera_query: Query<(&Camera, &GlobalTransform, &mut PanOrbitCamera)>,
+    cad_query: Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+    selected_query: Query<Entity, With<Selected>>,
+) -> Result {
+    let Some((camera, global_transform, mut orbit)) = camera_query.iter_mut().next() else {
+        messages.clear();
+        return Ok(());
+    };
+    let aspect = camera
+        .logical_viewport_size()
+        .filter(|size| size.y > 0.0)
+        .map_or(1.0, |size| size.x / size.y);
+
+    // 只处理最后一条命令
+    let mut target = None;
+    if let Some(message) = messages.read().last() {
+        zoom_window.active = false;
+        match message {
+            ZoomMessage::Extents => {
+                target = bounds(cad_query.iter().filter_map(|(cad, geometry, _)| {
+                    layers
+                        .is_visible(&cad.layer)
+                        .then(|| geometry_bounds(&geometry, &tolerance))
+                        .flatten()
+                }));
+                if target.is_none() {
+                    println!("没有可以缩放到的实体");
+                }
+            }
+            ZoomMessage::Selected => {
+                // 块参照等实体的图形在子实体中
+                let mut boxes = Vec::new();
+                for entity in &selected_query {
+                    collect_bounds(entity, &cad_query, &tolerance, &mut boxes);
+                }
+                target = bounds(boxes);
+                if target.is_none() {
+                    println!("没有选中的实体");
+                }
+            }
+            ZoomMessage::Previous => {
+                if let Some((focus, radius)) = previous.pop() {
+                    move_camera(&mut orbit, *view_mode, focus, radius);
+                }
+                return Ok(());
+            }
+            ZoomMessage::Window => {
+                zoom_window.active = true;
+                zoom_window.start = None;
+                zoom_window.end = None;
+            }
+        }
+    }
+
+    if zoom_window.active {
+        let ctx = contexts.ctx_mut()?;
+        let cursor = window.and_then(|window| window.cursor_position());
+        if keys.just_pressed(KeyCode::Escape) {
+            *zoom_window = ZoomWindow::default();
+        } else if buttons.just_pressed(MouseButton::Left) && !ctx.is_pointer_over_area() {
+            zoom_window.start = cursor;
+            zoom_window.end = cursor;
+        } else if let Some(start) = zoom_window.start {
+            if cursor.is_some() {
+                zoom_window.end = cursor;
+            }
+            if buttons.just_released(MouseButton::Left) {
+                let end = zoom_window.end.unwrap_or(start);
+                *zoom_window = ZoomWindow::default();
+                if (end - start).abs().max_element() >= MIN_WINDOW_SIZE {
+                    // 窗口的角投到焦点所在的水平面上
+                    let corners: Vec<Vec3> = [start, end]
+                        .into_iter()
+                        .filter_map(|corner| {
+                            let ray = camera.viewport_to_world(global_transform, corner).ok()?;
+                            let plane = InfinitePlane3d::new(Vec3::Y);
+                            let distance = ray.intersect_plane(Vec3::Y * orbit.focus.y, plane)?;
+                            Some(ray.get_point(distance))
+                        })
+                        .collect();
+                    if let [a, b] = corners[..] {
+                        target = Some((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some((min, max)) = target {
+        previous.push(camera_target(&orbit));
+        if previous.len() > MAX_PREVIOUS_VIEWS {
+            previous.remove(0);
+        }
+        frame_box(&mut orbit, *view_mode, aspect, min, max);
+    }
+    Ok(())
+}
+
+/// 窗口缩放界面系统 - 画出正在拖动的窗口
+pub fn zoom_window_ui_system(mut contexts: EguiContexts, zoom_window: Res<ZoomWindow>) -> Result {
+    let (Some(start), Some(end)) = (zoom_window.start, zoom_window.end) else {
+        return Ok(());
+    };
+    let ctx = contexts.ctx_mut()?;
+    let painter = ctx.layer_painter(LayerId::background());
+    let rect = Rect::from_two_pos(Pos2::new(start.x, start.y), Pos2::new(end.x, end.y));
+    painter.rect(
+        rect,
+        CornerRadius::ZERO,
+        Color32::from_rgba_unmultiplied(80, 140, 255, 30),
+        Stroke::new(1.0, Color32::from_rgb(80, 140, 255)),
+        StrokeKind::Inside,
+    );
+    Ok(())
+}
+
+/// 实体及其所有子实体的包围盒
+fn collect_bounds(
+    entity: Entity,
+    cad_query: &Query<(&CadEntity, CadGeometryQuery, Option<&Children>)>,
+    tolerance: &CurveTolerance,
+    boxes: &mut Vec<(Vec3, Vec3)>,
+) {
+    let Ok((_, geometry, children)) = cad_query.get(entity) else {
+        return;
+    };
+    boxes.extend(geometry_bounds(&geometry, tolerance));
+    for &child in children.into_iter().flatten() {
+        collect_bounds(child, cad_query, tolerance, boxes);
+    }
+}
+
+/// 合并多个包围盒
+fn bounds(boxes: impl IntoIterator<Item = (Vec3, Vec3)>) -> Option<(Vec3, Vec3)> {
+    boxes
+        .into_iter()
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)))
+}
+
+/// 几何组件的包围盒，曲线按离散后的折线计算
+fn geometry_bounds(
+    geometry: &CadGeometryQueryItem,
+    tolerance: &CurveTolerance,
+) -> Option<(Vec3, Vec3)> {
+    bounds(
+        geometry
+            .outline(tolerance)
+            .into_iter()
+            .flatten()
+            .map(|p| (p, p)),
+    )
+}
@@ -17,6 +17,8 @@ fn main() {
                 resolution: (1280, 720).into(),
                 ..default()
             }),
+            // 关闭窗口前要检查未保存的更改，见 in_project::unsaved_guard
+            close_when_requested: false,
             ..default()
         }))
         .init_state::<state::AppState>()